@@ -0,0 +1,252 @@
+use num_bigint::BigUint;
+use zeroize::Zeroizing;
+use crate::Error;
+
+
+/// Étiquette DER d'un entier.
+pub const TAG_INTEGER: u8 = 0x02;
+/// Étiquette DER d'une chaîne de bits.
+pub const TAG_BIT_STRING: u8 = 0x03;
+/// Étiquette DER d'une chaîne d'octets.
+pub const TAG_OCTET_STRING: u8 = 0x04;
+/// Étiquette DER de la valeur nulle.
+pub const TAG_NULL: u8 = 0x05;
+/// Étiquette DER d'un identifiant d'objet (OID).
+pub const TAG_OID: u8 = 0x06;
+/// Étiquette DER d'une séquence.
+pub const TAG_SEQUENCE: u8 = 0x30;
+/// Étiquette DER d'un élément explicite de contexte [0], les suivants ([1], [2]...) s'obtenant en ajoutant leur numéro.
+pub const TAG_CONTEXT: u8 = 0xa0;
+
+
+/// Encode un élément DER complet (étiquette, longueur, contenu).
+pub fn encode(tag: u8, content: &[u8]) -> Vec<u8>
+{
+    // La capacité est réservée d'emblée afin qu'aucune réallocation ne laisse de copie du contenu (potentiellement secret) en mémoire
+    let mut buf = Vec::with_capacity(content.len() + 2 + std::mem::size_of::<usize>());
+    buf.push(tag);
+    let len = content.len();
+
+    if len < 0x80
+    {
+        buf.push(len as u8);
+    }
+    else
+    {
+        // Forme longue : le premier octet indique le nombre d'octets de la longueur
+        let lbytes: Vec<u8> = len.to_be_bytes().iter().copied().skip_while(| &b | b == 0).collect();
+        buf.push(0x80 | lbytes.len() as u8);
+        buf.extend(lbytes);
+    }
+    buf.extend_from_slice(content);
+
+    buf
+}
+
+/// Encode un grand entier positif. La copie intermédiaire des octets de l'entier est effacée.
+pub fn integer(num: &BigUint) -> Vec<u8>
+{
+    let raw = Zeroizing::new(num.to_bytes_be());
+    let mut bytes = Zeroizing::new(Vec::with_capacity(raw.len() + 1));
+    // Le bit de poids fort indique le signe en DER, il faut donc ajouter un octet nul si celui-ci est à 1
+    if raw[0] & 0x80 != 0
+    {
+        bytes.push(0);
+    }
+    bytes.extend_from_slice(&raw);
+
+    encode(TAG_INTEGER, &bytes)
+}
+
+/// Encode une chaîne d'octets.
+pub fn octets(bytes: &[u8]) -> Vec<u8>
+{
+    encode(TAG_OCTET_STRING, bytes)
+}
+
+/// Encode une chaîne de bits dont la longueur est un multiple de 8 (aucun bit inutilisé).
+pub fn bit_string(bytes: &[u8]) -> Vec<u8>
+{
+    encode(TAG_BIT_STRING, &[&[0u8], bytes].concat())
+}
+
+/// Encode un élément explicite de contexte [n] depuis son contenu déjà encodé.
+pub fn explicit(n: u8, content: &[u8]) -> Vec<u8>
+{
+    encode(TAG_CONTEXT + n, content)
+}
+
+/// Encode la valeur nulle.
+pub fn null() -> Vec<u8>
+{
+    encode(TAG_NULL, &[])
+}
+
+/// Encode un identifiant d'objet depuis ses arcs.
+pub fn oid(arcs: &[u64]) -> Vec<u8>
+{
+    let mut content = vec![(arcs[0] * 40 + arcs[1]) as u8];
+
+    for &arc in &arcs[2..]
+    {
+        // Chaque arc est encodé en base 128, le bit de poids fort indiquant qu'un autre octet suit
+        let mut chunk = vec![(arc & 0x7f) as u8];
+        let mut rest = arc >> 7;
+        while rest > 0
+        {
+            chunk.push(0x80 | (rest & 0x7f) as u8);
+            rest >>= 7;
+        }
+        chunk.reverse();
+        content.extend(chunk);
+    }
+
+    encode(TAG_OID, &content)
+}
+
+/// Encode une séquence depuis ses éléments déjà encodés. La concaténation intermédiaire est effacée.
+pub fn sequence(items: &[Vec<u8>]) -> Vec<u8>
+{
+    encode(TAG_SEQUENCE, &Zeroizing::new(items.concat()))
+}
+
+
+/// Lecteur DER séquentiel : chaque méthode `read_xx` consomme l'élément suivant en vérifiant son étiquette.
+pub struct Reader<'a>
+{
+    data: &'a [u8],
+    pos: usize
+}
+
+impl<'a> Reader<'a>
+{
+    /// Construit un lecteur sur des données DER.
+    pub fn from(data: &'a [u8]) -> Self
+    {
+        Reader
+        {
+            data,
+            pos: 0
+        }
+    }
+
+    /// Indique si toutes les données ont été consommées.
+    pub fn is_empty(&self) -> bool
+    {
+        self.pos >= self.data.len()
+    }
+
+    /// Retourne l'étiquette de l'élément suivant sans le consommer.
+    pub fn peek_tag(&self) -> Option<u8>
+    {
+        self.data.get(self.pos).copied()
+    }
+
+    /// Lit l'élément suivant et retourne son contenu, à condition que son étiquette soit `tag`.
+    pub fn read(&mut self, tag: u8) -> Result<&'a [u8], Error>
+    {
+        if self.peek_tag() != Some(tag)
+        {
+            return Err(Error::Parse(String::from("Reader::read : étiquette DER inattendue."), None));
+        }
+
+        let first = *self.data.get(self.pos + 1).ok_or_else(Self::truncated)?;
+        self.pos += 2;
+        let len =
+            if first < 0x80
+            {
+                first as usize
+            }
+            else
+            {
+                let nbytes = (first & 0x7f) as usize;
+                if nbytes == 0 || nbytes > std::mem::size_of::<usize>()
+                {
+                    return Err(Error::Parse(String::from("Reader::read : longueur DER invalide."), None));
+                }
+                let end = self.pos.checked_add(nbytes).ok_or_else(Self::truncated)?;
+                let lbytes = self.data.get(self.pos..end).ok_or_else(Self::truncated)?;
+                self.pos += nbytes;
+                lbytes.iter().fold(0usize, | acc, &b | (acc << 8) | b as usize)
+            };
+
+        let end = self.pos.checked_add(len).ok_or_else(Self::truncated)?;
+        let content = self.data.get(self.pos..end).ok_or_else(Self::truncated)?;
+        self.pos += len;
+
+        Ok(content)
+    }
+
+    /// Lit un grand entier positif.
+    pub fn read_integer(&mut self) -> Result<BigUint, Error>
+    {
+        let content = self.read(TAG_INTEGER)?;
+        if content.is_empty() || content[0] & 0x80 != 0
+        {
+            return Err(Error::Parse(String::from("Reader::read_integer : entier DER vide ou négatif."), None));
+        }
+
+        Ok(BigUint::from_bytes_be(content))
+    }
+
+    /// Lit une chaîne d'octets.
+    pub fn read_octets(&mut self) -> Result<&'a [u8], Error>
+    {
+        self.read(TAG_OCTET_STRING)
+    }
+
+    /// Lit une chaîne de bits dont la longueur est un multiple de 8 et retourne ses octets.
+    pub fn read_bit_string(&mut self) -> Result<&'a [u8], Error>
+    {
+        match self.read(TAG_BIT_STRING)?.split_first()
+        {
+            Some((0, bytes)) => Ok(bytes),
+            _ => Err(Error::Parse(String::from("Reader::read_bit_string : chaîne de bits vide ou incomplète."), None))
+        }
+    }
+
+    /// Lit un élément explicite de contexte [n] et retourne un lecteur sur son contenu.
+    pub fn read_explicit(&mut self, n: u8) -> Result<Reader<'a>, Error>
+    {
+        Ok(Reader::from(self.read(TAG_CONTEXT + n)?))
+    }
+
+    /// Lit la valeur nulle.
+    pub fn read_null(&mut self) -> Result<(), Error>
+    {
+        self.read(TAG_NULL)?;
+        Ok(())
+    }
+
+    /// Lit un identifiant d'objet et retourne ses arcs.
+    pub fn read_oid(&mut self) -> Result<Vec<u64>, Error>
+    {
+        let content = self.read(TAG_OID)?;
+        let (first, rest) = content.split_first().ok_or_else(Self::truncated)?;
+        let mut arcs = vec![(first / 40) as u64, (first % 40) as u64];
+        let mut arc = 0u64;
+
+        for &b in rest
+        {
+            arc = (arc << 7) | (b & 0x7f) as u64;
+            if b & 0x80 == 0
+            {
+                arcs.push(arc);
+                arc = 0;
+            }
+        }
+
+        Ok(arcs)
+    }
+
+    /// Lit une séquence et retourne un lecteur sur son contenu.
+    pub fn read_sequence(&mut self) -> Result<Reader<'a>, Error>
+    {
+        Ok(Reader::from(self.read(TAG_SEQUENCE)?))
+    }
+
+    fn truncated() -> Error
+    {
+        Error::Parse(String::from("Reader : données DER tronquées."), None)
+    }
+}
@@ -0,0 +1,312 @@
+use std::fmt::Display;
+use num_bigint::{BigInt, BigUint};
+use num_integer::Integer;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use crate::{Error, audit, engines::{PublicKey, RsaKey}, maths};
+
+
+/// Nombre maximal d'itérations par défaut du rho de Pollard, pour chaque polynôme essayé (voir `RHO_TRIES`).
+/// Suffit à factoriser un module dont le plus petit facteur fait une quarantaine de bits.
+pub const RHO_DEF_ITERATIONS: u64 = 1 << 20;
+/// Borne de friabilité par défaut de l'algorithme p - 1 de Pollard.
+pub const PM1_DEF_BOUND: u32 = 1 << 20;
+/// Nombre d'itérations par défaut de la factorisation de Fermat.
+pub const FERMAT_DEF_ROUNDS: u32 = 1 << 20;
+/// Nombre de polynômes x² + c essayés par le rho de Pollard avant d'abandonner.
+const RHO_TRIES: u32 = 3;
+/// Nombre de pas du rho de Pollard dont les différences sont multipliées avant de calculer un PGCD.
+const RHO_BATCH: u64 = 128;
+/// Nombre de petits nombres premiers traités par l'algorithme p - 1 de Pollard avant de calculer un PGCD.
+const PM1_BATCH: usize = 64;
+
+
+/// Attaque de démonstration retrouvant la clé privée d'une clé publique RSA faible, voir `Attack::run`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Attack
+{
+    /// Rho de Pollard, efficace lorsque le plus petit facteur premier est petit (module trop court).
+    PollardRho,
+    /// Algorithme p - 1 de Pollard, efficace lorsque p - 1 n'a que de petits facteurs premiers.
+    PollardPm1,
+    /// Factorisation de Fermat, efficace lorsque les deux facteurs premiers sont proches.
+    Fermat,
+    /// Attaque de Wiener par fractions continues, efficace lorsque l'exposant privé est petit.
+    Wiener
+}
+
+impl Attack
+{
+    /// Toutes les attaques, dans l'ordre où `sdpe` les essaie.
+    pub fn all() -> [Attack; 4]
+    {
+        [Attack::PollardRho, Attack::PollardPm1, Attack::Fermat, Attack::Wiener]
+    }
+
+    /// Tente l'attaque sur une clé publique avec ses paramètres par défaut. Retourne la clé principale retrouvée, ou `None` si l'attaque échoue.
+    /// Retourne `Error::InvalidKey` si le module ou l'exposant de la clé est invalide.
+    pub fn run(&self, key: &PublicKey) -> Result<Option<RsaKey>, Error>
+    {
+        match self
+        {
+            Self::PollardRho => pollard_rho(key, RHO_DEF_ITERATIONS),
+            Self::PollardPm1 => pollard_pm1(key, PM1_DEF_BOUND),
+            Self::Fermat => fermat(key, FERMAT_DEF_ROUNDS),
+            Self::Wiener => wiener(key)
+        }
+    }
+}
+
+impl Display for Attack
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        match self
+        {
+            Self::PollardRho => write!(f, "rho de Pollard"),
+            Self::PollardPm1 => write!(f, "p - 1 de Pollard"),
+            Self::Fermat => write!(f, "factorisation de Fermat"),
+            Self::Wiener => write!(f, "attaque de Wiener")
+        }
+    }
+}
+
+
+/// Vérifie le module et l'exposant d'une clé publique attaquée, et retourne le module.
+fn modulus(key: &PublicKey) -> Result<&BigUint, Error>
+{
+    let (n, e) = (key.0.value(), key.1.value());
+    if n <= &BigUint::from(3u8) || e.is_zero()
+    {
+        return Err(Error::InvalidKey(String::from("attaque : le module doit être supérieur à 3 et l'exposant non nul.")));
+    }
+
+    Ok(n)
+}
+
+/// Valeur absolue de la différence de deux entiers.
+fn abs_diff(a: &BigUint, b: &BigUint) -> BigUint
+{
+    if a > b { a - b } else { b - a }
+}
+
+/// Rho de Pollard : itère x -> x² + c modulo n avec deux vitesses (Floyd) jusqu'à ce que |x - y| partage un facteur avec n,
+/// ce qui demande de l'ordre de sqrt(p) itérations pour le plus petit facteur premier p. Chacun des `RHO_TRIES` polynômes est itéré au plus `max_iter` fois.
+/// Retourne la clé principale retrouvée, ou `None` si aucun facteur n'est trouvé. Retourne `Error::InvalidKey` si la clé est invalide.
+pub fn pollard_rho(key: &PublicKey, max_iter: u64) -> Result<Option<RsaKey>, Error>
+{
+    let n = modulus(key)?;
+    if n.is_even()
+    {
+        return Ok(audit::key_from_factor(key, BigUint::from(2u8)));
+    }
+
+    for c in 1..=RHO_TRIES
+    {
+        let f = | x: &BigUint | (x * x + c) % n;
+        let (mut x, mut y) = (BigUint::from(2u8), BigUint::from(2u8));
+        let mut i = 0;
+        while i < max_iter
+        {
+            // Les différences d'un lot sont multipliées modulo n, pour ne calculer qu'un PGCD par lot
+            let (sx, sy) = (x.clone(), y.clone());
+            let steps = RHO_BATCH.min(max_iter - i);
+            let mut q = BigUint::one();
+            for _ in 0..steps
+            {
+                x = f(&x);
+                y = f(&f(&y));
+                q = q * abs_diff(&x, &y) % n;
+            }
+            i += steps;
+
+            let mut g = q.gcd(n);
+            if &g == n
+            {
+                // Le lot a dépassé le facteur, ou les deux suites se sont rejointes : il est rejoué pas à pas
+                (x, y) = (sx, sy);
+                g = loop
+                {
+                    x = f(&x);
+                    y = f(&f(&y));
+                    let g = abs_diff(&x, &y).gcd(n);
+                    if !g.is_one()
+                    {
+                        break g;
+                    }
+                };
+            }
+            if &g != n && !g.is_one()
+            {
+                return Ok(audit::key_from_factor(key, g));
+            }
+            if &g == n
+            {
+                // Cycle sans facteur isolé : polynôme suivant
+                break;
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Algorithme p - 1 de Pollard : calcule a = 2^M modulo n, où M est le produit des puissances des nombres premiers inférieurs à `bound`.
+/// Si p - 1 n'a que des facteurs premiers (et puissances) inférieurs à `bound`, p divise a - 1, retrouvé par PGCD avec n.
+/// Retourne la clé principale retrouvée, ou `None` si aucun facteur n'est trouvé. Retourne `Error::InvalidKey` si la clé est invalide.
+pub fn pollard_pm1(key: &PublicKey, bound: u32) -> Result<Option<RsaKey>, Error>
+{
+    let n = modulus(key)?;
+    if n.is_even()
+    {
+        return Ok(audit::key_from_factor(key, BigUint::from(2u8)));
+    }
+
+    // Plus grande puissance de r ne dépassant pas la borne
+    let power = | r: u32 |
+    {
+        let mut pk = u64::from(r);
+        while pk * u64::from(r) <= u64::from(bound)
+        {
+            pk *= u64::from(r);
+        }
+        BigUint::from(pk)
+    };
+    let mut a = BigUint::from(2u8);
+    for chunk in maths::small_primes(bound.saturating_add(1)).chunks(PM1_BATCH)
+    {
+        let saved = a.clone();
+        chunk.iter().for_each(| &r | a = a.modpow(&power(r), n));
+
+        let g = (&a + n - 1u8).gcd(n);
+        if &g == n
+        {
+            // p - 1 et q - 1 sont tous deux friables dans ce lot : il est rejoué nombre premier par nombre premier pour les séparer
+            a = saved;
+            for &r in chunk
+            {
+                a = a.modpow(&power(r), n);
+                let g = (&a + n - 1u8).gcd(n);
+                if &g == n
+                {
+                    return Ok(None);
+                }
+                if !g.is_one()
+                {
+                    return Ok(audit::key_from_factor(key, g));
+                }
+            }
+            return Ok(None);
+        }
+        if !g.is_one()
+        {
+            return Ok(audit::key_from_factor(key, g));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Factorisation de Fermat (voir `audit::audit`) : aboutit lorsque |p - q| est petit devant n^(1/4), et abandonne après `rounds` itérations.
+/// Retourne la clé principale retrouvée, ou `None` si aucun facteur n'est trouvé. Retourne `Error::InvalidKey` si la clé est invalide.
+pub fn fermat(key: &PublicKey, rounds: u32) -> Result<Option<RsaKey>, Error>
+{
+    let n = modulus(key)?;
+
+    Ok(audit::fermat(n, rounds).and_then(| (p, _) | audit::key_from_factor(key, p)))
+}
+
+/// Attaque de Wiener (voir `audit::audit`) : aboutit lorsque l'exposant privé est inférieur à n^(1/4) / 3.
+/// Les facteurs premiers sont ensuite retrouvés depuis les exposants. Retourne la clé principale retrouvée, ou `None` si l'attaque échoue.
+/// Retourne `Error::InvalidKey` si la clé est invalide.
+pub fn wiener(key: &PublicKey) -> Result<Option<RsaKey>, Error>
+{
+    let (n, e) = (modulus(key)?, key.1.value());
+
+    Ok(audit::wiener(n, e)
+        .and_then(| d | maths::factor_from_exponents(n, e, &d))
+        .and_then(| (p, _) | audit::key_from_factor(key, p)))
+}
+
+/// Attaque du module commun : retrouve un message chiffré sans nonce (`c1` et `c2`, voir `Engine::run_crypt`) avec deux clés publiques
+/// de même module et d'exposants premiers entre eux. Avec u * e1 + v * e2 = 1 (Bézout), m = c1^u * c2^v modulo n.
+/// Retourne `Error::InvalidParameter` si les modules diffèrent ou si les exposants ne sont pas premiers entre eux,
+/// et `Error::Decoding` si un des chiffrés n'est pas inversible modulo n.
+pub fn common_modulus(c1: &BigUint, key1: &PublicKey, c2: &BigUint, key2: &PublicKey) -> Result<BigUint, Error>
+{
+    let n = modulus(key1)?;
+    modulus(key2)?;
+    let (e1, e2) = (key1.1.value(), key2.1.value());
+    if n != key2.0.value()
+    {
+        return Err(Error::InvalidParameter(String::from("common_modulus : les deux clés doivent partager le même module.")));
+    }
+    if !e1.gcd(e2).is_one()
+    {
+        return Err(Error::InvalidParameter(String::from("common_modulus : les exposants doivent être premiers entre eux.")));
+    }
+
+    let (e1, e2) = (BigInt::from(e1.clone()), BigInt::from(e2.clone()));
+    let u = maths::euclide(&e1, &e2);
+    let v = (BigInt::one() - &u * &e1) / &e2;
+    // Un exposant négatif s'applique à l'inverse du chiffré
+    let pow = | c: &BigUint, x: &BigInt | -> Result<BigUint, Error>
+    {
+        let base = if x.is_negative()
+            {
+                maths::modinv(c, n).ok_or_else(|| Error::Decoding(String::from("common_modulus : chiffré non inversible modulo n.")))?
+            }
+            else
+            {
+                c.clone()
+            };
+        Ok(maths::fmodpow(&base, x.magnitude(), n))
+    };
+
+    Ok(pow(c1, &u)? * pow(c2, &v)? % n)
+}
+
+/// Attaque de Håstad par diffusion : un même message chiffré sans nonce (voir `Policy::legacy`) pour au moins e destinataires d'exposant commun e
+/// (typiquement 3, choisi par `expcode`) est retrouvé sans clé privée. Les chiffrés sont recombinés par le théorème des restes chinois
+/// en m^e modulo le produit des modules, qui dépasse m^e : sa racine e-ième entière (voir `maths::nth_root`) est alors le message.
+/// Retourne `Error::InvalidParameter` si les exposants diffèrent, si les chiffrés sont moins nombreux que l'exposant ou si deux modules ne sont pas premiers entre eux,
+/// et `Error::Decoding` si la racine n'est pas exacte : les messages chiffrés diffèrent, par exemple à cause d'un nonce aléatoire.
+pub fn hastad_broadcast(ciphertexts: &[(BigUint, PublicKey)]) -> Result<BigUint, Error>
+{
+    let e = match ciphertexts.first()
+    {
+        Some((_, key)) => { modulus(key)?; key.1.value() },
+        None => return Err(Error::Empty(String::from("hastad_broadcast : aucun chiffré.")))
+    };
+    let e_u32 = match e.to_u32()
+    {
+        Some(e) if ciphertexts.len() >= e as usize => e,
+        _ => return Err(Error::InvalidParameter(format!("hastad_broadcast : {} chiffrés pour un exposant {}, il en faut au moins autant que l'exposant.", ciphertexts.len(), e)))
+    };
+    // e chiffrés suffisent, m étant inférieur à chacun des modules
+    let ciphertexts = &ciphertexts[..e_u32 as usize];
+
+    let mut x = BigUint::zero();
+    let mut m = BigUint::one();
+    for (c, key) in ciphertexts
+    {
+        let n = modulus(key)?;
+        if key.1.value() != e
+        {
+            return Err(Error::InvalidParameter(String::from("hastad_broadcast : les clés doivent partager le même exposant.")));
+        }
+        // x = c mod n et x inchangé modulo le produit des modules précédents
+        let inv = maths::modinv(&(&m % n), n)
+            .ok_or_else(|| Error::InvalidParameter(String::from("hastad_broadcast : les modules doivent être premiers entre eux.")))?;
+        let t = ((c % n) + n - (&x % n)) % n * inv % n;
+        x += &m * t;
+        m *= n;
+    }
+
+    let root = maths::nth_root(&x, e_u32);
+    if root.pow(e_u32) != x
+    {
+        return Err(Error::Decoding(String::from("hastad_broadcast : la racine n'est pas exacte, les messages chiffrés diffèrent.")));
+    }
+
+    Ok(root)
+}
@@ -0,0 +1,310 @@
+use std::fmt::Display;
+use num_bigint::BigUint;
+use num_integer::Integer;
+use num_traits::{One, Zero};
+use crate::{Error, engines::{PrivateKey, PublicKey, RsaKey, RSA_MIN_MODULUS_BITS}, keys::{KeyPair, NumKey}, maths};
+
+
+/// Nombre d'itérations de la factorisation de Fermat tentée sur chaque module audité.
+/// Au delà, |p - q| est considéré comme suffisamment grand (environ n^(1/4) * sqrt(8 * AUDIT_FERMAT_ROUNDS)).
+pub const AUDIT_FERMAT_ROUNDS: u32 = 4096;
+/// Générateur du sous-groupe dont sont issus les facteurs premiers des clés vulnérables à ROCA (CVE-2017-15361).
+const ROCA_GENERATOR: u32 = 65537;
+/// Petits nombres premiers utilisés pour détecter l'empreinte ROCA : le module réduit par chacun d'eux doit appartenir au sous-groupe engendré par `ROCA_GENERATOR`.
+/// Une clé aléatoire présente cette empreinte avec une probabilité d'environ 4e-9.
+const ROCA_PRIMES: [u32; 38] = [ 3,5,7,11,13,17,19,23,29,31,37,41,43,47,53,59,61,67,71,73,79,83,89,97,101,103,107,109,113,127,131,137,139,149,151,157,163,167 ];
+
+
+/// Faiblesse détectée lors de l'audit d'une clé publique RSA.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Finding
+{
+    /// Le module est plus petit que `RSA_MIN_MODULUS_BITS` (taille en bits).
+    SmallModulus(u64),
+    /// L'exposant privé est assez petit pour être retrouvé par l'attaque de Wiener. Contient l'exposant privé retrouvé.
+    Wiener(BigUint),
+    /// Les facteurs premiers sont assez proches pour que la factorisation de Fermat aboutisse. Contient les facteurs `(p, q)`.
+    CloseFactors(BigUint, BigUint),
+    /// Le module présente l'empreinte des clés générées par la bibliothèque vulnérable à ROCA.
+    Roca,
+    /// Le module partage un facteur premier avec le module d'indice donné dans le corpus. Contient l'indice et le facteur commun.
+    SharedFactor(usize, BigUint),
+    /// Le module est identique au module d'indice donné dans le corpus.
+    DuplicateModulus(usize)
+}
+
+impl Finding
+{
+    /// Indique si la faiblesse expose la clé privée (exposant privé ou facteurs retrouvés, module partagé avec une autre clé).
+    pub fn is_critical(&self) -> bool
+    {
+        !matches!(self, Self::SmallModulus(_) | Self::Roca)
+    }
+}
+
+impl Display for Finding
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        match self
+        {
+            Self::SmallModulus(bits) => write!(f, "module trop petit ({} bits, au moins {} bits sont nécessaires)", bits, RSA_MIN_MODULUS_BITS),
+            Self::Wiener(d) => write!(f, "exposant privé trop petit, retrouvé par l'attaque de Wiener ({} bits)", d.bits()),
+            Self::CloseFactors(p, q) => write!(f, "facteurs premiers trop proches, retrouvés par la factorisation de Fermat (|p - q| de {} bits)",
+                if p > q { p - q } else { q - p }.bits()),
+            Self::Roca => write!(f, "empreinte ROCA (CVE-2017-15361) : la clé a probablement été générée par une bibliothèque vulnérable"),
+            Self::SharedFactor(i, factor) => write!(f, "facteur premier de {} bits commun avec le module n°{} du corpus", factor.bits(), i),
+            Self::DuplicateModulus(i) => write!(f, "module identique au module n°{} du corpus", i)
+        }
+    }
+}
+
+
+/// Rapport d'audit d'une clé publique RSA : taille du module et liste des faiblesses détectées.
+#[derive(Debug)]
+pub struct Report
+{
+    /// Taille du module audité, en bits.
+    pub modulus_bits: u64,
+    /// Faiblesses détectées, dans l'ordre des vérifications.
+    pub findings: Vec<Finding>
+}
+
+impl Report
+{
+    /// Indique si aucune faiblesse n'a été détectée.
+    pub fn is_clean(&self) -> bool
+    {
+        self.findings.is_empty()
+    }
+
+    /// Indique si au moins une faiblesse expose la clé privée.
+    pub fn is_critical(&self) -> bool
+    {
+        self.findings.iter().any(Finding::is_critical)
+    }
+}
+
+
+/// Audite une clé publique RSA : taille du module, attaque de Wiener, factorisation de Fermat, empreinte ROCA,
+/// et facteurs communs avec les modules des clés publiques du `corpus` (clés connues, déjà collectées...).
+/// Retourne `Error::InvalidKey` si le module ou l'exposant est nul.
+pub fn audit(key: &PublicKey, corpus: &[PublicKey]) -> Result<Report, Error>
+{
+    let (n, e) = (key.0.value(), key.1.value());
+    if n.is_zero() || e.is_zero()
+    {
+        return Err(Error::InvalidKey(String::from("audit : le module et l'exposant doivent être non nuls.")));
+    }
+
+    let mut findings = Vec::new();
+    if n.bits() < RSA_MIN_MODULUS_BITS
+    {
+        findings.push(Finding::SmallModulus(n.bits()));
+    }
+    if let Some(d) = wiener(n, e)
+    {
+        findings.push(Finding::Wiener(d));
+    }
+    if let Some((p, q)) = fermat(n, AUDIT_FERMAT_ROUNDS)
+    {
+        findings.push(Finding::CloseFactors(p, q));
+    }
+    if roca_fingerprint(n)
+    {
+        findings.push(Finding::Roca);
+    }
+    for (i, other) in corpus.iter().enumerate()
+    {
+        let m = other.0.value();
+        if m == n
+        {
+            findings.push(Finding::DuplicateModulus(i));
+            continue;
+        }
+        let g = n.gcd(m);
+        if !g.is_one()
+        {
+            findings.push(Finding::SharedFactor(i, g));
+        }
+    }
+
+    Ok(Report
+    {
+        modulus_bits: n.bits(),
+        findings
+    })
+}
+
+/// Recherche, parmi un grand nombre de clés publiques, celles dont le module partage un facteur premier avec une autre (PGCD par lots, voir `maths::batch_gcd`).
+/// Retourne l'indice de chaque clé concernée, avec la clé principale retrouvée lorsque la factorisation de son module aboutit
+/// (ce n'est pas le cas d'un module présent en double, dont aucun facteur ne peut être isolé).
+pub fn recover_shared(keys: &[PublicKey]) -> Vec<(usize, Option<RsaKey>)>
+{
+    // Les modules pairs ou inférieurs ou égaux à 1 ne sont pas des modules RSA : ils sont écartés avant le calcul, faute de quoi
+    // un seul fichier forgé (module nul) interromprait l'analyse, ou un facteur 2 commun signalerait toutes les clés paires
+    let (indices, moduli): (Vec<usize>, Vec<BigUint>) = keys.iter().enumerate()
+        .map(| (i, k) | (i, k.0.value().clone()))
+        .filter(| (_, n) | n.is_odd() && !n.is_one())
+        .unzip();
+    let mut affected = Vec::new();
+
+    for (j, g) in maths::batch_gcd(&moduli).into_iter().enumerate()
+    {
+        let n = &moduli[j];
+        if g.is_one()
+        {
+            continue;
+        }
+        // Si tous les facteurs du module sont partagés, le PGCD par lots retourne le module lui-même : on compare alors avec chaque autre module
+        let factor =
+            if &g == n
+            {
+                moduli.iter().enumerate()
+                    .filter(| &(k, _) | k != j)
+                    .map(| (_, m) | n.gcd(m))
+                    .find(| f | !f.is_one() && f != n)
+            }
+            else
+            {
+                Some(g)
+            };
+
+        affected.push((indices[j], factor.and_then(| p | key_from_factor(&keys[indices[j]], p))));
+    }
+
+    affected
+}
+
+/// Reconstruit la clé principale RSA d'une clé publique depuis un facteur premier `p` de son module.
+pub(crate) fn key_from_factor(key: &PublicKey, p: BigUint) -> Option<RsaKey>
+{
+    let (n, e) = (key.0.value(), key.1.value());
+    let p = NumKey::from(p);
+    let q = NumKey::from(n / p.value());
+    if &(p.value() * q.value()) != n || p.value() == q.value()
+    {
+        return None;
+    }
+
+    let phi = NumKey::from((p.value() - 1u8) * (q.value() - 1u8));
+    let d = maths::modinv(e, phi.value())?;
+
+    Some(KeyPair::from(
+        KeyPair::from(NumKey::from(n.clone()), NumKey::from(e.clone())),
+        PrivateKey::from(NumKey::from(n.clone()), NumKey::from(d))
+    ))
+}
+
+/// Attaque de Wiener : parcourt les réduites du développement en fraction continue de e / n à la recherche de k / d,
+/// ce qui aboutit lorsque d < n^(1/4) / 3. Retourne l'exposant privé s'il est retrouvé.
+pub(crate) fn wiener(n: &BigUint, e: &BigUint) -> Option<BigUint>
+{
+    let (mut num, mut den) = (e.clone(), n.clone());
+    // Réduites successives h / k de e / n, qui approchent k / d
+    let (mut h0, mut h1) = (BigUint::zero(), BigUint::one());
+    let (mut k0, mut k1) = (BigUint::one(), BigUint::zero());
+
+    while !den.is_zero()
+    {
+        let (a, r) = num.div_rem(&den);
+        (num, den) = (den, r);
+        (h0, h1) = (h1.clone(), &a * &h1 + &h0);
+        (k0, k1) = (k1.clone(), &a * &k1 + &k0);
+
+        // h1 / k1 est un candidat pour k / d
+        let (k, d) = (&h1, &k1);
+        if k.is_zero() || d.is_even()
+        {
+            continue;
+        }
+        let ed1 = e * d - 1u8;
+        if !(&ed1 % k).is_zero()
+        {
+            continue;
+        }
+        // φ(n) = (e * d - 1) / k, puis p et q sont les racines de x² - (n - φ(n) + 1) * x + n
+        let phi = ed1 / k;
+        if &phi >= n
+        {
+            continue;
+        }
+        let s = n - phi + 1u8;
+        let s2 = &s * &s;
+        let n4 = n * 4u8;
+        if s2 < n4
+        {
+            continue;
+        }
+        let disc = s2 - n4;
+        let root = disc.sqrt();
+        if &root * &root == disc
+        {
+            return Some(d.clone());
+        }
+    }
+
+    None
+}
+
+/// Factorisation de Fermat : cherche a tel que a² - n soit un carré b², alors n = (a - b)(a + b).
+/// Aboutit rapidement lorsque |p - q| est petit ; abandonne après `rounds` itérations.
+pub(crate) fn fermat(n: &BigUint, rounds: u32) -> Option<(BigUint, BigUint)>
+{
+    if n.is_even()
+    {
+        return None;
+    }
+
+    let mut a = n.sqrt();
+    if &(&a * &a) < n
+    {
+        a += 1u8;
+    }
+    let mut b2 = &a * &a - n;
+
+    for _ in 0..rounds
+    {
+        // Un carré est forcément un résidu quadratique modulo 16 : évite la plupart des calculs de racine
+        if matches!((&b2 % 16u8).to_u32_digits().first().copied().unwrap_or(0), 0 | 1 | 4 | 9)
+        {
+            let b = b2.sqrt();
+            if &b * &b == b2
+            {
+                let p = &a - &b;
+                if !p.is_one()
+                {
+                    return Some((p, &a + &b));
+                }
+            }
+        }
+        // (a + 1)² - n = a² - n + 2a + 1
+        b2 += &a * 2u8 + 1u8;
+        a += 1u8;
+    }
+
+    None
+}
+
+/// Détecte l'empreinte ROCA : pour chaque petit nombre premier r, n mod r doit appartenir au sous-groupe de (Z/rZ)* engendré par 65537.
+fn roca_fingerprint(n: &BigUint) -> bool
+{
+    ROCA_PRIMES.iter().all(| &r |
+    {
+        let target = (n % r).to_u32_digits().first().copied().unwrap_or(0);
+        let g = ROCA_GENERATOR % r;
+        let mut x = 1u32;
+        loop
+        {
+            if x == target
+            {
+                return true;
+            }
+            x = x * g % r;
+            if x == 1
+            {
+                return false;
+            }
+        }
+    })
+}
@@ -0,0 +1,630 @@
+use crate::{Error, engines::Engine, keys::*, policy::{Padding, Policy}, rng::RngHandle};
+use num_bigint::BigUint;
+use num_integer::Integer;
+use num_traits::ToPrimitive;
+use rand::Rng;
+
+
+/// Taille de l'alphabet latin sur lequel opèrent les chiffrements classiques.
+pub const ALPHABET_SIZE: u32 = 26;
+/// Longueur par défaut des clés Vigenère générées, en lettres.
+pub const VIGENERE_DEF_KEYLEN: u64 = 8;
+/// Taille par défaut des matrices des clés Hill générées.
+pub const HILL_DEF_SIZE: u64 = 3;
+/// Taille maximale des matrices des clés Hill.
+pub const HILL_MAX_SIZE: usize = 8;
+
+
+/// Applique `f` aux lettres latines (non accentuées) d'un nombre représentant un texte UTF-8, dans l'ordre du texte.
+/// `f` reçoit et retourne l'indice de la lettre dans l'alphabet (0 pour A) ; la casse est conservée et les autres caractères sont inchangés,
+/// si bien que le nombre obtenu représente toujours un texte UTF-8 de même longueur.
+fn map_letters(num: &mut BigUint, mut f: impl FnMut(u32) -> u32)
+{
+    let mut bytes = num.to_bytes_be();
+    for b in bytes.iter_mut()
+    {
+        let base = match b { b'A'..=b'Z' => b'A', b'a'..=b'z' => b'a', _ => continue };
+        *b = base + (f(u32::from(*b - base)) % ALPHABET_SIZE) as u8;
+    }
+    *num = BigUint::from_bytes_be(&bytes);
+}
+
+/// Indices dans l'alphabet des lettres latines d'un texte, dans l'ordre, sans distinction de casse. Les autres caractères sont ignorés.
+pub fn letters(text: &str) -> Vec<u32>
+{
+    text.bytes().filter(u8::is_ascii_alphabetic).map(| b | u32::from(b.to_ascii_uppercase() - b'A')).collect()
+}
+
+/// Inverse de `a` modulo la taille de l'alphabet, s'il existe.
+pub fn alphabet_inv(a: u32) -> Option<u32>
+{
+    (1..ALPHABET_SIZE).find(| x | (a * x) % ALPHABET_SIZE == 1)
+}
+
+
+/// Chiffrement par décalage (césar au sens classique) : chaque lettre est décalée de k positions dans l'alphabet.
+/// Contrairement à `Cesar`, qui ajoute un grand nombre à la valeur numérique du message, le chiffré reste un texte lisible.
+pub struct Shift
+{
+    rng: RngHandle
+}
+
+impl Shift
+{
+    /// Construit un moteur de décalage utilisant la source d'aléa du système.
+    pub fn new() -> Shift
+    {
+        Shift::with_rng(RngHandle::os())
+    }
+
+    /// Construit un moteur de décalage utilisant la source d'aléa donnée.
+    pub fn with_rng(rng: RngHandle) -> Shift
+    {
+        Shift
+        {
+            rng
+        }
+    }
+
+    /// Décalage d'une clé, réduit modulo la taille de l'alphabet.
+    fn shift(key: &NumKey) -> u32
+    {
+        (key.value() % ALPHABET_SIZE).to_u32().unwrap_or(0)
+    }
+}
+
+impl Default for Shift
+{
+    fn default() -> Self
+    {
+        Shift::new()
+    }
+}
+
+impl Engine for Shift
+{
+    type EncryptionKey = NumKey;
+    type DecryptionKey = NumKey;
+    type MainKey = NumKey;
+
+    /// Les paramètres sont ignorés : le décalage est tiré dans [1, 25].
+    fn try_generate(&self, _: u64, _: u8) -> Result<Self::MainKey, Error>
+    {
+        Ok(NumKey::from(BigUint::from(self.rng().gen_range(1..ALPHABET_SIZE))))
+    }
+
+    fn try_gen_def(&self) -> Result<Self::MainKey, Error>
+    {
+        self.try_generate(0, 0)
+    }
+
+    fn try_run_crypt(&self, num: &mut BigUint, key: &Self::EncryptionKey) -> Result<(), Error>
+    {
+        let k = Shift::shift(key);
+        map_letters(num, | x | x + k);
+        Ok(())
+    }
+
+    fn try_run_decrypt(&self, num: &mut BigUint, key: &Self::DecryptionKey) -> Result<(), Error>
+    {
+        let k = Shift::shift(key);
+        map_letters(num, | x | x + ALPHABET_SIZE - k);
+        Ok(())
+    }
+
+    fn rng(&self) -> RngHandle
+    {
+        self.rng.clone()
+    }
+
+    /// Chiffrement classique sans sécurité moderne : les paramètres hérités sont autorisés.
+    fn policy(&self) -> Policy
+    {
+        Policy::legacy()
+    }
+
+    /// Le chiffrement étant déterministe et lettre à lettre, aucun nonce n'est ajouté : le chiffré reste un texte.
+    fn pad(&self, _num: &mut BigUint, _padsize: u32) {}
+
+    /// Aucun nonce n'ayant été ajouté, rien n'est retiré.
+    fn unpad(&self, _num: &mut BigUint, _padsize: u32) {}
+
+    fn padding(&self, _padsize: u32) -> Padding
+    {
+        Padding::Raw
+    }
+}
+
+
+/// Clé Vigenère : un mot, dont chaque lettre donne le décalage à appliquer à une lettre du texte, cycliquement.
+/// Sérialisation textuelle : le mot en majuscules.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VigenereKey(pub Vec<u32>);
+
+impl Key for VigenereKey
+{
+    const PARTS: Option<usize> = Some(1);
+
+    fn from_str(val: String) -> Result<Self, Error>
+    {
+        let val = val.trim();
+        if val.is_empty() || !val.bytes().all(| b | b.is_ascii_alphabetic())
+        {
+            return Err(Error::Parse(String::from("VigenereKey::from_str : la clé doit être un mot non vide, composé de lettres non accentuées."), None));
+        }
+
+        Ok(VigenereKey(letters(val)))
+    }
+
+    fn serialize_str(&self) -> String
+    {
+        self.0.iter().map(| &x | char::from(b'A' + (x % ALPHABET_SIZE) as u8)).collect()
+    }
+}
+
+/// Chiffrement de Vigenère : la i-ème lettre du texte est décalée de la (i mod longueur)-ième lettre de la clé. Les autres caractères ne consomment pas de lettre de clé.
+pub struct Vigenere
+{
+    rng: RngHandle
+}
+
+impl Vigenere
+{
+    /// Construit un moteur Vigenère utilisant la source d'aléa du système.
+    pub fn new() -> Vigenere
+    {
+        Vigenere::with_rng(RngHandle::os())
+    }
+
+    /// Construit un moteur Vigenère utilisant la source d'aléa donnée.
+    pub fn with_rng(rng: RngHandle) -> Vigenere
+    {
+        Vigenere
+        {
+            rng
+        }
+    }
+
+    /// Applique la clé, dans un sens ou dans l'autre, aux lettres d'un nombre représentant un texte.
+    fn apply(num: &mut BigUint, key: &VigenereKey, decrypt: bool) -> Result<(), Error>
+    {
+        if key.0.is_empty()
+        {
+            return Err(Error::InvalidKey(String::from("Vigenere : clé vide.")));
+        }
+        let mut i = 0;
+        map_letters(num, | x |
+        {
+            let k = key.0[i % key.0.len()] % ALPHABET_SIZE;
+            i += 1;
+            if decrypt { x + ALPHABET_SIZE - k } else { x + k }
+        });
+        Ok(())
+    }
+}
+
+impl Default for Vigenere
+{
+    fn default() -> Self
+    {
+        Vigenere::new()
+    }
+}
+
+impl Engine for Vigenere
+{
+    type EncryptionKey = VigenereKey;
+    type DecryptionKey = VigenereKey;
+    type MainKey = VigenereKey;
+
+    /// `sz_b` est la longueur de la clé en lettres.
+    fn try_generate(&self, sz_b: u64, _: u8) -> Result<Self::MainKey, Error>
+    {
+        if sz_b == 0
+        {
+            return Err(Error::InvalidParameter(String::from("Vigenere.generate : la longueur de clé doit être non nulle.")));
+        }
+        let mut rng = self.rng();
+        Ok(VigenereKey((0..sz_b).map(| _ | rng.gen_range(0..ALPHABET_SIZE)).collect()))
+    }
+
+    fn try_gen_def(&self) -> Result<Self::MainKey, Error>
+    {
+        self.try_generate(VIGENERE_DEF_KEYLEN, 0)
+    }
+
+    fn try_run_crypt(&self, num: &mut BigUint, key: &Self::EncryptionKey) -> Result<(), Error>
+    {
+        Vigenere::apply(num, key, false)
+    }
+
+    fn try_run_decrypt(&self, num: &mut BigUint, key: &Self::DecryptionKey) -> Result<(), Error>
+    {
+        Vigenere::apply(num, key, true)
+    }
+
+    fn rng(&self) -> RngHandle
+    {
+        self.rng.clone()
+    }
+
+    /// Chiffrement classique sans sécurité moderne : les paramètres hérités sont autorisés.
+    fn policy(&self) -> Policy
+    {
+        Policy::legacy()
+    }
+
+    /// Le chiffrement étant déterministe et lettre à lettre, aucun nonce n'est ajouté : le chiffré reste un texte.
+    fn pad(&self, _num: &mut BigUint, _padsize: u32) {}
+
+    /// Aucun nonce n'ayant été ajouté, rien n'est retiré.
+    fn unpad(&self, _num: &mut BigUint, _padsize: u32) {}
+
+    fn padding(&self, _padsize: u32) -> Padding
+    {
+        Padding::Raw
+    }
+}
+
+
+/// Clé du chiffrement affine (a, b) : a doit être inversible modulo la taille de l'alphabet. Sérialisation textuelle : `a::b`, dans la même base que les autres clés.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AffineKey(pub u32, pub u32);
+
+impl AffineKey
+{
+    /// Construit une clé affine, réduite modulo la taille de l'alphabet. Retourne `Error::InvalidKey` si a n'est pas inversible.
+    pub fn new(a: u32, b: u32) -> Result<AffineKey, Error>
+    {
+        let (a, b) = (a % ALPHABET_SIZE, b % ALPHABET_SIZE);
+        if alphabet_inv(a).is_none()
+        {
+            return Err(Error::InvalidKey(format!("AffineKey : {} n'est pas inversible modulo {}.", a, ALPHABET_SIZE)));
+        }
+
+        Ok(AffineKey(a, b))
+    }
+}
+
+impl Key for AffineKey
+{
+    const PARTS: Option<usize> = Some(2);
+
+    fn from_str(val: String) -> Result<Self, Error>
+    {
+        let pair = KeyPair::<NumKey, NumKey>::from_str(val)?;
+        let (a, b) = (pair.0.value() % ALPHABET_SIZE, pair.1.value() % ALPHABET_SIZE);
+        AffineKey::new(a.to_u32().unwrap_or(0), b.to_u32().unwrap_or(0))
+    }
+
+    fn serialize_str(&self) -> String
+    {
+        KeyPair::from(NumKey::from(BigUint::from(self.0)), NumKey::from(BigUint::from(self.1))).serialize_str()
+    }
+}
+
+/// Chiffrement affine : chaque lettre x devient a * x + b modulo la taille de l'alphabet.
+pub struct Affine
+{
+    rng: RngHandle
+}
+
+impl Affine
+{
+    /// Construit un moteur affine utilisant la source d'aléa du système.
+    pub fn new() -> Affine
+    {
+        Affine::with_rng(RngHandle::os())
+    }
+
+    /// Construit un moteur affine utilisant la source d'aléa donnée.
+    pub fn with_rng(rng: RngHandle) -> Affine
+    {
+        Affine
+        {
+            rng
+        }
+    }
+}
+
+impl Default for Affine
+{
+    fn default() -> Self
+    {
+        Affine::new()
+    }
+}
+
+impl Engine for Affine
+{
+    type EncryptionKey = AffineKey;
+    type DecryptionKey = AffineKey;
+    type MainKey = AffineKey;
+
+    /// Les paramètres sont ignorés : a est tiré parmi les inversibles différents de 1, b dans l'alphabet.
+    fn try_generate(&self, _: u64, _: u8) -> Result<Self::MainKey, Error>
+    {
+        let mut rng = self.rng();
+        loop
+        {
+            let a = rng.gen_range(3..ALPHABET_SIZE);
+            if a.gcd(&ALPHABET_SIZE) == 1
+            {
+                return AffineKey::new(a, rng.gen_range(0..ALPHABET_SIZE));
+            }
+        }
+    }
+
+    fn try_gen_def(&self) -> Result<Self::MainKey, Error>
+    {
+        self.try_generate(0, 0)
+    }
+
+    fn try_run_crypt(&self, num: &mut BigUint, key: &Self::EncryptionKey) -> Result<(), Error>
+    {
+        let key = AffineKey::new(key.0, key.1)?;
+        map_letters(num, | x | key.0 * x + key.1);
+        Ok(())
+    }
+
+    fn try_run_decrypt(&self, num: &mut BigUint, key: &Self::DecryptionKey) -> Result<(), Error>
+    {
+        let key = AffineKey::new(key.0, key.1)?;
+        let a_inv = alphabet_inv(key.0).unwrap();
+        map_letters(num, | y | a_inv * (y + ALPHABET_SIZE - key.1));
+        Ok(())
+    }
+
+    fn rng(&self) -> RngHandle
+    {
+        self.rng.clone()
+    }
+
+    /// Chiffrement classique sans sécurité moderne : les paramètres hérités sont autorisés.
+    fn policy(&self) -> Policy
+    {
+        Policy::legacy()
+    }
+
+    /// Le chiffrement étant déterministe et lettre à lettre, aucun nonce n'est ajouté : le chiffré reste un texte.
+    fn pad(&self, _num: &mut BigUint, _padsize: u32) {}
+
+    /// Aucun nonce n'ayant été ajouté, rien n'est retiré.
+    fn unpad(&self, _num: &mut BigUint, _padsize: u32) {}
+
+    fn padding(&self, _padsize: u32) -> Padding
+    {
+        Padding::Raw
+    }
+}
+
+
+/// Clé Hill : matrice carrée de taille n, inversible modulo la taille de l'alphabet, stockée ligne par ligne.
+/// Sérialisation textuelle : `n::m00::m01::...`, dans la même base que les autres clés.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HillKey
+{
+    size: usize,
+    matrix: Vec<u32>
+}
+
+impl HillKey
+{
+    /// Construit une clé Hill depuis ses lignes, réduites modulo la taille de l'alphabet.
+    /// Retourne `Error::InvalidKey` si la matrice n'est pas carrée, trop grande ou pas inversible.
+    pub fn new(rows: &[Vec<u32>]) -> Result<HillKey, Error>
+    {
+        let size = rows.len();
+        if size == 0 || size > HILL_MAX_SIZE || rows.iter().any(| r | r.len() != size)
+        {
+            return Err(Error::InvalidKey(format!("HillKey : la matrice doit être carrée, de taille 1 à {}.", HILL_MAX_SIZE)));
+        }
+        let key = HillKey { size, matrix: rows.iter().flatten().map(| x | x % ALPHABET_SIZE).collect() };
+        key.inverse()?;
+
+        Ok(key)
+    }
+
+    /// Taille de la matrice.
+    pub fn size(&self) -> usize
+    {
+        self.size
+    }
+
+    /// Coefficient de la matrice à la ligne `i` et à la colonne `j`.
+    pub fn get(&self, i: usize, j: usize) -> u32
+    {
+        self.matrix[i * self.size + j]
+    }
+
+    /// Lignes de la matrice.
+    fn rows(&self) -> Vec<Vec<u32>>
+    {
+        self.matrix.chunks(self.size).map(<[u32]>::to_vec).collect()
+    }
+
+    /// Calcule la matrice inverse modulo la taille de l'alphabet : det(A)^-1 * adj(A), la comatrice transposée.
+    /// Retourne `Error::InvalidKey` si le déterminant n'est pas inversible.
+    pub fn inverse(&self) -> Result<HillKey, Error>
+    {
+        let (n, rows) = (self.size, self.rows());
+        let det_inv = alphabet_inv(det(&rows)).ok_or_else(|| Error::InvalidKey(format!("HillKey : matrice non inversible modulo {}.", ALPHABET_SIZE)))?;
+        // adj(A)[i][j] = (-1)^(i + j) * det(A privée de la ligne j et de la colonne i)
+        let matrix = (0..n).flat_map(| i | (0..n).map(move | j | (i, j)))
+            .map(| (i, j) |
+            {
+                let d = det(&minor(&rows, j, i));
+                let cof = if (i + j) % 2 == 0 { d } else { (ALPHABET_SIZE - d) % ALPHABET_SIZE };
+                (cof * det_inv) % ALPHABET_SIZE
+            })
+            .collect();
+
+        Ok(HillKey { size: n, matrix })
+    }
+}
+
+/// Matrice privée de la ligne `r` et de la colonne `c`.
+fn minor(rows: &[Vec<u32>], r: usize, c: usize) -> Vec<Vec<u32>>
+{
+    rows.iter().enumerate().filter(| &(i, _) | i != r)
+        .map(| (_, row) | row.iter().enumerate().filter(| &(j, _) | j != c).map(| (_, &x) | x).collect())
+        .collect()
+}
+
+/// Déterminant d'une matrice carrée modulo la taille de l'alphabet (développement selon la première ligne ; les matrices de clés restent petites).
+fn det(rows: &[Vec<u32>]) -> u32
+{
+    if rows.is_empty()
+    {
+        return 1;
+    }
+    (0..rows.len()).fold(0, | acc, j |
+    {
+        let term = (rows[0][j] * det(&minor(rows, 0, j))) % ALPHABET_SIZE;
+        if j % 2 == 0 { (acc + term) % ALPHABET_SIZE } else { (acc + ALPHABET_SIZE - term) % ALPHABET_SIZE }
+    })
+}
+
+impl Key for HillKey
+{
+    fn from_str(val: String) -> Result<Self, Error>
+    {
+        let mut parts = val.trim().split(KEY_SERIAL_DELIMITER);
+        let size = NumKey::from_str(String::from(parts.next().unwrap_or_default()))?.value().to_usize().unwrap_or(0);
+        let mut nums = Vec::new();
+        for part in parts
+        {
+            nums.push((NumKey::from_str(String::from(part))?.value() % ALPHABET_SIZE).to_u32().unwrap_or(0));
+        }
+        if size == 0 || nums.len() != size * size
+        {
+            return Err(Error::Parse(format!("HillKey::from_str : nombre de parties ({}) invalide pour une matrice de taille {}.", nums.len() + 1, size), None));
+        }
+
+        HillKey::new(&nums.chunks(size).map(<[u32]>::to_vec).collect::<Vec<_>>())
+    }
+
+    fn serialize_str(&self) -> String
+    {
+        let mut parts = vec![NumKey::from(BigUint::from(self.size)).serialize_str()];
+        parts.extend(self.matrix.iter().map(| &x | NumKey::from(BigUint::from(x)).serialize_str()));
+
+        parts.join(KEY_SERIAL_DELIMITER)
+    }
+}
+
+/// Chiffrement de Hill : les lettres du texte sont regroupées par blocs de n, et chaque bloc (vecteur colonne) est multiplié par la matrice de la clé.
+/// Un éventuel dernier bloc incomplet est laissé en clair, afin que le chiffré garde la longueur du texte.
+pub struct Hill
+{
+    rng: RngHandle
+}
+
+impl Hill
+{
+    /// Construit un moteur Hill utilisant la source d'aléa du système.
+    pub fn new() -> Hill
+    {
+        Hill::with_rng(RngHandle::os())
+    }
+
+    /// Construit un moteur Hill utilisant la source d'aléa donnée.
+    pub fn with_rng(rng: RngHandle) -> Hill
+    {
+        Hill
+        {
+            rng
+        }
+    }
+
+    /// Multiplie chaque bloc complet de lettres d'un nombre représentant un texte par la matrice `key`.
+    fn apply(num: &mut BigUint, key: &HillKey)
+    {
+        let n = key.size();
+        let mut bytes = num.to_bytes_be();
+        let positions: Vec<usize> = (0..bytes.len()).filter(| &i | bytes[i].is_ascii_alphabetic()).collect();
+        for block in positions.chunks_exact(n)
+        {
+            let x: Vec<u32> = block.iter().map(| &i | u32::from(bytes[i].to_ascii_uppercase() - b'A')).collect();
+            for (r, &i) in block.iter().enumerate()
+            {
+                let y = (0..n).map(| c | key.get(r, c) * x[c]).sum::<u32>() % ALPHABET_SIZE;
+                let base = if bytes[i].is_ascii_uppercase() { b'A' } else { b'a' };
+                bytes[i] = base + y as u8;
+            }
+        }
+        *num = BigUint::from_bytes_be(&bytes);
+    }
+}
+
+impl Default for Hill
+{
+    fn default() -> Self
+    {
+        Hill::new()
+    }
+}
+
+impl Engine for Hill
+{
+    type EncryptionKey = HillKey;
+    type DecryptionKey = HillKey;
+    type MainKey = HillKey;
+
+    /// `sz_b` est la taille de la matrice, de 1 à `HILL_MAX_SIZE`. Les matrices aléatoires sont tirées jusqu'à en obtenir une inversible.
+    fn try_generate(&self, sz_b: u64, _: u8) -> Result<Self::MainKey, Error>
+    {
+        if sz_b == 0 || sz_b > HILL_MAX_SIZE as u64
+        {
+            return Err(Error::InvalidParameter(format!("Hill.generate : la taille de la matrice doit être comprise entre 1 et {}.", HILL_MAX_SIZE)));
+        }
+        let (n, mut rng) = (sz_b as usize, self.rng());
+        loop
+        {
+            let rows: Vec<Vec<u32>> = (0..n).map(| _ | (0..n).map(| _ | rng.gen_range(0..ALPHABET_SIZE)).collect()).collect();
+            if let Ok(key) = HillKey::new(&rows)
+            {
+                return Ok(key);
+            }
+        }
+    }
+
+    fn try_gen_def(&self) -> Result<Self::MainKey, Error>
+    {
+        self.try_generate(HILL_DEF_SIZE, 0)
+    }
+
+    fn try_run_crypt(&self, num: &mut BigUint, key: &Self::EncryptionKey) -> Result<(), Error>
+    {
+        Hill::apply(num, key);
+        Ok(())
+    }
+
+    fn try_run_decrypt(&self, num: &mut BigUint, key: &Self::DecryptionKey) -> Result<(), Error>
+    {
+        Hill::apply(num, &key.inverse()?);
+        Ok(())
+    }
+
+    fn rng(&self) -> RngHandle
+    {
+        self.rng.clone()
+    }
+
+    /// Chiffrement classique sans sécurité moderne : les paramètres hérités sont autorisés.
+    fn policy(&self) -> Policy
+    {
+        Policy::legacy()
+    }
+
+    /// Le chiffrement étant déterministe et par blocs de lettres, aucun nonce n'est ajouté : le chiffré reste un texte.
+    fn pad(&self, _num: &mut BigUint, _padsize: u32) {}
+
+    /// Aucun nonce n'ayant été ajouté, rien n'est retiré.
+    fn unpad(&self, _num: &mut BigUint, _padsize: u32) {}
+
+    fn padding(&self, _padsize: u32) -> Padding
+    {
+        Padding::Raw
+    }
+}
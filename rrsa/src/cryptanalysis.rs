@@ -0,0 +1,316 @@
+use crate::{Error, classical::*, engines::Engine, keys::NumKey};
+use std::collections::HashMap;
+use num_bigint::BigUint;
+
+
+/// Longueur maximale par défaut des clés Vigenère recherchées.
+pub const VIGENERE_MAX_KEYLEN: usize = 20;
+/// Longueur minimale des séquences répétées prises en compte par le test de Kasiski.
+pub const KASISKI_MIN_LEN: usize = 3;
+/// Nombre de longueurs de clé essayées par `crack_vigenere`, les plus probables selon l'indice de coïncidence.
+const VIGENERE_TRIED_KEYLENS: usize = 4;
+
+/// Fréquences des lettres en français, en pourcentages. Les lettres accentuées étant ignorées par les moteurs classiques, la table est ramenée aux 26 lettres latines.
+const FREQ_FR: [f64; 26] = [
+    7.854, 0.927, 3.353, 3.774, 15.135, 1.096, 0.891, 0.758, 7.744, 0.630, 0.076, 5.612, 3.053,
+    7.297, 5.961, 2.593, 1.401, 6.884, 8.175, 7.451, 6.491, 1.890, 0.050, 0.439, 0.132, 0.335
+];
+/// Fréquences des lettres en anglais, en pourcentages.
+const FREQ_EN: [f64; 26] = [
+    8.167, 1.492, 2.782, 4.253, 12.702, 2.228, 2.015, 6.094, 6.966, 0.153, 0.772, 4.025, 2.406,
+    6.749, 7.507, 1.929, 0.095, 5.987, 6.327, 9.056, 2.758, 0.978, 2.360, 0.150, 1.974, 0.074
+];
+
+
+/// Langue supposée du texte clair, qui détermine la table de fréquences utilisée par l'analyse.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Language
+{
+    French,
+    English
+}
+
+impl Language
+{
+    /// Retrouve une langue depuis son code (`fr` ou `en`, sans distinction de casse).
+    pub fn from_code(code: &str) -> Option<Language>
+    {
+        match code.to_lowercase().as_str()
+        {
+            "fr" | "french" | "francais" | "français" => Some(Language::French),
+            "en" | "english" | "anglais" => Some(Language::English),
+            _ => None
+        }
+    }
+
+    /// Fréquences des lettres de la langue, en pourcentages (A à Z).
+    pub fn frequencies(&self) -> &'static [f64; 26]
+    {
+        match self
+        {
+            Self::French => &FREQ_FR,
+            Self::English => &FREQ_EN
+        }
+    }
+
+    /// Indice de coïncidence attendu pour un texte de la langue : probabilité que deux lettres tirées au hasard soient identiques.
+    pub fn coincidence(&self) -> f64
+    {
+        self.frequencies().iter().map(| f | (f / 100.0) * (f / 100.0)).sum()
+    }
+}
+
+
+/// Texte clair candidat d'une attaque, avec la clé qui le produit et son score (voir `score`) : plus il est bas, plus le candidat est probable.
+#[derive(Clone, Debug)]
+pub struct Candidate<K>
+{
+    pub key: K,
+    pub plaintext: String,
+    pub score: f64
+}
+
+/// Nombre d'occurrences de chaque lettre (A à Z) d'une suite d'indices dans l'alphabet.
+fn counts(letters: &[u32]) -> [usize; 26]
+{
+    let mut counts = [0; 26];
+    letters.iter().for_each(| &l | counts[l as usize] += 1);
+    counts
+}
+
+/// Fréquences observées des lettres latines d'un texte (A à Z), en pourcentages. Toutes nulles si le texte ne contient aucune lettre.
+pub fn frequencies(text: &str) -> [f64; 26]
+{
+    let letters = letters(text);
+    let mut freqs = [0.0; 26];
+    if !letters.is_empty()
+    {
+        for (f, c) in freqs.iter_mut().zip(counts(&letters).iter())
+        {
+            *f = 100.0 * *c as f64 / letters.len() as f64;
+        }
+    }
+    freqs
+}
+
+/// Distance du χ² entre les lettres d'une suite et les fréquences de la langue.
+fn chi_squared(letters: &[u32], lang: Language) -> f64
+{
+    let n = letters.len() as f64;
+    counts(letters).iter().zip(lang.frequencies().iter()).map(| (&c, f) |
+    {
+        let expected = n * f / 100.0;
+        (c as f64 - expected) * (c as f64 - expected) / expected
+    }).sum()
+}
+
+/// Score d'un texte pour une langue : distance du χ² entre ses fréquences de lettres et celles de la langue, ramenée au nombre de lettres.
+/// Plus il est bas, plus le texte ressemble à la langue ; un texte sans lettre obtient un score infini.
+pub fn score(text: &str, lang: Language) -> f64
+{
+    let letters = letters(text);
+    if letters.is_empty()
+    {
+        return f64::INFINITY;
+    }
+
+    chi_squared(&letters, lang) / letters.len() as f64
+}
+
+/// Indice de coïncidence d'une suite de lettres, nul s'il y a moins de deux lettres.
+fn coincidence(letters: &[u32]) -> f64
+{
+    let n = letters.len();
+    if n < 2
+    {
+        return 0.0;
+    }
+
+    counts(letters).iter().map(| &c | c * c.saturating_sub(1)).sum::<usize>() as f64 / (n * (n - 1)) as f64
+}
+
+/// Indice de coïncidence des lettres latines d'un texte : proche de celui de la langue (voir `Language::coincidence`) pour un texte clair ou chiffré
+/// par substitution mono-alphabétique, et de 1/26 pour un texte aléatoire ou chiffré par substitution poly-alphabétique.
+pub fn index_of_coincidence(text: &str) -> f64
+{
+    coincidence(&letters(text))
+}
+
+/// Déchiffre un texte avec un moteur classique. Ces moteurs ne pouvant échouer qu'avec une clé invalide, les clés essayées doivent être valides.
+fn decrypt_text<E, K>(engine: &E, text: &str, key: &K) -> String
+where E : Engine<DecryptionKey = K>
+{
+    let mut num = BigUint::from_bytes_be(text.as_bytes());
+    engine.run_decrypt(&mut num, key);
+    String::from_utf8(num.to_bytes_be()).expect("decrypt_text : les moteurs classiques conservent l'UTF-8.")
+}
+
+/// Trie des candidats du plus probable au moins probable.
+fn rank<K>(mut candidates: Vec<Candidate<K>>) -> Vec<Candidate<K>>
+{
+    candidates.sort_by(| a, b | a.score.total_cmp(&b.score));
+    candidates
+}
+
+/// Vérifie qu'un texte chiffré contient des lettres à analyser.
+fn check_letters(letters: &[u32]) -> Result<(), Error>
+{
+    if letters.is_empty()
+    {
+        return Err(Error::Empty(String::from("cryptanalyse : le texte chiffré ne contient aucune lettre à analyser.")));
+    }
+
+    Ok(())
+}
+
+/// Attaque par force brute d'un chiffrement par décalage (voir `Shift`) : les 26 décalages sont essayés et retournés du plus probable au moins probable.
+/// Retourne `Error::Empty` si le texte ne contient aucune lettre.
+pub fn crack_shift(text: &str, lang: Language) -> Result<Vec<Candidate<NumKey>>, Error>
+{
+    check_letters(&letters(text))?;
+    let shift = Shift::new();
+
+    Ok(rank((0..ALPHABET_SIZE).map(| k |
+    {
+        let key = NumKey::from(BigUint::from(k));
+        let plaintext = decrypt_text(&shift, text, &key);
+        Candidate { score: score(&plaintext, lang), key, plaintext }
+    }).collect()))
+}
+
+/// Test de Kasiski : recherche les séquences d'au moins `KASISKI_MIN_LEN` lettres répétées dans le texte, et compte pour chaque longueur de clé
+/// de 2 à `max_keylen` le nombre d'écarts entre répétitions qu'elle divise. Retourne les longueurs ayant au moins une voix, par nombre de voix décroissant.
+pub fn kasiski(text: &str, max_keylen: usize) -> Vec<(usize, usize)>
+{
+    let letters = letters(text);
+    let mut last: HashMap<&[u32], usize> = HashMap::new();
+    let mut votes = vec![0; max_keylen + 1];
+    for i in 0..letters.len().saturating_sub(KASISKI_MIN_LEN - 1)
+    {
+        let seq = &letters[i..i + KASISKI_MIN_LEN];
+        if let Some(j) = last.insert(seq, i)
+        {
+            let gap = i - j;
+            (2..=max_keylen).filter(| l | gap % l == 0).for_each(| l | votes[l] += 1);
+        }
+    }
+
+    let mut ranked: Vec<(usize, usize)> = votes.into_iter().enumerate().filter(| &(_, v) | v > 0).collect();
+    // À nombre de voix égal, les longueurs les plus courtes d'abord : leurs multiples reçoivent les mêmes voix
+    ranked.sort_by(| a, b | b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    ranked
+}
+
+/// Lettres de la colonne `col` d'un texte découpé en `keylen` colonnes, chiffrées avec la même lettre de clé Vigenère.
+fn column(letters: &[u32], keylen: usize, col: usize) -> Vec<u32>
+{
+    letters.iter().skip(col).step_by(keylen).copied().collect()
+}
+
+/// Indice de coïncidence moyen des colonnes du texte pour chaque longueur de clé de 1 à `max_keylen`.
+/// Pour la bonne longueur (et ses multiples), chaque colonne est chiffrée par un simple décalage et l'indice est proche de celui de la langue.
+pub fn vigenere_coincidences(text: &str, max_keylen: usize) -> Vec<(usize, f64)>
+{
+    let letters = letters(text);
+    (1..=max_keylen.min(letters.len().max(1))).map(| l |
+    {
+        (l, (0..l).map(| c | coincidence(&column(&letters, l, c))).sum::<f64>() / l as f64)
+    }).collect()
+}
+
+/// Longueurs de clé Vigenère les plus probables : celles dont l'indice de coïncidence moyen dépasse le milieu entre celui d'un texte aléatoire
+/// et celui de la langue, les plus courtes d'abord (leurs multiples dépassent aussi ce seuil), suivies des autres par indice décroissant.
+pub fn vigenere_keylens(text: &str, lang: Language, max_keylen: usize) -> Vec<usize>
+{
+    let threshold = (lang.coincidence() + 1.0 / f64::from(ALPHABET_SIZE)) / 2.0;
+    let coincidences = vigenere_coincidences(text, max_keylen);
+    let mut above: Vec<usize> = coincidences.iter().filter(| &&(_, ic) | ic >= threshold).map(| &(l, _) | l).collect();
+    let mut below: Vec<(usize, f64)> = coincidences.into_iter().filter(| &(_, ic) | ic < threshold).collect();
+    below.sort_by(| a, b | b.1.total_cmp(&a.1));
+    above.extend(below.into_iter().map(| (l, _) | l));
+    above
+}
+
+/// Attaque d'un chiffrement de Vigenère (voir `Vigenere`) : pour les longueurs de clé les plus probables (voir `vigenere_keylens`, complétées
+/// de la meilleure longueur du test de Kasiski, sans leurs multiples), chaque lettre de clé est le décalage dont la colonne ressemble le plus à la langue.
+/// Les candidats sont retournés du plus probable au moins probable, sans doublon de texte clair. Retourne `Error::Empty` si le texte ne contient aucune lettre.
+pub fn crack_vigenere(text: &str, lang: Language, max_keylen: usize) -> Result<Vec<Candidate<VigenereKey>>, Error>
+{
+    let letters = letters(text);
+    check_letters(&letters)?;
+    // Les multiples d'une longueur déjà essayée sont écartés : ils donneraient le même texte clair, ou un texte sur-ajusté sur de trop courtes colonnes
+    let mut keylens: Vec<usize> = Vec::new();
+    let kasiski_best = kasiski(text, max_keylen).first().map(| &(l, _) | l);
+    for l in vigenere_keylens(text, lang, max_keylen).into_iter().take(VIGENERE_TRIED_KEYLENS).chain(kasiski_best)
+    {
+        if keylens.iter().all(| k | l % k != 0)
+        {
+            keylens.push(l);
+        }
+    }
+
+    let vigenere = Vigenere::new();
+    let mut candidates: Vec<Candidate<VigenereKey>> = Vec::new();
+    for l in keylens
+    {
+        let key = VigenereKey((0..l).map(| c |
+        {
+            let col = column(&letters, l, c);
+            (0..ALPHABET_SIZE).min_by(| &a, &b |
+            {
+                let shifted = | k: u32 | col.iter().map(| x | (x + ALPHABET_SIZE - k) % ALPHABET_SIZE).collect::<Vec<u32>>();
+                chi_squared(&shifted(a), lang).total_cmp(&chi_squared(&shifted(b), lang))
+            }).unwrap()
+        }).collect());
+        let plaintext = decrypt_text(&vigenere, text, &key);
+        if candidates.iter().all(| c | c.plaintext != plaintext)
+        {
+            candidates.push(Candidate { score: score(&plaintext, lang), key, plaintext });
+        }
+    }
+
+    Ok(rank(candidates))
+}
+
+/// Attaque par force brute d'un chiffrement affine (voir `Affine`) : les 312 clés sont essayées et retournées du plus probable au moins probable.
+/// Retourne `Error::Empty` si le texte ne contient aucune lettre.
+pub fn crack_affine(text: &str, lang: Language) -> Result<Vec<Candidate<AffineKey>>, Error>
+{
+    check_letters(&letters(text))?;
+    let affine = Affine::new();
+
+    Ok(rank((1..ALPHABET_SIZE).filter_map(| a | AffineKey::new(a, 0).ok()).flat_map(| k | (0..ALPHABET_SIZE).map(move | b | AffineKey(k.0, b))).map(| key |
+    {
+        let plaintext = decrypt_text(&affine, text, &key);
+        Candidate { score: score(&plaintext, lang), key, plaintext }
+    }).collect()))
+}
+
+/// Attaque à clair connu d'un chiffrement affine : retrouve la clé depuis un texte clair et son chiffré (seules leurs lettres sont prises en compte).
+/// Deux lettres claires dont la différence est inversible modulo 26 suffisent ; la clé est ensuite vérifiée sur toutes les lettres.
+/// Retourne `Error::InvalidParameter` si les textes n'ont pas le même nombre de lettres ou ne permettent pas de déterminer la clé,
+/// et `Error::Decoding` s'ils ne correspondent à aucune clé affine.
+pub fn affine_known_plaintext(plaintext: &str, ciphertext: &str) -> Result<AffineKey, Error>
+{
+    let (p, c) = (letters(plaintext), letters(ciphertext));
+    if p.len() != c.len()
+    {
+        return Err(Error::InvalidParameter(format!("affine_known_plaintext : {} lettres claires pour {} lettres chiffrées.", p.len(), c.len())));
+    }
+
+    // a * (p1 - p2) = c1 - c2 : il faut une différence de lettres claires inversible
+    let diff = | x: u32, y: u32 | (x + ALPHABET_SIZE - y) % ALPHABET_SIZE;
+    let (a_num, d_inv) = p.iter().zip(c.iter()).skip(1)
+        .find_map(| (&pi, &ci) | alphabet_inv(diff(pi, p[0])).map(| inv | (diff(ci, c[0]), inv)))
+        .ok_or_else(|| Error::InvalidParameter(String::from("affine_known_plaintext : texte clair insuffisant pour déterminer la clé.")))?;
+    let a = (a_num * d_inv) % ALPHABET_SIZE;
+    let b = diff(c[0], (a * p[0]) % ALPHABET_SIZE);
+    let key = AffineKey::new(a, b).map_err(| _ | Error::Decoding(String::from("affine_known_plaintext : aucune clé affine ne correspond à ces textes.")))?;
+
+    if p.iter().zip(c.iter()).any(| (&pi, &ci) | (key.0 * pi + key.1) % ALPHABET_SIZE != ci)
+    {
+        return Err(Error::Decoding(String::from("affine_known_plaintext : aucune clé affine ne correspond à ces textes.")));
+    }
+
+    Ok(key)
+}
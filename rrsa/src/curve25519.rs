@@ -0,0 +1,727 @@
+use crate::{Error, asn1::{self, Reader}, dh, engines::DetachedSigner, keys::*, maths, rng::RngHandle};
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+use rand::RngCore;
+use sha2::{Digest, Sha512};
+use zeroize::Zeroizing;
+
+
+/// Taille en octets des clés, coordonnées encodées et scalaires de Curve25519 et Ed25519.
+pub const CURVE25519_KEY_SIZE: usize = 32;
+/// Taille en bits des scalaires et des éléments du corps encodés, nombre d'itérations des multiplications scalaires.
+const CURVE25519_BITS: u64 = (CURVE25519_KEY_SIZE * 8) as u64;
+/// Taille en octets d'une signature Ed25519 (point R encodé suivi du scalaire S).
+pub const ED25519_SIG_SIZE: usize = 64;
+/// Constante (A - 2) / 4 de la courbe de Montgomery Curve25519 (A = 486662), utilisée par l'échelle de Montgomery.
+const A24: u32 = 121665;
+/// Coordonnée u du point de base de Curve25519.
+const X25519_BASE_U: u8 = 9;
+/// OID des clés X25519 et Ed25519 (RFC 8410).
+const OID_X25519: [u64; 4] = [1, 3, 101, 110];
+const OID_ED25519: [u64; 4] = [1, 3, 101, 112];
+
+
+/// Corps premier des deux courbes, modulo p = 2^255 - 19. Les éléments manipulés sont toujours réduits.
+/// Les multiplications scalaires sont des échelles de Montgomery dont la suite d'opérations ne dépend pas du scalaire ; les grands entiers n'étant
+/// toutefois pas à temps constant, ces implémentations ne protègent qu'en partie contre les attaques par canaux auxiliaires temporels.
+struct Field
+{
+    p: BigUint
+}
+
+impl Field
+{
+    fn new() -> Field
+    {
+        Field
+        {
+            p: (BigUint::one() << 255u8) - 19u8
+        }
+    }
+
+    fn add(&self, a: &BigUint, b: &BigUint) -> BigUint
+    {
+        (a + b) % &self.p
+    }
+
+    fn sub(&self, a: &BigUint, b: &BigUint) -> BigUint
+    {
+        (a + &self.p - b) % &self.p
+    }
+
+    fn mul(&self, a: &BigUint, b: &BigUint) -> BigUint
+    {
+        (a * b) % &self.p
+    }
+
+    /// Inverse par le petit théorème de Fermat (l'inverse de 0 vaut 0, comme le prévoit la RFC 7748).
+    fn inv(&self, a: &BigUint) -> BigUint
+    {
+        maths::fmodpow(a, &(&self.p - 2u8), &self.p)
+    }
+}
+
+/// Encode un entier inférieur à 2^256 sur 32 octets en petit boutiste, comme le font les deux RFC.
+fn to_le32(num: &BigUint) -> [u8; CURVE25519_KEY_SIZE]
+{
+    let mut bytes = [0u8; CURVE25519_KEY_SIZE];
+    let le = Zeroizing::new(num.to_bytes_le());
+    bytes[..le.len()].copy_from_slice(&le);
+    bytes
+}
+
+/// Applique le « clamping » des RFC 7748 et 8032 à un scalaire : multiple de 8 (cofacteur), bit 254 forcé à 1.
+fn clamp(bytes: &[u8]) -> BigUint
+{
+    let mut k = Zeroizing::new([0u8; CURVE25519_KEY_SIZE]);
+    k.copy_from_slice(&bytes[..CURVE25519_KEY_SIZE]);
+    k[0] &= 248;
+    k[31] &= 127;
+    k[31] |= 64;
+    BigUint::from_bytes_le(k.as_ref())
+}
+
+
+/// Échelle de Montgomery (RFC 7748, section 5) : coordonnée u de [k]P, où P a pour coordonnée u (réduite).
+fn ladder(f: &Field, k: &BigUint, u: &BigUint) -> BigUint
+{
+    let (x1, a24) = (u.clone(), BigUint::from(A24));
+    let (mut x2, mut z2, mut x3, mut z3) = (BigUint::one(), BigUint::zero(), u.clone(), BigUint::one());
+    let mut swap = false;
+
+    for t in (0..255).rev()
+    {
+        let kt = k.bit(t);
+        maths::cswap(&mut x2, &mut x3, swap ^ kt, CURVE25519_BITS);
+        maths::cswap(&mut z2, &mut z3, swap ^ kt, CURVE25519_BITS);
+        swap = kt;
+
+        let (a, b) = (f.add(&x2, &z2), f.sub(&x2, &z2));
+        let (aa, bb) = (f.mul(&a, &a), f.mul(&b, &b));
+        let e = f.sub(&aa, &bb);
+        let (c, d) = (f.add(&x3, &z3), f.sub(&x3, &z3));
+        let (da, cb) = (f.mul(&d, &a), f.mul(&c, &b));
+        let (sum, diff) = (f.add(&da, &cb), f.sub(&da, &cb));
+        x3 = f.mul(&sum, &sum);
+        z3 = f.mul(&x1, &f.mul(&diff, &diff));
+        x2 = f.mul(&aa, &bb);
+        z2 = f.mul(&e, &f.add(&aa, &f.mul(&a24, &e)));
+    }
+    maths::cswap(&mut x2, &mut x3, swap, CURVE25519_BITS);
+    maths::cswap(&mut z2, &mut z3, swap, CURVE25519_BITS);
+
+    f.mul(&x2, &f.inv(&z2))
+}
+
+/// Décode une coordonnée u (RFC 7748) : le bit de poids fort est ignoré, les valeurs non canoniques sont réduites.
+fn decode_u(f: &Field, bytes: &[u8; CURVE25519_KEY_SIZE]) -> BigUint
+{
+    let mut u = *bytes;
+    u[31] &= 127;
+    BigUint::from_bytes_le(&u) % &f.p
+}
+
+/// Fonction X25519 de la RFC 7748 : multiplie le point de coordonnée `u` par le scalaire `scalar` après « clamping » et retourne la coordonnée u obtenue.
+pub fn x25519(scalar: &[u8; CURVE25519_KEY_SIZE], u: &[u8; CURVE25519_KEY_SIZE]) -> [u8; CURVE25519_KEY_SIZE]
+{
+    let f = Field::new();
+    let mut k = clamp(scalar);
+    let res = to_le32(&ladder(&f, &k, &decode_u(&f, u)));
+    maths::wipe(&mut k);
+    res
+}
+
+
+/// Point de la courbe d'Edwards d'Ed25519 en coordonnées étendues (X : Y : Z : T), avec x = X / Z, y = Y / Z et xy = T / Z.
+struct Point
+{
+    x: BigUint,
+    y: BigUint,
+    z: BigUint,
+    t: BigUint
+}
+
+/// Paramètres d'Ed25519 (RFC 8032, section 5.1) : corps, constante d de la courbe, ordre l du sous-groupe premier, racine de -1 et point de base.
+struct Edwards
+{
+    f: Field,
+    d: BigUint,
+    l: BigUint,
+    sqrt_m1: BigUint,
+    base: Point
+}
+
+impl Edwards
+{
+    fn new() -> Edwards
+    {
+        let f = Field::new();
+        // d = -121665 / 121666
+        let d = f.mul(&f.sub(&BigUint::zero(), &BigUint::from(121665u32)), &f.inv(&BigUint::from(121666u32)));
+        let l = (BigUint::one() << 252u8) + "27742317777372353535851937790883648493".parse::<BigUint>().unwrap();
+        let sqrt_m1 = maths::fmodpow(&BigUint::from(2u8), &((&f.p - 1u8) >> 2u8), &f.p);
+        let mut ed = Edwards
+        {
+            f,
+            d,
+            l,
+            sqrt_m1,
+            base: Edwards::identity()
+        };
+        // Le point de base a pour ordonnée 4 / 5 et une abscisse paire
+        let by = ed.f.mul(&BigUint::from(4u8), &ed.f.inv(&BigUint::from(5u8)));
+        ed.base = ed.decode(&to_le32(&by)).expect("point de base Ed25519");
+        ed
+    }
+
+    fn identity() -> Point
+    {
+        Point { x: BigUint::zero(), y: BigUint::one(), z: BigUint::one(), t: BigUint::zero() }
+    }
+
+    /// Addition de deux points (formules complètes de la RFC 8032, section 5.1.4, valables aussi pour le doublement).
+    fn add(&self, p: &Point, q: &Point) -> Point
+    {
+        let f = &self.f;
+        let a = f.mul(&f.sub(&p.y, &p.x), &f.sub(&q.y, &q.x));
+        let b = f.mul(&f.add(&p.y, &p.x), &f.add(&q.y, &q.x));
+        let c = f.mul(&f.mul(&p.t, &q.t), &f.add(&self.d, &self.d));
+        let d = f.mul(&f.add(&p.z, &p.z), &q.z);
+        let (e, ff, g, h) = (f.sub(&b, &a), f.sub(&d, &c), f.add(&d, &c), f.add(&b, &a));
+
+        Point { x: f.mul(&e, &ff), y: f.mul(&g, &h), z: f.mul(&ff, &g), t: f.mul(&e, &h) }
+    }
+
+    /// Échange conditionnel de deux points, sans branchement (voir `maths::cswap`).
+    fn cswap(p: &mut Point, q: &mut Point, swap: bool)
+    {
+        maths::cswap(&mut p.x, &mut q.x, swap, CURVE25519_BITS);
+        maths::cswap(&mut p.y, &mut q.y, swap, CURVE25519_BITS);
+        maths::cswap(&mut p.z, &mut q.z, swap, CURVE25519_BITS);
+        maths::cswap(&mut p.t, &mut q.t, swap, CURVE25519_BITS);
+    }
+
+    /// Multiplication d'un point par un scalaire inférieur à 2^256 (échelle de Montgomery), pour que le temps de calcul ne dépende pas du scalaire secret :
+    /// les formules d'addition étant complètes, chacune des 256 itérations fait deux additions, les points étant échangés selon les bits du scalaire par `cswap`.
+    fn mul(&self, k: &BigUint, p: &Point) -> Point
+    {
+        let mut r0 = Edwards::identity();
+        let mut r1 = Point { x: p.x.clone(), y: p.y.clone(), z: p.z.clone(), t: p.t.clone() };
+        for i in (0..CURVE25519_BITS).rev()
+        {
+            let bit = k.bit(i);
+            Edwards::cswap(&mut r0, &mut r1, bit);
+            r1 = self.add(&r0, &r1);
+            r0 = self.add(&r0, &r0);
+            Edwards::cswap(&mut r0, &mut r1, bit);
+        }
+        r0
+    }
+
+    /// Égalité de deux points, sans repasser en coordonnées affines.
+    fn eq(&self, p: &Point, q: &Point) -> bool
+    {
+        let f = &self.f;
+        f.mul(&p.x, &q.z) == f.mul(&q.x, &p.z) && f.mul(&p.y, &q.z) == f.mul(&q.y, &p.z)
+    }
+
+    /// Indique si un point est d'ordre faible, c'est-à-dire annulé par le cofacteur 8.
+    fn is_small_order(&self, p: &Point) -> bool
+    {
+        self.eq(&self.mul(&BigUint::from(8u8), p), &Edwards::identity())
+    }
+
+    /// Encode un point (RFC 8032, section 5.1.2) : ordonnée en petit boutiste, bit de poids fort égal à la parité de l'abscisse.
+    fn encode(&self, p: &Point) -> [u8; CURVE25519_KEY_SIZE]
+    {
+        let zi = self.f.inv(&p.z);
+        let (x, y) = (self.f.mul(&p.x, &zi), self.f.mul(&p.y, &zi));
+        let mut bytes = to_le32(&y);
+        if x.bit(0)
+        {
+            bytes[31] |= 0x80;
+        }
+        bytes
+    }
+
+    /// Décode un point (RFC 8032, section 5.1.3). Retourne `Error::InvalidKey` si l'encodage n'est pas canonique ou si le point n'est pas sur la courbe.
+    fn decode(&self, bytes: &[u8]) -> Result<Point, Error>
+    {
+        let f = &self.f;
+        let mut yb = [0u8; CURVE25519_KEY_SIZE];
+        yb.copy_from_slice(&bytes[..CURVE25519_KEY_SIZE]);
+        let sign = yb[31] >> 7 == 1;
+        yb[31] &= 127;
+        let y = BigUint::from_bytes_le(&yb);
+        if y >= f.p
+        {
+            return Err(Error::InvalidKey(String::from("point Ed25519 non canonique.")));
+        }
+
+        // x² = (y² - 1) / (d y² + 1)
+        let yy = f.mul(&y, &y);
+        let x2 = f.mul(&f.sub(&yy, &BigUint::one()), &f.inv(&f.add(&f.mul(&self.d, &yy), &BigUint::one())));
+        let mut x = maths::fmodpow(&x2, &((&f.p + 3u8) >> 3u8), &f.p);
+        if f.mul(&x, &x) != x2
+        {
+            x = f.mul(&x, &self.sqrt_m1);
+        }
+        if f.mul(&x, &x) != x2 || (x.is_zero() && sign)
+        {
+            return Err(Error::InvalidKey(String::from("le point n'appartient pas à la courbe Ed25519.")));
+        }
+        if x.bit(0) != sign
+        {
+            x = &f.p - x;
+        }
+
+        let t = f.mul(&x, &y);
+        Ok(Point { x, y, z: BigUint::one(), t })
+    }
+
+    /// Condensat SHA-512 d'une concaténation, interprété en petit boutiste et réduit modulo l.
+    fn hash_scalar(&self, parts: &[&[u8]]) -> BigUint
+    {
+        let mut h = Sha512::new();
+        parts.iter().for_each(| p | h.update(p));
+        BigUint::from_bytes_le(&h.finalize()) % &self.l
+    }
+
+    /// Développe une graine de clé privée (RFC 8032, section 5.1.5) : scalaire secret s après « clamping » et préfixe de dérivation des nonces.
+    fn expand(seed: &[u8]) -> (BigUint, Zeroizing<Vec<u8>>)
+    {
+        let h = Zeroizing::new(Sha512::digest(seed).to_vec());
+        (clamp(&h[..CURVE25519_KEY_SIZE]), Zeroizing::new(h[CURVE25519_KEY_SIZE..].to_vec()))
+    }
+}
+
+
+/// Encode une clé privée de courbe au format PKCS#8 (PrivateKeyInfo, DER) de la RFC 8410 : la clé y figure dans une chaîne d'octets imbriquée.
+fn pkcs8_encode(oid: &[u64], key: &[u8]) -> Zeroizing<Vec<u8>>
+{
+    let items = Zeroizing::new(vec![
+        asn1::integer(&BigUint::zero()),
+        asn1::sequence(&[asn1::oid(oid)]),
+        asn1::octets(&asn1::octets(key))
+    ]);
+
+    Zeroizing::new(asn1::sequence(&items))
+}
+
+/// Décode une clé privée de courbe depuis le format PKCS#8 de la RFC 8410 (version 0 ou 1, la clé publique éventuelle étant ignorée).
+fn pkcs8_decode(oid: &[u64], der: &[u8], name: &str) -> Result<Zeroizing<[u8; CURVE25519_KEY_SIZE]>, Error>
+{
+    let mut seq = Reader::from(der).read_sequence()?;
+    seq.read_integer()?;
+    if seq.read_sequence()?.read_oid()? != oid
+    {
+        return Err(Error::Parse(format!("{}::from_pkcs8 : algorithme de clé non supporté.", name), None));
+    }
+    let key = Reader::from(seq.read_octets()?).read_octets()?;
+    if key.len() != CURVE25519_KEY_SIZE
+    {
+        return Err(Error::Parse(format!("{}::from_pkcs8 : taille de clé invalide.", name), None));
+    }
+
+    let mut bytes = Zeroizing::new([0u8; CURVE25519_KEY_SIZE]);
+    bytes.copy_from_slice(key);
+    Ok(bytes)
+}
+
+/// Décode la sérialisation hexadécimale d'une clé de courbe (voir `keys::hex_decode`).
+fn key_bytes(val: &str, name: &str) -> Result<Zeroizing<[u8; CURVE25519_KEY_SIZE]>, Error>
+{
+    let mut bytes = Zeroizing::new([0u8; CURVE25519_KEY_SIZE]);
+    bytes.copy_from_slice(&hex_decode(val, CURVE25519_KEY_SIZE, name)?);
+    Ok(bytes)
+}
+
+/// Tire une clé privée de 32 octets aléatoires.
+fn random_key(rng: &RngHandle) -> Result<Zeroizing<[u8; CURVE25519_KEY_SIZE]>, Error>
+{
+    let mut bytes = Zeroizing::new([0u8; CURVE25519_KEY_SIZE]);
+    rng.clone().try_fill_bytes(bytes.as_mut()).map_err(| e | Error::Rng(e.to_string()))?;
+    Ok(bytes)
+}
+
+
+/// Clé publique X25519 : coordonnée u encodée sur 32 octets. Sérialisation textuelle : hexadécimal.
+pub struct X25519PublicKey(pub [u8; CURVE25519_KEY_SIZE]);
+
+impl Key for X25519PublicKey
+{
+    const PARTS: Option<usize> = Some(1);
+
+    fn from_str(val: String) -> Result<Self, Error>
+    {
+        Ok(X25519PublicKey(*key_bytes(&val, "X25519PublicKey")?))
+    }
+
+    fn serialize_str(&self) -> String
+    {
+        hex_encode(&self.0)
+    }
+}
+
+/// Validation d'une clé publique : rejette les points d'ordre faible (dont 0 et 1), avec lesquels le secret partagé ne dépendrait pas de la clé privée.
+impl Validate for X25519PublicKey
+{
+    fn validate(&self) -> Result<(), Error>
+    {
+        let f = Field::new();
+        if ladder(&f, &BigUint::from(8u8), &decode_u(&f, &self.0)).is_zero()
+        {
+            return Err(Error::InvalidKey(String::from("point X25519 d'ordre faible.")));
+        }
+
+        Ok(())
+    }
+}
+
+/// Clé privée X25519 : scalaire de 32 octets, effacé de la mémoire à la destruction de la clé. Sérialisation textuelle : hexadécimal.
+pub struct X25519PrivateKey(Zeroizing<[u8; CURVE25519_KEY_SIZE]>);
+
+impl X25519PrivateKey
+{
+    /// Construit une clé privée depuis ses 32 octets.
+    pub fn from(bytes: [u8; CURVE25519_KEY_SIZE]) -> X25519PrivateKey
+    {
+        X25519PrivateKey(Zeroizing::new(bytes))
+    }
+
+    /// Octets de la clé privée.
+    pub fn bytes(&self) -> &[u8; CURVE25519_KEY_SIZE]
+    {
+        &self.0
+    }
+
+    /// Clé publique correspondante : X25519 appliqué au point de base.
+    pub fn public(&self) -> X25519PublicKey
+    {
+        let mut base = [0u8; CURVE25519_KEY_SIZE];
+        base[0] = X25519_BASE_U;
+        X25519PublicKey(x25519(&self.0, &base))
+    }
+
+    /// Encode la clé au format PKCS#8 (PrivateKeyInfo, DER) de la RFC 8410.
+    pub fn to_pkcs8(&self) -> Zeroizing<Vec<u8>>
+    {
+        pkcs8_encode(&OID_X25519, self.0.as_ref())
+    }
+
+    /// Décode une clé depuis le format PKCS#8 (PrivateKeyInfo, DER) de la RFC 8410.
+    pub fn from_pkcs8(der: &[u8]) -> Result<X25519PrivateKey, Error>
+    {
+        Ok(X25519PrivateKey(pkcs8_decode(&OID_X25519, der, "X25519PrivateKey")?))
+    }
+}
+
+impl Key for X25519PrivateKey
+{
+    const PARTS: Option<usize> = Some(1);
+
+    fn from_str(val: String) -> Result<Self, Error>
+    {
+        Ok(X25519PrivateKey(key_bytes(&val, "X25519PrivateKey")?))
+    }
+
+    fn serialize_str(&self) -> String
+    {
+        hex_encode(self.0.as_ref())
+    }
+}
+
+/// Clé principale X25519 : clé publique et clé privée.
+pub type X25519Key = KeyPair<X25519PublicKey, X25519PrivateKey>;
+
+/// Validation d'une clé principale : la clé publique doit être valide et correspondre à la clé privée.
+impl Validate for X25519Key
+{
+    fn validate(&self) -> Result<(), Error>
+    {
+        self.0.validate()?;
+        if self.1.public().0 != self.0.0
+        {
+            return Err(Error::InvalidKey(String::from("la clé publique X25519 ne correspond pas à la clé privée.")));
+        }
+
+        Ok(())
+    }
+}
+
+
+/// Clé publique Ed25519 : point A encodé sur 32 octets. Sérialisation textuelle : hexadécimal.
+pub struct Ed25519PublicKey(pub [u8; CURVE25519_KEY_SIZE]);
+
+impl Key for Ed25519PublicKey
+{
+    const PARTS: Option<usize> = Some(1);
+
+    fn from_str(val: String) -> Result<Self, Error>
+    {
+        Ok(Ed25519PublicKey(*key_bytes(&val, "Ed25519PublicKey")?))
+    }
+
+    fn serialize_str(&self) -> String
+    {
+        hex_encode(&self.0)
+    }
+}
+
+/// Validation d'une clé publique : le point doit être encodé de manière canonique, appartenir à la courbe et ne pas être d'ordre faible.
+impl Validate for Ed25519PublicKey
+{
+    fn validate(&self) -> Result<(), Error>
+    {
+        let ed = Edwards::new();
+        if ed.is_small_order(&ed.decode(&self.0)?)
+        {
+            return Err(Error::InvalidKey(String::from("point Ed25519 d'ordre faible.")));
+        }
+
+        Ok(())
+    }
+}
+
+/// Clé privée Ed25519 : graine de 32 octets dont sont dérivés le scalaire secret et le préfixe des nonces (RFC 8032, section 5.1.5).
+/// Elle est effacée de la mémoire à la destruction de la clé. Sérialisation textuelle : hexadécimal.
+pub struct Ed25519PrivateKey(Zeroizing<[u8; CURVE25519_KEY_SIZE]>);
+
+impl Ed25519PrivateKey
+{
+    /// Construit une clé privée depuis sa graine de 32 octets.
+    pub fn from(bytes: [u8; CURVE25519_KEY_SIZE]) -> Ed25519PrivateKey
+    {
+        Ed25519PrivateKey(Zeroizing::new(bytes))
+    }
+
+    /// Graine de la clé privée.
+    pub fn bytes(&self) -> &[u8; CURVE25519_KEY_SIZE]
+    {
+        &self.0
+    }
+
+    /// Clé publique correspondante : A = [s]B.
+    pub fn public(&self) -> Ed25519PublicKey
+    {
+        let ed = Edwards::new();
+        let (mut s, _) = Edwards::expand(self.0.as_ref());
+        let a = ed.encode(&ed.mul(&s, &ed.base));
+        maths::wipe(&mut s);
+        Ed25519PublicKey(a)
+    }
+
+    /// Encode la clé au format PKCS#8 (PrivateKeyInfo, DER) de la RFC 8410.
+    pub fn to_pkcs8(&self) -> Zeroizing<Vec<u8>>
+    {
+        pkcs8_encode(&OID_ED25519, self.0.as_ref())
+    }
+
+    /// Décode une clé depuis le format PKCS#8 (PrivateKeyInfo, DER) de la RFC 8410.
+    pub fn from_pkcs8(der: &[u8]) -> Result<Ed25519PrivateKey, Error>
+    {
+        Ok(Ed25519PrivateKey(pkcs8_decode(&OID_ED25519, der, "Ed25519PrivateKey")?))
+    }
+}
+
+impl Key for Ed25519PrivateKey
+{
+    const PARTS: Option<usize> = Some(1);
+
+    fn from_str(val: String) -> Result<Self, Error>
+    {
+        Ok(Ed25519PrivateKey(key_bytes(&val, "Ed25519PrivateKey")?))
+    }
+
+    fn serialize_str(&self) -> String
+    {
+        hex_encode(self.0.as_ref())
+    }
+}
+
+/// Clé principale Ed25519 : clé publique et clé privée.
+pub type Ed25519Key = KeyPair<Ed25519PublicKey, Ed25519PrivateKey>;
+
+/// Validation d'une clé principale : la clé publique doit être valide et correspondre à la clé privée.
+impl Validate for Ed25519Key
+{
+    fn validate(&self) -> Result<(), Error>
+    {
+        self.0.validate()?;
+        if self.1.public().0 != self.0.0
+        {
+            return Err(Error::InvalidKey(String::from("la clé publique Ed25519 ne correspond pas à la clé privée.")));
+        }
+
+        Ok(())
+    }
+}
+
+
+/// Accord de clé X25519 (RFC 7748) sur la courbe de Montgomery Curve25519. Comme pour `Dh`, le secret partagé n'est jamais utilisé directement : il est dérivé par HKDF.
+pub struct X25519
+{
+    rng: RngHandle
+}
+
+impl X25519
+{
+    /// Construit un accord de clé utilisant la source d'aléa du système.
+    pub fn new() -> X25519
+    {
+        X25519::with_rng(RngHandle::os())
+    }
+
+    /// Construit un accord de clé utilisant la source d'aléa donnée, qui doit être cryptographiquement sûre (voir `RngHandle`).
+    pub fn with_rng(rng: RngHandle) -> X25519
+    {
+        X25519
+        {
+            rng
+        }
+    }
+
+    /// Donne la source d'aléa utilisée.
+    pub fn rng(&self) -> RngHandle
+    {
+        self.rng.clone()
+    }
+
+    /// Génère une clé principale : 32 octets aléatoires et la clé publique correspondante. Retourne `Error::Rng` si la source d'aléa échoue.
+    pub fn try_generate(&self) -> Result<X25519Key, Error>
+    {
+        let prk = X25519PrivateKey(random_key(&self.rng)?);
+        Ok(KeyPair(prk.public(), prk))
+    }
+
+    /// Génère une clé principale, voir `try_generate`. Panique en cas d'erreur.
+    pub fn generate(&self) -> X25519Key
+    {
+        self.try_generate().expect("X25519.generate")
+    }
+
+    /// Dérive un secret de `len` octets partagé avec le détenteur de la clé publique `peer`, lié au contexte `info` (voir `dh::hkdf_sha256`).
+    /// Retourne `Error::InvalidKey` si le secret X25519 est nul, c'est-à-dire si la clé publique est d'ordre faible (RFC 7748, section 6.1).
+    pub fn try_derive(&self, key: &X25519PrivateKey, peer: &X25519PublicKey, info: &[u8], len: usize) -> Result<Zeroizing<Vec<u8>>, Error>
+    {
+        let z = Zeroizing::new(x25519(&key.0, &peer.0));
+        if z.iter().all(| b | *b == 0)
+        {
+            return Err(Error::InvalidKey(String::from("X25519.derive : clé publique d'ordre faible, secret partagé nul.")));
+        }
+
+        dh::hkdf_sha256(&[], z.as_ref(), info, len)
+    }
+}
+
+impl Default for X25519
+{
+    fn default() -> Self
+    {
+        X25519::new()
+    }
+}
+
+
+/// Signature Ed25519 (RFC 8032) sur la courbe d'Edwards équivalente à Curve25519. La signature est déterministe : seule la génération de clé utilise l'aléa.
+pub struct Ed25519
+{
+    rng: RngHandle
+}
+
+impl Ed25519
+{
+    /// Construit un moteur de signature utilisant la source d'aléa du système.
+    pub fn new() -> Ed25519
+    {
+        Ed25519::with_rng(RngHandle::os())
+    }
+
+    /// Construit un moteur de signature utilisant la source d'aléa donnée, qui doit être cryptographiquement sûre (voir `RngHandle`).
+    pub fn with_rng(rng: RngHandle) -> Ed25519
+    {
+        Ed25519
+        {
+            rng
+        }
+    }
+
+    /// Donne la source d'aléa utilisée.
+    pub fn rng(&self) -> RngHandle
+    {
+        self.rng.clone()
+    }
+
+    /// Génère une clé principale : graine de 32 octets aléatoires et la clé publique correspondante. Retourne `Error::Rng` si la source d'aléa échoue.
+    pub fn try_generate(&self) -> Result<Ed25519Key, Error>
+    {
+        let prk = Ed25519PrivateKey(random_key(&self.rng)?);
+        Ok(KeyPair(prk.public(), prk))
+    }
+
+    /// Génère une clé principale, voir `try_generate`. Panique en cas d'erreur.
+    pub fn generate(&self) -> Ed25519Key
+    {
+        self.try_generate().expect("Ed25519.generate")
+    }
+}
+
+impl Default for Ed25519
+{
+    fn default() -> Self
+    {
+        Ed25519::new()
+    }
+}
+
+/// Signature et vérification de la RFC 8032 (section 5.1.6 et 5.1.7). La vérification utilise l'équation avec cofacteur [8][S]B = [8]R + [8][k]A.
+impl DetachedSigner for Ed25519
+{
+    type SigningKey = Ed25519PrivateKey;
+    type VerifyingKey = Ed25519PublicKey;
+
+    fn try_sign_detached(&self, message: &[u8], key: &Ed25519PrivateKey) -> Result<Vec<u8>, Error>
+    {
+        let ed = Edwards::new();
+        let (mut s, prefix) = Edwards::expand(key.0.as_ref());
+        let a = ed.encode(&ed.mul(&s, &ed.base));
+
+        let mut r = ed.hash_scalar(&[&prefix, message]);
+        let rb = ed.encode(&ed.mul(&r, &ed.base));
+        let k = ed.hash_scalar(&[&rb, &a, message]);
+        let sig_s = (&r + k * &s) % &ed.l;
+        maths::wipe(&mut r);
+        maths::wipe(&mut s);
+
+        let mut sig = rb.to_vec();
+        sig.extend_from_slice(&to_le32(&sig_s));
+        Ok(sig)
+    }
+
+    fn try_verify_detached(&self, message: &[u8], signature: &[u8], key: &Ed25519PublicKey) -> Result<(), Error>
+    {
+        let invalid = || Error::Decoding(String::from("Ed25519.verify : signature invalide."));
+        if signature.len() != ED25519_SIG_SIZE
+        {
+            return Err(invalid());
+        }
+        let ed = Edwards::new();
+        let a = ed.decode(&key.0)?;
+        let r = ed.decode(&signature[..CURVE25519_KEY_SIZE]).map_err(|_| invalid())?;
+        let s = BigUint::from_bytes_le(&signature[CURVE25519_KEY_SIZE..]);
+        if s >= ed.l
+        {
+            return Err(invalid());
+        }
+
+        let k = ed.hash_scalar(&[&signature[..CURVE25519_KEY_SIZE], &key.0, message]);
+        let (lhs, rhs) = (ed.mul(&s, &ed.base), ed.add(&r, &ed.mul(&k, &a)));
+        let cofactor = BigUint::from(8u8);
+        if !ed.eq(&ed.mul(&cofactor, &lhs), &ed.mul(&cofactor, &rhs))
+        {
+            return Err(invalid());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,349 @@
+use crate::{Error, elgamal, engines::{self, GenControl, GenStats, RSA_MIN_MODULUS_BITS}, keys::*, maths, policy::Policy, rng::RngHandle};
+use std::{convert::TryInto, time::Instant};
+use hmac::{Hmac, Mac};
+use num_bigint::{BigUint, RandBigInt};
+use num_traits::One;
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+
+/// Taille en bits du groupe RFC 7919 utilisé par défaut (ffdhe2048).
+pub const DH_DEF_GROUP_BITS: u32 = 2048;
+/// Taille par défaut en octets des secrets dérivés.
+pub const DH_DEF_SECRET_LEN: usize = 32;
+/// Taille en octets de la sortie de SHA-256, fonction de hachage de la HKDF.
+const HKDF_HASH_LEN: usize = 32;
+/// Groupes ffdhe de la RFC 7919 (taille en bits, constante c) : p = 2^b - 2^(b - 64) + (floor(2^(b - 130) * e) + c) * 2^64 - 1, de générateur 2.
+const FFDHE_GROUPS: [(u32, u32); 5] = [(2048, 560316), (3072, 2625351), (4096, 5736041), (6144, 15705020), (8192, 10965728)];
+
+
+/// Groupe Diffie-Hellman (p, g) : p = 2q + 1 est un nombre premier sûr, et g engendre le sous-groupe d'ordre q.
+/// Sérialisation textuelle : `p::g`.
+pub struct DhGroup(pub NumKey, pub NumKey);
+
+impl DhGroup
+{
+    /// Construit un groupe depuis le module p et le générateur g.
+    pub fn from(p: NumKey, g: NumKey) -> DhGroup
+    {
+        DhGroup(p, g)
+    }
+
+    /// Groupe ffdhe de la RFC 7919 de la taille donnée en bits : 2048, 3072, 4096, 6144 ou 8192.
+    /// Retourne `Error::InvalidParameter` pour toute autre taille.
+    pub fn ffdhe(bits: u32) -> Result<DhGroup, Error>
+    {
+        let c = FFDHE_GROUPS.iter().find(| (b, _) | *b == bits).map(| (_, c) | *c)
+            .ok_or_else(|| Error::InvalidParameter(format!("DhGroup::ffdhe : aucun groupe ffdhe de {} bits (2048, 3072, 4096, 6144 ou 8192).", bits)))?;
+        let b = u64::from(bits);
+
+        // floor(2^m * e) = somme des 2^m / k!, calculée avec 64 bits de garde pour absorber les troncatures
+        let (mut e, mut term, mut k) = (BigUint::default(), BigUint::one() << (b - 130 + 64), 0u32);
+        while term.bits() > 0
+        {
+            e += &term;
+            k += 1;
+            term /= k;
+        }
+        let p = (BigUint::one() << b) - (BigUint::one() << (b - 64)) + (((e >> 64u8) + c) << 64u8) - 1u8;
+
+        Ok(DhGroup(NumKey::from(p), NumKey::from(BigUint::from(2u8))))
+    }
+
+    /// Taille du module p en bits, si le groupe est un des groupes ffdhe de la RFC 7919.
+    pub fn ffdhe_bits(&self) -> Option<u32>
+    {
+        let bits = self.0.value().bits().try_into().ok()?;
+        DhGroup::ffdhe(bits).ok().filter(| g | g == self).map(| _ | bits)
+    }
+
+    /// Taille du module p en bits.
+    pub fn bits(&self) -> u64
+    {
+        self.0.value().bits()
+    }
+}
+
+/// Le groupe étant public, il peut être copié et comparé librement, contrairement aux clés numériques qui le composent.
+impl Clone for DhGroup
+{
+    fn clone(&self) -> Self
+    {
+        DhGroup(NumKey::from(self.0.value().clone()), NumKey::from(self.1.value().clone()))
+    }
+}
+
+impl PartialEq for DhGroup
+{
+    fn eq(&self, other: &Self) -> bool
+    {
+        self.0.value() == other.0.value() && self.1.value() == other.1.value()
+    }
+}
+
+impl Key for DhGroup
+{
+    const PARTS: Option<usize> = Some(2);
+
+    fn from_str(val: String) -> Result<Self, Error>
+    {
+        let pair = KeyPair::<NumKey, NumKey>::from_str(val)?;
+        Ok(DhGroup(pair.0, pair.1))
+    }
+
+    fn serialize_str(&self) -> String
+    {
+        [self.0.serialize_str(), self.1.serialize_str()].join(KEY_SERIAL_DELIMITER)
+    }
+}
+
+/// Validation d'un groupe : p doit être un nombre premier sûr et g appartenir au sous-groupe d'ordre q. La primalité n'est pas revérifiée pour les groupes ffdhe.
+/// Retourne `Error::WeakKey` si le module fait moins de `RSA_MIN_MODULUS_BITS` bits.
+impl Validate for DhGroup
+{
+    fn validate(&self) -> Result<(), Error>
+    {
+        let p = self.0.value();
+        if self.ffdhe_bits().is_none()
+        {
+            elgamal::validate_safe_prime(p)?;
+        }
+        elgamal::validate_subgroup(p, self.1.value(), "le générateur")?;
+        if p.bits() < RSA_MIN_MODULUS_BITS
+        {
+            return Err(Error::WeakKey(format!("module de {} bits, au moins {} bits sont nécessaires.", p.bits(), RSA_MIN_MODULUS_BITS)));
+        }
+
+        Ok(())
+    }
+}
+
+
+/// Valeur publique Diffie-Hellman (groupe, y), avec y = g^x mod p. Sérialisation textuelle : `p::g::y`.
+pub struct DhPublicKey(pub DhGroup, pub NumKey);
+
+impl Key for DhPublicKey
+{
+    const PARTS: Option<usize> = Some(3);
+
+    fn from_str(val: String) -> Result<Self, Error>
+    {
+        let parts: Vec<&str> = val.split(KEY_SERIAL_DELIMITER).collect();
+        if parts.len() != 3
+        {
+            return Err(Error::Parse(format!("DhPublicKey::from_str : nombre de parties ({}) invalide pour une valeur publique Diffie-Hellman.", parts.len()), None));
+        }
+
+        Ok(DhPublicKey(DhGroup::from_str(parts[..2].join(KEY_SERIAL_DELIMITER))?, NumKey::from_str(String::from(parts[2]))?))
+    }
+
+    fn serialize_str(&self) -> String
+    {
+        [self.0.serialize_str(), self.1.serialize_str()].join(KEY_SERIAL_DELIMITER)
+    }
+}
+
+/// Validation d'une valeur publique : en plus de la validation du groupe, y doit appartenir au sous-groupe d'ordre q (ce qui exclut 0, 1 et p - 1),
+/// pour empêcher le confinement du secret partagé dans un petit sous-groupe.
+impl Validate for DhPublicKey
+{
+    fn validate(&self) -> Result<(), Error>
+    {
+        validate_deferred(self.0.validate(), || elgamal::validate_subgroup(self.0.0.value(), self.1.value(), "la valeur publique"))
+    }
+}
+
+/// Clé Diffie-Hellman (groupe, x, y) : exposant secret x et valeur publique y = g^x mod p. Sérialisation textuelle : `p::g::x::y`.
+pub struct DhKey(pub DhGroup, pub NumKey, pub NumKey);
+
+impl DhKey
+{
+    /// Valeur publique de la clé, à transmettre à l'autre partie.
+    pub fn public(&self) -> DhPublicKey
+    {
+        DhPublicKey(self.0.clone(), NumKey::from(self.2.value().clone()))
+    }
+}
+
+impl Key for DhKey
+{
+    const PARTS: Option<usize> = Some(4);
+
+    fn from_str(val: String) -> Result<Self, Error>
+    {
+        let parts: Vec<&str> = val.split(KEY_SERIAL_DELIMITER).collect();
+        if parts.len() != 4
+        {
+            return Err(Error::Parse(format!("DhKey::from_str : nombre de parties ({}) invalide pour une clé Diffie-Hellman.", parts.len()), None));
+        }
+
+        Ok(DhKey(
+            DhGroup::from_str(parts[..2].join(KEY_SERIAL_DELIMITER))?,
+            NumKey::from_str(String::from(parts[2]))?,
+            NumKey::from_str(String::from(parts[3]))?
+        ))
+    }
+
+    fn serialize_str(&self) -> String
+    {
+        [self.0.serialize_str(), self.1.serialize_str(), self.2.serialize_str()].join(KEY_SERIAL_DELIMITER)
+    }
+}
+
+/// Validation d'une clé : en plus de la validation de la valeur publique, vérifie que y = g^x mod p.
+impl Validate for DhKey
+{
+    fn validate(&self) -> Result<(), Error>
+    {
+        let (p, g) = (self.0.0.value(), self.0.1.value());
+        validate_deferred(self.public().validate(), ||
+        {
+            if maths::fmodpow(g, self.1.value(), p) != *self.2.value()
+            {
+                return Err(Error::InvalidKey(String::from("la valeur publique ne correspond pas à l'exposant secret.")));
+            }
+
+            Ok(())
+        })
+    }
+}
+
+
+/// Dérivation de clé HKDF-SHA256 (RFC 5869) : extraction d'une clé pseudo-aléatoire depuis `ikm` et le sel `salt` (éventuellement vide),
+/// puis expansion en `len` octets liés au contexte `info`. Retourne `Error::InvalidParameter` si `len` est nul ou dépasse 255 * 32 octets.
+pub fn hkdf_sha256(salt: &[u8], ikm: &[u8], info: &[u8], len: usize) -> Result<Zeroizing<Vec<u8>>, Error>
+{
+    if len == 0 || len > 255 * HKDF_HASH_LEN
+    {
+        return Err(Error::InvalidParameter(format!("hkdf_sha256 : la taille de sortie doit être comprise entre 1 et {} octets.", 255 * HKDF_HASH_LEN)));
+    }
+    let hmac = | key: &[u8], parts: &[&[u8]] | -> Zeroizing<[u8; HKDF_HASH_LEN]>
+    {
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC accepte toutes les tailles de clé");
+        parts.iter().for_each(| p | mac.update(p));
+        Zeroizing::new(mac.finalize().into_bytes().into())
+    };
+
+    // Sans sel, la RFC prévoit une clé HMAC de HashLen zéros, équivalente à une clé vide
+    let prk = hmac(salt, &[ikm]);
+    let mut okm = Zeroizing::new(Vec::with_capacity(len));
+    let mut t = Zeroizing::new([0u8; HKDF_HASH_LEN]);
+    for i in 1..=len.div_ceil(HKDF_HASH_LEN)
+    {
+        let prev: &[u8] = if i == 1 { &[] } else { t.as_slice() };
+        t = hmac(prk.as_slice(), &[prev, info, &[i as u8]]);
+        let n = HKDF_HASH_LEN.min(len - okm.len());
+        okm.extend_from_slice(&t[..n]);
+    }
+
+    Ok(okm)
+}
+
+
+/// Accord de clé Diffie-Hellman sur un groupe multiplicatif modulo un nombre premier sûr : groupes ffdhe de la RFC 7919, ou groupes générés
+/// avec la recherche parallèle de nombres premiers sûrs des moteurs. Le secret partagé g^(xy) mod p n'est jamais utilisé directement : il est dérivé par HKDF.
+pub struct Dh
+{
+    rng: RngHandle,
+    policy: Policy
+}
+
+impl Dh
+{
+    /// Construit un accord de clé utilisant la source d'aléa du système.
+    pub fn new() -> Dh
+    {
+        Dh::with_rng(RngHandle::os())
+    }
+
+    /// Construit un accord de clé utilisant la source d'aléa donnée, qui doit être cryptographiquement sûre (voir `RngHandle`).
+    pub fn with_rng(rng: RngHandle) -> Dh
+    {
+        Dh
+        {
+            rng,
+            policy: Policy::new()
+        }
+    }
+
+    /// Modifie la politique de sécurité, stricte par défaut (voir `Policy::new`) : elle s'applique à la taille du module p
+    /// lors de la génération des groupes et des clés.
+    pub fn with_policy(mut self, policy: Policy) -> Dh
+    {
+        self.policy = policy;
+        self
+    }
+
+    /// Donne la politique de sécurité utilisée.
+    pub fn policy(&self) -> Policy
+    {
+        self.policy
+    }
+
+    /// Donne la source d'aléa utilisée.
+    pub fn rng(&self) -> RngHandle
+    {
+        self.rng.clone()
+    }
+
+    /// Génère un groupe non standard sur `n_threads` threads avec la recherche parallèle de nombres premiers sûrs des moteurs (voir `Rsa::try_generate_controlled`),
+    /// et retourne les statistiques de la génération. `sz_b` est la taille en octets du module p ; le générateur est g = 4, carré donc d'ordre q.
+    /// Les groupes ffdhe (voir `DhGroup::ffdhe`) sont à préférer : ils évitent une génération longue et sont connus des autres implémentations.
+    pub fn try_generate_group(&self, sz_b: u64, n_threads: u8, control: &GenControl) -> Result<(DhGroup, GenStats), Error>
+    {
+        self.policy.check_modulus_bits(sz_b * 8)?;
+        let start = Instant::now();
+        let (p, mut stats) = engines::search_safe_prime(&self.rng, sz_b, n_threads, control)?;
+        stats.elapsed = start.elapsed();
+
+        Ok((DhGroup(NumKey::from(p), NumKey::from(BigUint::from(4u8))), stats))
+    }
+
+    /// Génère une clé dans le groupe donné : l'exposant secret x est tiré dans [2, q - 1].
+    /// Retourne `Error::Policy` si le module est trop petit pour la politique de sécurité, et `Error::InvalidKey` s'il est trop petit pour contenir un exposant.
+    pub fn try_generate_key(&self, group: &DhGroup) -> Result<DhKey, Error>
+    {
+        let (p, g) = (group.0.value(), group.1.value());
+        self.policy.check_modulus_bits(p.bits())?;
+        let q = p >> 1u8;
+        if q <= BigUint::from(2u8)
+        {
+            return Err(Error::InvalidKey(String::from("Dh.generate_key : module trop petit.")));
+        }
+
+        let x = self.rng().gen_biguint_range(&BigUint::from(2u8), &q);
+        let y = maths::fmodpow(g, &x, p);
+        Ok(DhKey(group.clone(), NumKey::from(x), NumKey::from(y)))
+    }
+
+    /// Dérive un secret de `len` octets partagé avec le détenteur de la valeur publique `peer`, lié au contexte `info` (voir `hkdf_sha256`).
+    /// Le secret g^(xy) mod p, complété à gauche par des zéros jusqu'à la taille de p, sert de matériau d'entrée à la HKDF, puis est effacé.
+    /// Retourne `Error::InvalidKey` si la valeur publique n'est pas dans le même groupe ou n'appartient pas au sous-groupe d'ordre q.
+    pub fn try_derive(&self, key: &DhKey, peer: &DhPublicKey, info: &[u8], len: usize) -> Result<Zeroizing<Vec<u8>>, Error>
+    {
+        if key.0 != peer.0
+        {
+            return Err(Error::InvalidKey(String::from("Dh.derive : la valeur publique n'appartient pas au même groupe que la clé.")));
+        }
+        let p = key.0.0.value();
+        self.policy.check_modulus_bits(p.bits())?;
+        elgamal::validate_subgroup(p, peer.1.value(), "la valeur publique")?;
+
+        let mut z = maths::fmodpow(peer.1.value(), key.1.value(), p);
+        let size = (p.bits() as usize).div_ceil(8);
+        let mut ikm = Zeroizing::new(vec![0u8; size]);
+        let bytes = Zeroizing::new(z.to_bytes_be());
+        ikm[size - bytes.len()..].copy_from_slice(&bytes);
+        maths::wipe(&mut z);
+
+        hkdf_sha256(&[], &ikm, info, len)
+    }
+}
+
+impl Default for Dh
+{
+    fn default() -> Self
+    {
+        Dh::new()
+    }
+}
@@ -0,0 +1,314 @@
+use crate::{Error, engines::{self, Engine, GenControl, GenStats, RSA_MIN_MODULUS_BITS}, keys::*, maths, policy::Policy, rng::RngHandle};
+use std::time::Instant;
+use num_bigint::{BigUint, RandBigInt};
+use num_integer::Integer;
+use num_traits::{One, Zero};
+use rand::rngs::OsRng;
+
+
+/// Taille par défaut en octets du module p des clés ElGamal à générer (2048 bits).
+pub const ELGAMAL_DEF_SIZEB: u64 = 256;
+/// Nombre de tours de Miller-Rabin (témoins aléatoires) pour la validation de p et q lors de la vérification d'une clé ou d'un groupe Diffie-Hellman.
+const ELGAMAL_VALIDATE_ROUNDS: usize = 20;
+
+
+/// Clé de chiffrement ElGamal (p, g, y) : p = 2q + 1 est un nombre premier sûr, g engendre le sous-groupe d'ordre q des carrés modulo p et y = g^x mod p.
+/// Sérialisation textuelle : `p::g::y`.
+pub struct ElGamalPublicKey(pub NumKey, pub NumKey, pub NumKey);
+
+impl ElGamalPublicKey
+{
+    /// Construit une clé de chiffrement depuis le module p, le générateur g et la valeur publique y.
+    pub fn from(p: NumKey, g: NumKey, y: NumKey) -> ElGamalPublicKey
+    {
+        ElGamalPublicKey(p, g, y)
+    }
+}
+
+impl Key for ElGamalPublicKey
+{
+    const PARTS: Option<usize> = Some(3);
+
+    fn from_str(val: String) -> Result<Self, Error>
+    {
+        let parts: Vec<&str> = val.split(KEY_SERIAL_DELIMITER).collect();
+        if parts.len() != 3
+        {
+            return Err(Error::Parse(format!("ElGamalPublicKey::from_str : nombre de parties ({}) invalide pour une clé publique ElGamal.", parts.len()), None));
+        }
+
+        Ok(ElGamalPublicKey(
+            NumKey::from_str(String::from(parts[0]))?,
+            NumKey::from_str(String::from(parts[1]))?,
+            NumKey::from_str(String::from(parts[2]))?
+        ))
+    }
+
+    fn serialize_str(&self) -> String
+    {
+        [self.0.serialize_str(), self.1.serialize_str(), self.2.serialize_str()].join(KEY_SERIAL_DELIMITER)
+    }
+}
+
+/// Alias de type pour les clés de déchiffrement ElGamal, qui sont des paires de clés numériques (p, x), x étant l'exposant secret.
+pub type ElGamalPrivateKey = KeyPair<NumKey, NumKey>;
+/// Alias de type pour les clés principales ElGamal, paires composées d'une clé de chiffrement et de déchiffrement.
+pub type ElGamalKey = KeyPair<ElGamalPublicKey, ElGamalPrivateKey>;
+
+
+/// Implémentation d'un moteur cryptographique ElGamal sur le groupe des carrés modulo un nombre premier sûr p = 2q + 1.
+/// Le chiffrement est probabiliste : un même message donne un chiffré différent à chaque fois. Un nombre m (inférieur à q) est d'abord plongé
+/// dans le sous-groupe des carrés (voir `encode_subgroup`), puis chiffré en la paire (c1, c2) = (g^k, m' * y^k) mod p, avec k aléatoire ;
+/// elle est représentée par le seul nombre c1 * p + c2, deux fois plus grand que le module.
+pub struct ElGamal
+{
+    rng: RngHandle,
+    policy: Policy
+}
+
+impl ElGamal
+{
+    /// Construit un moteur ElGamal utilisant la source d'aléa du système.
+    pub fn new() -> ElGamal
+    {
+        ElGamal::with_rng(RngHandle::os())
+    }
+
+    /// Construit un moteur ElGamal utilisant la source d'aléa donnée, qui doit être cryptographiquement sûre (voir `RngHandle`).
+    pub fn with_rng(rng: RngHandle) -> ElGamal
+    {
+        ElGamal
+        {
+            rng,
+            policy: Policy::new()
+        }
+    }
+
+    /// Modifie la politique de sécurité du moteur, stricte par défaut (voir `Policy::new`) : elle s'applique à la taille du module p
+    /// lors de la génération et du chiffrement, ainsi qu'au nonce des messages.
+    pub fn with_policy(mut self, policy: Policy) -> ElGamal
+    {
+        self.policy = policy;
+        self
+    }
+
+    /// Génère une clé principale sur `n_threads` threads avec la recherche parallèle de nombres premiers sûrs des moteurs (voir `Rsa::try_generate_controlled`),
+    /// et retourne les statistiques de la génération. `sz_b` est la taille en octets du module p, dont les deux bits de poids fort sont à 1.
+    pub fn try_generate_controlled(&self, sz_b: u64, n_threads: u8, control: &GenControl) -> Result<(ElGamalKey, GenStats), Error>
+    {
+        if sz_b < 2
+        {
+            return Err(Error::InvalidParameter(String::from("ElGamal.generate : la taille de clé doit être d'au moins 2 octets.")));
+        }
+        self.policy.check_modulus_bits(sz_b * 8)?;
+        let start = Instant::now();
+
+        let (p, mut stats) = engines::search_safe_prime(&self.rng, sz_b, n_threads, control)?;
+        let key = self.key_from_prime(p);
+        stats.elapsed = start.elapsed();
+
+        Ok((key, stats))
+    }
+
+    /// Construit une clé principale depuis un nombre premier sûr p : le générateur est le carré d'un entier aléatoire, et l'exposant secret x est tiré dans [1, q - 1].
+    fn key_from_prime(&self, p: BigUint) -> ElGamalKey
+    {
+        let mut rng = self.rng();
+        let q = &p >> 1u8;
+        // h² est d'ordre q pour tout h dans [2, p - 2], puisque seuls 1 et p - 1 ont un carré égal à 1
+        let mut h = rng.gen_biguint_range(&BigUint::from(2u8), &(&p - 1u8));
+        let g = maths::fmodpow(&h, &BigUint::from(2u8), &p);
+        maths::wipe(&mut h);
+        let x = rng.gen_biguint_range(&BigUint::one(), &q);
+        let y = maths::fmodpow(&g, &x, &p);
+
+        KeyPair::from(
+            ElGamalPublicKey::from(NumKey::from(p.clone()), NumKey::from(g), NumKey::from(y)),
+            KeyPair::from(NumKey::from(p), NumKey::from(x))
+        )
+    }
+}
+
+impl Default for ElGamal
+{
+    fn default() -> Self
+    {
+        ElGamal::new()
+    }
+}
+
+/// Vérifie que `p` est un nombre premier sûr, p et q = (p - 1) / 2 étant tous deux premiers. Les témoins de Miller-Rabin sont tirés aléatoirement,
+/// un module fourni par un tiers pouvant avoir été construit pour tromper des témoins fixes (voir `maths::miller_rabin`).
+pub(crate) fn validate_safe_prime(p: &BigUint) -> Result<(), Error>
+{
+    if p.is_even() || !maths::miller_rabin_with(&mut OsRng, p, ELGAMAL_VALIDATE_ROUNDS) || !maths::miller_rabin_with(&mut OsRng, &(p >> 1u8), ELGAMAL_VALIDATE_ROUNDS)
+    {
+        return Err(Error::InvalidKey(String::from("le module n'est pas un nombre premier sûr.")));
+    }
+
+    Ok(())
+}
+
+/// Vérifie que `v` appartient au sous-groupe d'ordre q = (p - 1) / 2, sans être égal à 1.
+pub(crate) fn validate_subgroup(p: &BigUint, v: &BigUint, name: &str) -> Result<(), Error>
+{
+    let q = p >> 1u8;
+    if v <= &BigUint::one() || v >= p || !maths::fmodpow(v, &q, p).is_one()
+    {
+        return Err(Error::InvalidKey(format!("{} n'appartient pas au sous-groupe d'ordre (p - 1) / 2.", name)));
+    }
+
+    Ok(())
+}
+
+/// Plonge le nombre `m` de [0, q - 1] dans le sous-groupe d'ordre q des carrés modulo p = 2q + 1 : m + 1 s'il est un carré, p - (m + 1) sinon
+/// (-1 n'étant pas un carré, puisque p ≡ 3 mod 4). Chiffrer m directement laisserait fuir son symbole de Legendre, identique à celui de c2.
+fn encode_subgroup(p: &BigUint, m: &BigUint) -> BigUint
+{
+    let a = m + 1u8;
+    if maths::fmodpow(&a, &(p >> 1u8), p).is_one()
+    {
+        a
+    }
+    else
+    {
+        p - a
+    }
+}
+
+/// Retrouve le nombre plongé dans le sous-groupe par `encode_subgroup` : les valeurs de [1, q] sont des m + 1, celles de [q + 1, p - 1] des p - (m + 1).
+fn decode_subgroup(p: &BigUint, a: &BigUint) -> Result<BigUint, Error>
+{
+    if a.is_zero() || a >= p
+    {
+        return Err(Error::Decoding(String::from("ElGamal.run_decrypt : paire chiffrée invalide.")));
+    }
+
+    if a <= &(p >> 1u8)
+    {
+        Ok(a - 1u8)
+    }
+    else
+    {
+        Ok(p - a - 1u8)
+    }
+}
+
+/// Validation d'une clé publique ElGamal : p doit être un nombre premier sûr, g et y doivent appartenir au sous-groupe d'ordre q.
+/// Retourne `Error::WeakKey` si le module fait moins de `RSA_MIN_MODULUS_BITS` bits.
+impl Validate for ElGamalPublicKey
+{
+    fn validate(&self) -> Result<(), Error>
+    {
+        let p = self.0.value();
+        validate_safe_prime(p)?;
+        validate_subgroup(p, self.1.value(), "le générateur")?;
+        validate_subgroup(p, self.2.value(), "la valeur publique")?;
+        if p.bits() < RSA_MIN_MODULUS_BITS
+        {
+            return Err(Error::WeakKey(format!("module de {} bits, au moins {} bits sont nécessaires.", p.bits(), RSA_MIN_MODULUS_BITS)));
+        }
+
+        Ok(())
+    }
+}
+
+/// Validation d'une clé principale ElGamal : en plus de la validation de la clé publique, vérifie que les modules sont identiques et que y = g^x mod p.
+impl Validate for ElGamalKey
+{
+    fn validate(&self) -> Result<(), Error>
+    {
+        let (p, g, y, x) = (self.0.0.value(), self.0.1.value(), self.0.2.value(), self.1.1.value());
+        if p != self.1.0.value()
+        {
+            return Err(Error::InvalidKey(String::from("les modules des clés publique et privée diffèrent.")));
+        }
+        validate_deferred(self.0.validate(), ||
+        {
+            if x.is_zero() || &maths::fmodpow(g, x, p) != y
+            {
+                return Err(Error::InvalidKey(String::from("l'exposant secret ne correspond pas à la valeur publique.")));
+            }
+
+            Ok(())
+        })
+    }
+}
+
+impl Engine for ElGamal
+{
+    type EncryptionKey = ElGamalPublicKey;
+    type DecryptionKey = ElGamalPrivateKey;
+    type MainKey = ElGamalKey;
+
+    fn try_generate(&self, sz_b: u64, n_threads: u8) -> Result<Self::MainKey, Error>
+    {
+        self.try_generate_controlled(sz_b, n_threads, &GenControl::new()).map(| (key, _) | key)
+    }
+
+    fn try_gen_def(&self) -> Result<Self::MainKey, Error>
+    {
+        self.try_generate(ELGAMAL_DEF_SIZEB, engines::def_gthreads())
+    }
+
+    fn try_run_crypt(&self, num: &mut BigUint, key: &Self::EncryptionKey) -> Result<(), Error>
+    {
+        let (p, g, y) = (key.0.value(), key.1.value(), key.2.value());
+        self.policy.check_modulus_bits(p.bits())?;
+        if p < &BigUint::from(5u8)
+        {
+            return Err(Error::InvalidKey(String::from("ElGamal : module trop petit.")));
+        }
+        let q = p >> 1u8;
+        if *num >= q
+        {
+            return Err(Error::MessageTooLong);
+        }
+
+        // k est éphémère et doit rester secret : le connaître permet de retrouver le message
+        let mut k = self.rng().gen_biguint_range(&BigUint::one(), &q);
+        let c1 = maths::fmodpow(g, &k, p);
+        let mut s = maths::fmodpow(y, &k, p);
+        let mut m = encode_subgroup(p, num);
+        let c2 = (&m * &s) % p;
+        maths::wipe(&mut k);
+        maths::wipe(&mut s);
+        maths::wipe(&mut m);
+
+        *num = c1 * p + c2;
+        Ok(())
+    }
+
+    fn try_run_decrypt(&self, num: &mut BigUint, key: &Self::DecryptionKey) -> Result<(), Error>
+    {
+        let (p, x) = (key.0.value(), key.1.value());
+        if p.is_zero() || x.is_zero() || x >= &(p >> 1u8)
+        {
+            return Err(Error::InvalidKey(String::from("ElGamal : clé privée invalide.")));
+        }
+        let (c1, c2) = num.div_rem(p);
+        if c1.is_zero() || &c1 >= p
+        {
+            return Err(Error::Decoding(String::from("ElGamal.run_decrypt : paire chiffrée invalide.")));
+        }
+
+        // s^-1 = c1^(p - 1 - x) mod p, puisque c1^(p - 1) = 1
+        let mut s_inv = maths::fmodpow(&c1, &(p - 1u8 - x), p);
+        let mut m = (c2 * &s_inv) % p;
+        maths::wipe(&mut s_inv);
+        let res = decode_subgroup(p, &m);
+        maths::wipe(&mut m);
+        *num = res?;
+        Ok(())
+    }
+
+    fn rng(&self) -> RngHandle
+    {
+        self.rng.clone()
+    }
+
+    fn policy(&self) -> Policy
+    {
+        self.policy
+    }
+}
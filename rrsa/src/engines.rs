@@ -1,8 +1,11 @@
-use crate::{keys::*, maths, messages::Message};
-use std::{convert::TryInto, sync::{Arc, atomic}, thread};
+use crate::{Error, keys::*, maths, messages::Message, policy::{self, Padding, Policy}, provable::{self, Certificate}, rng::RngHandle};
+use std::{convert::TryInto, sync::{Arc, atomic}, thread, time::{Duration, Instant}};
 use crossbeam::channel;
-use num_bigint::{BigUint, RandBigInt, ToBigInt};
-use num_traits::Signed;
+use num_bigint::{BigUint, RandBigInt};
+use num_integer::Integer;
+use num_traits::{One, Zero};
+use rand::{CryptoRng, RngCore, SeedableRng, rngs::OsRng};
+use rand_chacha::ChaCha20Rng;
 
 
 /// Taille par défaut du padding (nonce) à appliquer aux nombres à chiffrer. Peut changer en fonction du message. En octets.
@@ -26,13 +29,46 @@ pub trait Engine
     /// Génère une clé principale. Les paramètres peuvent ou non être pris en compte selon l'implémentation exacte.
     /// `sz_b` est la taille en octets de la clé à générer
     /// `n_threads` est le nombre de threads à utiliser pour la génération.
-    fn generate(&self, sz_b: u64, n_threads: u8) -> Self::MainKey;
+    fn try_generate(&self, sz_b: u64, n_threads: u8) -> Result<Self::MainKey, Error>;
     /// Génère une clé principale avec des options par défaut.
-    fn gen_def(&self) -> Self::MainKey;
+    fn try_gen_def(&self) -> Result<Self::MainKey, Error>;
     /// Chiffre un nombre avec une clé de chiffrement donnée.
-    fn run_crypt(&self, num: &mut BigUint, key: &Self::EncryptionKey);
+    fn try_run_crypt(&self, num: &mut BigUint, key: &Self::EncryptionKey) -> Result<(), Error>;
     /// Déchiffre un nombre avec une clé de déchiffrement donnée.
-    fn run_decrypt(&self, num: &mut BigUint, key: &Self::DecryptionKey);
+    fn try_run_decrypt(&self, num: &mut BigUint, key: &Self::DecryptionKey) -> Result<(), Error>;
+    /// Source d'aléa utilisée par le moteur (génération de clés, padding...). Retourne un clone de la poignée, qui partage la même source.
+    fn rng(&self) -> RngHandle;
+
+    /// Politique de sécurité consultée par le moteur (voir `Policy`). Par défaut, la politique stricte (`Policy::new`) : un moteur n'autorise
+    /// les paramètres hérités que s'il le déclare explicitement, comme les chiffrements classiques.
+    fn policy(&self) -> Policy
+    {
+        Policy::new()
+    }
+
+    /// Génère une clé principale, voir `try_generate`. Panique en cas d'erreur.
+    fn generate(&self, sz_b: u64, n_threads: u8) -> Self::MainKey
+    {
+        self.try_generate(sz_b, n_threads).expect("Engine.generate")
+    }
+
+    /// Génère une clé principale avec des options par défaut, voir `try_gen_def`. Panique en cas d'erreur.
+    fn gen_def(&self) -> Self::MainKey
+    {
+        self.try_gen_def().expect("Engine.gen_def")
+    }
+
+    /// Chiffre un nombre avec une clé de chiffrement donnée, voir `try_run_crypt`. Panique en cas d'erreur.
+    fn run_crypt(&self, num: &mut BigUint, key: &Self::EncryptionKey)
+    {
+        self.try_run_crypt(num, key).expect("Engine.run_crypt")
+    }
+
+    /// Déchiffre un nombre avec une clé de déchiffrement donnée, voir `try_run_decrypt`. Panique en cas d'erreur.
+    fn run_decrypt(&self, num: &mut BigUint, key: &Self::DecryptionKey)
+    {
+        self.try_run_decrypt(num, key).expect("Engine.run_decrypt")
+    }
 
     /// Ajoute un nonce (padding) d'une taille donnée `padsize` en octets à un nombre.
     fn pad(&self, num: &mut BigUint, padsize: u32)
@@ -40,7 +76,14 @@ pub trait Engine
         let bits: u32 = padsize * 8;
 
         *num *= BigUint::from(2u8).pow(bits);
-        *num += rand::thread_rng().gen_biguint(bits.into());
+        *num += self.rng().gen_biguint(bits.into());
+    }
+
+    /// Schéma de padding appliqué par `pad` à un message dont le nonce fait `padsize` octets, contrôlé par la politique du moteur (voir `Policy::check_padding`).
+    /// Par défaut, un nonce aléatoire, ou aucun padding si `padsize` est nul : les moteurs redéfinissant `pad` doivent aussi redéfinir cette méthode.
+    fn padding(&self, padsize: u32) -> Padding
+    {
+        if padsize == 0 { Padding::Raw } else { Padding::Nonce }
     }
 
     /// Retire le nonce (padding) d'un nombre : retire en fait les derniers `padsize` octets.
@@ -50,46 +93,183 @@ pub trait Engine
     }
 
     /// Ajoute un nonce et chiffre un nombre.
-    fn encode(&self, num: &mut BigUint, key: &Self::EncryptionKey, padsize: u32)
+    fn try_encode(&self, num: &mut BigUint, key: &Self::EncryptionKey, padsize: u32) -> Result<(), Error>
     {
         self.pad(num, padsize);
-        self.run_crypt(num, key);
+        self.try_run_crypt(num, key)
     }
 
     /// Déchiffre un nombre et lui retire son nonce.
-    fn decode(&self, num: &mut BigUint, key: &Self::DecryptionKey, padsize: u32)
+    fn try_decode(&self, num: &mut BigUint, key: &Self::DecryptionKey, padsize: u32) -> Result<(), Error>
     {
-        self.run_decrypt(num, key);
+        self.try_run_decrypt(num, key)?;
         self.unpad(num, padsize);
+        Ok(())
+    }
+
+    /// Ajoute un nonce et chiffre un nombre, voir `try_encode`. Panique en cas d'erreur.
+    fn encode(&self, num: &mut BigUint, key: &Self::EncryptionKey, padsize: u32)
+    {
+        self.try_encode(num, key, padsize).expect("Engine.encode")
+    }
+
+    /// Déchiffre un nombre et lui retire son nonce, voir `try_decode`. Panique en cas d'erreur.
+    fn decode(&self, num: &mut BigUint, key: &Self::DecryptionKey, padsize: u32)
+    {
+        self.try_decode(num, key, padsize).expect("Engine.decode")
     }
 
     /// Chiffre un message avec une clé de chiffrement donnée.
-    fn encrypt(&self, message: &mut Message, key: &Self::EncryptionKey)
+    /// En cas d'erreur sur l'une des parties, le message n'est pas modifié. Retourne `Error::Policy` si le schéma de padding ou le nonce du message ne respectent pas la politique du moteur.
+    fn try_encrypt(&self, message: &mut Message, key: &Self::EncryptionKey) -> Result<(), Error>
     {
-        for part in message.parts.iter_mut()
+        self.policy().check_padding(self.padding(message.padsize), message.padsize)?;
+        let mut parts = message.parts.clone();
+        for part in parts.iter_mut()
         {
-            self.encode(part, key, message.padsize);
+            self.try_encode(part, key, message.padsize)?;
         }
+        message.parts = parts;
         message.encrypted = true;
-        message.refresh_nval();
+        message.try_refresh_nval()
     }
 
     /// Déchiffre un message avec une clé de déchiffrement donnée.
+    /// En cas d'erreur sur l'une des parties, le message n'est pas modifié.
+    fn try_decrypt(&self, message: &mut Message, key: &Self::DecryptionKey) -> Result<(), Error>
+    {
+        let mut parts = message.parts.clone();
+        for part in parts.iter_mut()
+        {
+            self.try_decode(part, key, message.padsize)?;
+        }
+        message.parts = parts;
+        message.encrypted = false;
+        message.try_refresh_nval()
+    }
+
+    /// Chiffre un message avec une clé de chiffrement donnée, voir `try_encrypt`. Panique en cas d'erreur.
+    fn encrypt(&self, message: &mut Message, key: &Self::EncryptionKey)
+    {
+        self.try_encrypt(message, key).expect("Engine.encrypt")
+    }
+
+    /// Déchiffre un message avec une clé de déchiffrement donnée, voir `try_decrypt`. Panique en cas d'erreur.
     fn decrypt(&self, message: &mut Message, key: &Self::DecryptionKey)
     {
-        for part in message.parts.iter_mut()
+        self.try_decrypt(message, key).expect("Engine.decrypt")
+    }
+}
+
+
+/// Un `Signer` est un moteur capable de signer un message avec sa clé de déchiffrement (privée), la signature étant vérifiée avec la clé de chiffrement (publique).
+/// Comme pour `Engine`, seules les opérations sur un nombre sont à implémenter ; la signature et la vérification des messages sont définies par défaut.
+pub trait Signer : Engine
+{
+    /// Signe un nombre avec une clé de déchiffrement (privée) donnée.
+    fn try_run_sign(&self, num: &mut BigUint, key: &Self::DecryptionKey) -> Result<(), Error>;
+    /// Vérifie (déchiffre) un nombre signé avec une clé de chiffrement (publique) donnée.
+    fn try_run_verify(&self, num: &mut BigUint, key: &Self::EncryptionKey) -> Result<(), Error>;
+
+    /// Signe un message avec une clé de déchiffrement (privée) donnée : chaque partie reçoit un nonce puis est signée.
+    /// En cas d'erreur sur l'une des parties, le message n'est pas modifié.
+    fn try_sign(&self, message: &mut Message, key: &Self::DecryptionKey) -> Result<(), Error>
+    {
+        self.policy().check_padding(self.padding(message.padsize), message.padsize)?;
+        let mut parts = message.parts.clone();
+        for part in parts.iter_mut()
         {
-            self.decode(part, key, message.padsize);
+            self.pad(part, message.padsize);
+            self.try_run_sign(part, key)?;
         }
+        message.parts = parts;
+        message.encrypted = true;
+        message.try_refresh_nval()
+    }
+
+    /// Vérifie un message signé avec une clé de chiffrement (publique) donnée : chaque partie est déchiffrée puis son nonce retiré.
+    /// Le message obtenu doit ensuite être comparé à l'original. En cas d'erreur sur l'une des parties, le message n'est pas modifié.
+    fn try_verify(&self, message: &mut Message, key: &Self::EncryptionKey) -> Result<(), Error>
+    {
+        self.policy().check_padding(self.padding(message.padsize), message.padsize)?;
+        let mut parts = message.parts.clone();
+        for part in parts.iter_mut()
+        {
+            self.try_run_verify(part, key)?;
+            self.unpad(part, message.padsize);
+        }
+        message.parts = parts;
         message.encrypted = false;
-        message.refresh_nval();
+        message.try_refresh_nval()
+    }
+
+    /// Signe un message, voir `try_sign`. Panique en cas d'erreur.
+    fn sign(&self, message: &mut Message, key: &Self::DecryptionKey)
+    {
+        self.try_sign(message, key).expect("Signer.sign")
+    }
+
+    /// Vérifie un message signé, voir `try_verify`. Panique en cas d'erreur.
+    fn verify(&self, message: &mut Message, key: &Self::EncryptionKey)
+    {
+        self.try_verify(message, key).expect("Signer.verify")
+    }
+}
+
+/// Un `DetachedSigner` produit des signatures distinctes du message (Ed25519 par exemple) : contrairement à `Signer`, la vérification ne restitue pas
+/// le message, elle contrôle la signature d'un message connu. Les messages sont des octets bruts, hachés par le schéma de signature lui-même.
+pub trait DetachedSigner
+{
+    /// Type de la clé de signature (privée).
+    type SigningKey : Key;
+    /// Type de la clé de vérification (publique).
+    type VerifyingKey : Key;
+
+    /// Signe un message avec une clé de signature donnée et retourne la signature encodée.
+    fn try_sign_detached(&self, message: &[u8], key: &Self::SigningKey) -> Result<Vec<u8>, Error>;
+    /// Vérifie la signature d'un message avec une clé de vérification donnée. Retourne `Error::Decoding` si la signature est invalide.
+    fn try_verify_detached(&self, message: &[u8], signature: &[u8], key: &Self::VerifyingKey) -> Result<(), Error>;
+
+    /// Signe un message, voir `try_sign_detached`. Panique en cas d'erreur.
+    fn sign_detached(&self, message: &[u8], key: &Self::SigningKey) -> Vec<u8>
+    {
+        self.try_sign_detached(message, key).expect("DetachedSigner.sign_detached")
     }
 }
 
 
 /// Implémentation d'un moteur de chiffrement pour un codage césar
 /// Une seule clé numérique aléatoire joue le rôle de clé de chiffrement / principale / déchiffrement.
-pub struct Cesar;
+pub struct Cesar
+{
+    rng: RngHandle
+}
+
+impl Cesar
+{
+    /// Construit un moteur césar utilisant la source d'aléa du système.
+    pub fn new() -> Cesar
+    {
+        Cesar::with_rng(RngHandle::os())
+    }
+
+    /// Construit un moteur césar utilisant la source d'aléa donnée.
+    pub fn with_rng(rng: RngHandle) -> Cesar
+    {
+        Cesar
+        {
+            rng
+        }
+    }
+}
+
+impl Default for Cesar
+{
+    fn default() -> Self
+    {
+        Cesar::new()
+    }
+}
 
 impl Engine for Cesar
 {
@@ -97,129 +277,1052 @@ impl Engine for Cesar
     type DecryptionKey = NumKey;
     type MainKey = NumKey;
 
-    fn generate(&self, sz_b: u64, _: u8) -> Self::MainKey {
-        NumKey::from(rand::thread_rng().gen_biguint(sz_b * 8))
+    fn try_generate(&self, sz_b: u64, _: u8) -> Result<Self::MainKey, Error> {
+        Ok(NumKey::from(self.rng().gen_biguint(sz_b * 8)))
+    }
+
+    fn try_gen_def(&self) -> Result<Self::MainKey, Error>
+    {
+        self.try_generate(8, 0)
+    }
+
+    fn try_run_crypt(&self, num: &mut BigUint, key: &Self::EncryptionKey) -> Result<(), Error> {
+        *num += key.value();
+        Ok(())
+    }
+
+    fn try_run_decrypt(&self, num: &mut BigUint, key: &Self::DecryptionKey) -> Result<(), Error> {
+        if &*num < key.value()
+        {
+            return Err(Error::Decoding(String::from("Cesar.run_decrypt : valeur chiffrée inférieure à la clé.")));
+        }
+        *num -= key.value();
+        Ok(())
+    }
+
+    fn rng(&self) -> RngHandle
+    {
+        self.rng.clone()
+    }
+
+    /// Chiffrement de démonstration sans aucune sécurité : les paramètres hérités sont autorisés.
+    fn policy(&self) -> Policy
+    {
+        Policy::legacy()
     }
+}
 
-    fn gen_def(&self) -> Self::MainKey 
+
+/// Jeton d'annulation d'une génération de clé. Les clones partagent le même état : l'un peut être passé à la génération, l'autre conservé pour l'interrompre.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<atomic::AtomicBool>);
+
+impl CancelToken
+{
+    /// Construit un jeton non annulé.
+    pub fn new() -> CancelToken
     {
-        self.generate(8, 0)
+        CancelToken::default()
     }
 
-    fn run_crypt(&self, num: &mut BigUint, key: &Self::EncryptionKey) {
-        *num += &key.value;
+    /// Demande l'interruption des générations utilisant ce jeton (ou l'un de ses clones).
+    pub fn cancel(&self)
+    {
+        self.0.store(true, atomic::Ordering::Relaxed);
     }
 
-    fn run_decrypt(&self, num: &mut BigUint, key: &Self::DecryptionKey) {
-        *num -= &key.value;
+    /// Indique si l'interruption a été demandée.
+    pub fn is_cancelled(&self) -> bool
+    {
+        self.0.load(atomic::Ordering::Relaxed)
     }
 }
 
+/// Observateur de la progression d'une génération de clé. Les méthodes sont appelées depuis les threads de génération et doivent donc rester rapides.
+/// Par défaut, elles ne font rien.
+pub trait GenObserver : Sync
+{
+    /// Appelée après le test de primalité d'un entier candidat, avec le nombre total de candidats testés jusque-là.
+    fn candidate_tested(&self, _tested: u64) {}
+    /// Appelée lorsqu'un facteur premier est retenu, avec le nombre de facteurs trouvés et le nombre de facteurs attendus.
+    fn prime_found(&self, _found: usize, _total: usize) {}
+}
+
+/// Contrôle d'une génération de clé : observateur de progression, jeton d'annulation et date limite, tous optionnels.
+#[derive(Default)]
+pub struct GenControl<'a>
+{
+    observer: Option<&'a dyn GenObserver>,
+    cancel: Option<CancelToken>,
+    deadline: Option<Instant>
+}
+
+impl<'a> GenControl<'a>
+{
+    /// Construit un contrôle sans observateur, sans annulation et sans limite de temps.
+    pub fn new() -> GenControl<'a>
+    {
+        GenControl::default()
+    }
+
+    /// Notifie la progression de la génération à l'observateur donné.
+    pub fn with_observer(mut self, observer: &'a dyn GenObserver) -> GenControl<'a>
+    {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Interrompt la génération dès que le jeton donné est annulé.
+    pub fn with_cancel(mut self, token: CancelToken) -> GenControl<'a>
+    {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Interrompt la génération si elle n'est pas terminée à la date donnée.
+    pub fn with_deadline(mut self, deadline: Instant) -> GenControl<'a>
+    {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Interrompt la génération si elle n'est pas terminée après la durée donnée (à partir de maintenant).
+    pub fn with_timeout(self, timeout: Duration) -> GenControl<'a>
+    {
+        self.with_deadline(Instant::now() + timeout)
+    }
+
+    /// Retourne `Error::Cancelled` si le jeton a été annulé ou si la date limite est dépassée.
+    fn check(&self) -> Result<(), Error>
+    {
+        if self.cancel.as_ref().is_some_and(CancelToken::is_cancelled)
+        {
+            return Err(Error::Cancelled(String::from("génération annulée.")));
+        }
+        if self.deadline.is_some_and(| d | Instant::now() >= d)
+        {
+            return Err(Error::Cancelled(String::from("délai de génération dépassé.")));
+        }
+        Ok(())
+    }
+
+    fn candidate_tested(&self, tested: u64)
+    {
+        if let Some(observer) = self.observer
+        {
+            observer.candidate_tested(tested);
+        }
+    }
+
+    fn prime_found(&self, found: usize, total: usize)
+    {
+        if let Some(observer) = self.observer
+        {
+            observer.prime_found(found, total);
+        }
+    }
+}
+
+/// Observateur relayant la progression d'une recherche partielle à l'observateur d'une génération en plusieurs recherches :
+/// les compteurs de la recherche partielle sont décalés de ceux des recherches précédentes.
+struct GenOffset<'a>
+{
+    inner: &'a dyn GenObserver,
+    found: usize,
+    total: usize,
+    tested: u64
+}
+
+impl GenObserver for GenOffset<'_>
+{
+    fn candidate_tested(&self, tested: u64)
+    {
+        self.inner.candidate_tested(self.tested + tested);
+    }
+
+    fn prime_found(&self, found: usize, _total: usize)
+    {
+        self.inner.prime_found(self.found + found, self.total);
+    }
+}
+
+/// Statistiques d'une génération de clé.
+#[derive(Clone, Debug, Default)]
+pub struct GenStats
+{
+    /// Nombre d'entiers candidats dont la primalité a été testée.
+    pub candidates: u64,
+    /// Temps écoulé pour trouver chaque facteur premier, depuis le précédent (ou depuis le début de la génération pour le premier).
+    pub prime_times: Vec<Duration>,
+    /// Durée totale de la génération.
+    pub elapsed: Duration
+}
+
+
+/// Intervalle maximal entre deux vérifications de l'annulation et de la date limite par le thread appelant pendant la génération.
+const GEN_POLL: Duration = Duration::from_millis(10);
+
+/// Recherche parallèle de `count` nombres premiers distincts sur `n_threads` threads, partagée par les moteurs générant leurs clés depuis des nombres premiers.
+/// Chaque thread tire ses propres entiers candidats depuis un clone de `rng` et les passe à `candidate`, qui retourne le nombre premier retenu
+/// (le candidat lui-même ou un nombre qui en dérive), ou `None` en effaçant le candidat s'il est rejeté. Aucun candidat n'est mis en attente :
+/// la mémoire utilisée ne dépend que du nombre de threads. La progression est notifiée à l'observateur de `control`, et la recherche s'interrompt
+/// avec `Error::Cancelled` dès que son jeton est annulé ou que sa date limite est dépassée ; tous les threads sont joints avant le retour.
+pub(crate) fn search_primes<F>(rng: &RngHandle, count: usize, n_threads: u8, control: &GenControl, candidate: F) -> Result<(Vec<BigUint>, GenStats), Error>
+where F : Fn(&mut RngHandle) -> Option<BigUint> + Sync
+{
+    if count == 0 || n_threads == 0
+    {
+        return Err(Error::InvalidParameter(String::from("search_primes : le nombre de nombres premiers et de threads doivent être non nuls.")));
+    }
+    let start = Instant::now();
+
+    // f_xx est un canal borné permettant de passer les nombres premiers trouvés : un thread qui en trouve alors que le canal est plein attend qu'il se libère
+    let (f_tx, f_rx) = channel::bounded(count);
+    let tested = atomic::AtomicU64::new(0);
+    let done = atomic::AtomicBool::new(false);
+
+    let mut prime_times = Vec::with_capacity(count);
+    let outcome = thread::scope(| s |
+    {
+        for _ in 0..n_threads
+        {
+            let f_tx_c = f_tx.clone();
+            let mut rng = rng.clone();
+            let (tested, done, candidate) = (&tested, &done, &candidate);
+            s.spawn(move ||
+            {
+                while !done.load(atomic::Ordering::Relaxed)
+                {
+                    let prime = candidate(&mut rng);
+                    control.candidate_tested(tested.fetch_add(1, atomic::Ordering::Relaxed) + 1);
+                    if let Some(prime) = prime
+                    {
+                        if let Err(channel::SendError(mut prime)) = f_tx_c.send(prime)
+                        {
+                            maths::wipe(&mut prime);
+                            break;
+                        }
+                    }
+                }
+            });
+        }
+        drop(f_tx);
+
+        // Le thread courant récupère les nombres premiers trouvés, jusqu'à en avoir assez ou être interrompu
+        let mut primes: Vec<BigUint> = Vec::with_capacity(count);
+        let mut last = start;
+        let outcome = loop
+        {
+            if let Err(e) = control.check()
+            {
+                break Err(e);
+            }
+            match f_rx.recv_timeout(GEN_POLL)
+            {
+                // Un nombre déjà retenu est rejeté : les nombres premiers retournés doivent être distincts
+                Ok(mut prime) if primes.contains(&prime) => maths::wipe(&mut prime),
+                Ok(prime) => {
+                    primes.push(prime);
+                    prime_times.push(last.elapsed());
+                    last = Instant::now();
+                    control.prime_found(primes.len(), count);
+                    if primes.len() == count
+                    {
+                        break Ok(());
+                    }
+                },
+                Err(channel::RecvTimeoutError::Timeout) => (),
+                Err(channel::RecvTimeoutError::Disconnected) => break Err(Error::Generation(String::from("search_primes : erreur dans la réception des nombres premiers.")))
+            }
+        };
+
+        // Arrêt des threads : le canal est vidé (et les nombres premiers surnuméraires effacés) jusqu'à ce que tous les threads aient terminé
+        done.store(true, atomic::Ordering::Relaxed);
+        while let Ok(mut extra) = f_rx.recv()
+        {
+            maths::wipe(&mut extra);
+        }
+        match outcome
+        {
+            Ok(()) => Ok(primes),
+            Err(e) => {
+                primes.iter_mut().for_each(maths::wipe);
+                Err(e)
+            }
+        }
+    });
+
+    Ok((outcome?, GenStats
+    {
+        candidates: tested.load(atomic::Ordering::Relaxed),
+        prime_times,
+        elapsed: start.elapsed()
+    }))
+}
+
+/// Nombre de tours de Miller-Rabin (témoins aléatoires) appliqués aux candidats lors de la génération des facteurs premiers d'une clé.
+pub(crate) const GEN_PRIME_ROUNDS: usize = 20;
+/// Borne des petits nombres premiers utilisés pour écarter rapidement les candidats q tels que q ou 2q + 1 est composé.
+const SAFE_PRIME_SIEVE_LIMIT: u32 = 2048;
+
+/// Recherche parallèle (voir `search_primes`) d'un nombre premier sûr p = 2q + 1 de `sz_b` octets, dont les deux bits de poids fort sont à 1, q étant lui aussi premier.
+/// Chaque thread tire des candidats q et ne retient que ceux pour lesquels q et p sont tous deux premiers.
+pub(crate) fn search_safe_prime(rng: &RngHandle, sz_b: u64, n_threads: u8, control: &GenControl) -> Result<(BigUint, GenStats), Error>
+{
+    if sz_b < 2
+    {
+        return Err(Error::InvalidParameter(String::from("search_safe_prime : la taille doit être d'au moins 2 octets.")));
+    }
+
+    let sieve = maths::small_primes(SAFE_PRIME_SIEVE_LIMIT);
+    let (mut primes, stats) = search_primes(rng, 1, n_threads, control, | rng |
+    {
+        // q a un bit de moins que p, et ses deux bits de poids fort à 1
+        let mut q = (maths::rand_primelike_with(rng, sz_b) >> 1u8) | BigUint::one();
+        // q et 2q + 1 ne doivent être divisibles par aucun petit nombre premier r, soit q mod r différent de 0 et de (r - 1) / 2
+        let sieved = sieve.iter().all(| &r | { let m = &q % r; (!m.is_zero() && m != BigUint::from((r - 1) / 2)) || q == BigUint::from(r) });
+        if sieved && maths::miller_rabin_with(rng, &q, GEN_PRIME_ROUNDS)
+        {
+            let p = (&q << 1u8) + 1u8;
+            if maths::miller_rabin_with(rng, &p, GEN_PRIME_ROUNDS)
+            {
+                maths::wipe(&mut q);
+                return Some(p);
+            }
+        }
+        maths::wipe(&mut q);
+        None
+    })?;
+
+    Ok((primes.pop().unwrap(), stats))
+}
 
 /// Taille par défaut des entiers premiers (p et q) à générer pour RSA. Pour du RSA-2048 (par défaut), on génère 128 octets.
 pub const RSA_DEF_PRIME_SIZEB: u64 = 128;
-/// Nombre de threads par défaut pour la génération RSA. Ils ne sont utilisés que pour la vérification, très consommatrice en temps processeur.
+/// Nombre de threads par défaut pour la génération RSA. Chacun tire ses propres entiers candidats et en teste la primalité, très consommatrice en temps processeur.
 /// Le nombre par défaut de 4 correspond aux processeurs modernes dont la plupart ont quatre coeurs.
 /// Ce nombre n'est utilisé qu'en cas d'échec de l'obtention du nombre de coeurs du processeur de la machine locale ; sinon ce dernier est utilisé (par défaut) afin de paralléliser au maximum la génération.
 pub const RSA_DEF_GEN_THREADS: u8 = 4;
 
+/// Donne le nombre par défaut de threads de génération pour cette machine, commun à tous les moteurs : le nombre de coeurs du processeur,
+/// ou `RSA_DEF_GEN_THREADS` s'il ne peut pas être obtenu.
+pub fn def_gthreads() -> u8
+{
+    num_cpus::get().try_into().unwrap_or(RSA_DEF_GEN_THREADS)
+}
+
+/// Taille minimale en bits du module d'une clé pour qu'elle soit considérée comme sûre par `Validate` : celle de la politique par défaut,
+/// afin qu'une clé validée ne soit pas ensuite refusée par les moteurs.
+pub const RSA_MIN_MODULUS_BITS: u64 = policy::POLICY_MIN_MODULUS_BITS;
+/// Nombre maximal de facteurs premiers d'une clé RSA multi-premiers (RFC 8017). Au delà, les facteurs deviennent trop petits pour une taille de module donnée.
+pub const RSA_MAX_PRIMES: usize = 4;
+/// Taille minimale en bits de chaque facteur premier d'une clé RSA générée.
+const RSA_MIN_PRIME_BITS: u64 = 8;
+/// Exposant public des clés générées en mode FIPS 186-5.
+const RSA_FIPS_EXPONENT: u32 = 65537;
+/// Tailles de module acceptées en mode FIPS 186-5, avec la taille en bits des nombres premiers auxiliaires correspondants (FIPS 186-5, table A.1 : plus de 140, 170 et 200 bits).
+const RSA_FIPS_SIZES: [(u64, u64); 3] = [(2048, 141), (3072, 171), (4096, 201)];
+/// Écart minimal entre p et q en mode FIPS 186-5 : |p - q| > 2^(nlen/2 - RSA_FIPS_MIN_GAP_SHIFT).
+const RSA_FIPS_MIN_GAP_SHIFT: u64 = 100;
+/// Nombre de tours de Miller-Rabin (témoins aléatoires) pour les nombres premiers auxiliaires en mode FIPS 186-5, au moins celui exigé par FIPS 186-5 (table B.1).
+const RSA_FIPS_AUX_ROUNDS: usize = 44;
+/// Nombre de tours de Miller-Rabin (témoins aléatoires) pour les facteurs p et q en mode FIPS 186-5, au moins celui exigé par FIPS 186-5 (table B.1).
+const RSA_FIPS_PRIME_ROUNDS: usize = 5;
+/// Nombre de tours de Miller-Rabin (témoins aléatoires) utilisés pour vérifier la primalité des facteurs lors de la validation d'une clé.
+const RSA_VALIDATE_ROUNDS: usize = 20;
+
 /// Alias de type pour les clés de chiffrement RSA, qui sont des paires de clés numériques (n, e).
 pub type PublicKey = KeyPair<NumKey, NumKey>;
-/// Alias de type pour les clés de déchiffrement RSA, qui sont des paires de clés numériques (n, d).
-pub type PrivateKey = KeyPair<NumKey, NumKey>;
+
+/// Clé de déchiffrement RSA (n, d, facteurs premiers). Les facteurs premiers sont optionnels : s'ils sont connus (clés multi-premiers),
+/// les opérations privées utilisent le théorème des restes chinois, nettement plus rapide pour les grands modules.
+/// Sérialisation textuelle : `n::d`, ou `n::d::r1::r2::...` si les facteurs sont connus.
+pub struct PrivateKey(pub NumKey, pub NumKey, pub Vec<NumKey>);
+
+impl PrivateKey
+{
+    /// Construit une clé de déchiffrement (n, d) sans facteurs premiers.
+    pub fn from(n: NumKey, d: NumKey) -> PrivateKey
+    {
+        PrivateKey(n, d, Vec::new())
+    }
+
+    /// Construit une clé de déchiffrement (n, d) avec les facteurs premiers du module, dans l'ordre utilisé pour le théorème des restes chinois.
+    pub fn with_primes(n: NumKey, d: NumKey, primes: Vec<NumKey>) -> PrivateKey
+    {
+        PrivateKey(n, d, primes)
+    }
+}
+
+impl Key for PrivateKey
+{
+    fn from_str(val: String) -> Result<Self, Error>
+    {
+        let parts: Vec<&str> = val.split(KEY_SERIAL_DELIMITER).collect();
+        // n et d, puis éventuellement au moins deux facteurs premiers
+        if parts.len() < 2 || parts.len() == 3 || parts.len() > 2 + RSA_MAX_PRIMES
+        {
+            return Err(Error::Parse(format!("PrivateKey::from_str : nombre de parties ({}) invalide pour une clé privée RSA.", parts.len()), None));
+        }
+
+        let mut nums = Vec::with_capacity(parts.len());
+        for part in parts
+        {
+            nums.push(NumKey::from_str(String::from(part))?);
+        }
+        let primes = nums.split_off(2);
+        let d = nums.pop().unwrap();
+        let n = nums.pop().unwrap();
+
+        Ok(PrivateKey(n, d, primes))
+    }
+
+    fn serialize_str(&self) -> String
+    {
+        let mut parts = vec![self.0.serialize_str(), self.1.serialize_str()];
+        parts.extend(self.2.iter().map(NumKey::serialize_str));
+
+        parts.join(KEY_SERIAL_DELIMITER)
+    }
+}
 /// Alias de type pour les clés principales RSA, qui sont des paires composés d'une clé de chiffrement et déchiffrement RSA (publique et privée).
 /// Somme toute, une clé principale RSA est une paire de paire de clés numériques.
 pub type RsaKey = KeyPair<PublicKey, PrivateKey>;
 
 /// Implémentation d'un moteur cryptographique RSA complet.
-pub struct Rsa;
+/// La source d'aléa du moteur fournit les entiers candidats lors de la génération de clé, ainsi que le padding des messages.
+pub struct Rsa
+{
+    rng: RngHandle,
+    policy: Policy,
+    primes: usize,
+    fips186: bool
+}
 
 impl Rsa
 {
-    /// Donne le nombre par défaut de threads à utiliser pour cette machine
+    /// Construit un moteur RSA utilisant la source d'aléa du système.
+    pub fn new() -> Rsa
+    {
+        Rsa::with_rng(RngHandle::os())
+    }
+
+    /// Construit un moteur RSA utilisant la source d'aléa donnée, qui doit être cryptographiquement sûre (voir `RngHandle`).
+    pub fn with_rng(rng: RngHandle) -> Rsa
+    {
+        Rsa
+        {
+            rng,
+            policy: Policy::new(),
+            primes: 2,
+            fips186: false
+        }
+    }
+
+    /// Modifie la politique de sécurité du moteur, stricte par défaut (voir `Policy::new`) : elle s'applique à la génération des clés (taille du module, exposant public),
+    /// au chiffrement et à la vérification de signatures (taille et exposant de la clé publique, nonce des messages).
+    /// `Policy::legacy()` permet d'utiliser des paramètres hérités, comme de petites clés.
+    pub fn with_policy(mut self, policy: Policy) -> Rsa
+    {
+        self.policy = policy;
+        self
+    }
+
+    /// Modifie le nombre de facteurs premiers des clés générées : 2 par défaut, jusqu'à `RSA_MAX_PRIMES` pour des clés multi-premiers (RFC 8017).
+    /// La taille du module reste la même, les facteurs étant plus petits. Un nombre invalide est signalé lors de la génération.
+    pub fn with_primes(mut self, count: usize) -> Rsa
+    {
+        self.primes = count;
+        self
+    }
+
+    /// Nombre de facteurs premiers des clés générées.
+    pub fn primes(&self) -> usize
+    {
+        self.primes
+    }
+
+    /// Active ou désactive la génération de clés conformes à FIPS 186-5 (annexe B.3.6, nombres probablement premiers avec conditions) :
+    /// e = 65537, facteurs de la moitié exacte du module construits à partir de nombres premiers auxiliaires, |p - q| > 2^(nlen/2 - 100) et d > 2^(nlen/2).
+    /// Seuls les modules de 2048, 3072 et 4096 bits à deux facteurs sont alors acceptés ; la génération se fait dans le thread courant.
+    pub fn with_fips186(mut self, enabled: bool) -> Rsa
+    {
+        self.fips186 = enabled;
+        self
+    }
+
+    /// Indique si les clés générées sont conformes à FIPS 186-5, voir `with_fips186`.
+    pub fn fips186(&self) -> bool
+    {
+        self.fips186
+    }
+
+    /// Donne le nombre par défaut de threads à utiliser pour cette machine, voir `engines::def_gthreads`
     pub fn def_gthreads(&self) -> u8
     {
-        num_cpus::get().try_into().unwrap_or(RSA_DEF_GEN_THREADS)
+        def_gthreads()
     } 
-}
 
-impl Engine for Rsa
-{
-    type EncryptionKey = PublicKey;
-    type DecryptionKey = PrivateKey;
-    type MainKey = RsaKey;
+    /// Génère une clé principale en tirant les entiers candidats depuis la source d'aléa `rng`, fournie par l'appelant.
+    /// La génération se fait dans le thread courant : elle est donc reproductible, la même source initialisée de la même manière donnant toujours la même clé.
+    /// `sz_b` est la taille en octets des facteurs premiers p et q (le module fait donc `2 * sz_b` octets, quel que soit le nombre de facteurs, voir `prime_bits`).
+    pub fn try_generate_with_rng<R: RngCore + CryptoRng>(&self, sz_b: u64, rng: &mut R) -> Result<RsaKey, Error>
+    {
+        if self.fips186
+        {
+            return self.generate_fips186(sz_b, rng, &GenControl::new()).map(| (key, _) | key);
+        }
+        let sizes = self.prime_bits(sz_b)?;
 
-    fn generate(&self, sz_b: u64, n_threads: u8) -> Self::MainKey 
+        let mut primes: Vec<BigUint> = Vec::with_capacity(self.primes);
+        while primes.len() < self.primes
+        {
+            let mut candidate = maths::rand_primelike_bits_with(rng, sizes[primes.len()], self.prime_top());
+            if maths::miller_rabin_with(rng, &candidate, GEN_PRIME_ROUNDS) && !primes.contains(&candidate)
+            {
+                primes.push(candidate);
+            }
+            else
+            {
+                maths::wipe(&mut candidate);
+            }
+        }
+
+        self.key_from_primes(primes)
+    }
+
+    /// Génère une clé principale depuis la source d'aléa `rng`, voir `try_generate_with_rng`.
+    pub fn generate_with_rng<R: RngCore + CryptoRng>(&self, sz_b: u64, rng: &mut R) -> RsaKey
     {
-        // g_xx est un canal permettant de passer les entiers dont la primalité est à tester
-        let (g_tx, g_rx) = channel::unbounded();
-        // f_xx est un canal permettant de passer des nombres premiers satisfaisant toutes les contraintes
-        let (f_tx, f_rx) = channel::unbounded();
-        let working = Arc::new(atomic::AtomicBool::new(true));
-        
-        for _ in 0..n_threads
+        self.try_generate_with_rng(sz_b, rng).expect("Rsa.generate_with_rng")
+    }
+
+    /// Génère de manière déterministe une clé principale depuis une graine de 32 octets (ChaCha20) : la même graine donne toujours la même clé.
+    /// La graine doit être secrète et aléatoire, puisqu'elle permet de reconstruire la clé privée.
+    pub fn try_generate_from_seed(&self, sz_b: u64, seed: [u8; 32]) -> Result<RsaKey, Error>
+    {
+        self.try_generate_with_rng(sz_b, &mut ChaCha20Rng::from_seed(seed))
+    }
+
+    /// Génère de manière déterministe une clé principale depuis une graine, voir `try_generate_from_seed`.
+    pub fn generate_from_seed(&self, sz_b: u64, seed: [u8; 32]) -> RsaKey
+    {
+        self.try_generate_from_seed(sz_b, seed).expect("Rsa.generate_from_seed")
+    }
+
+    /// Génère une clé principale sur `n_threads` threads en notifiant la progression à l'observateur de `control`, et retourne les statistiques de la génération.
+    /// Chaque thread tire ses propres entiers candidats depuis la source d'aléa du moteur et en teste la primalité : aucun candidat n'est mis en attente,
+    /// la mémoire utilisée ne dépend donc que du nombre de threads. Les facteurs premiers retenus sont tous distincts.
+    /// La génération s'interrompt avec `Error::Cancelled` dès que le jeton de `control` est annulé ou que sa date limite est dépassée.
+    /// Tous les threads sont joints avant le retour : un test de primalité en cours est mené à son terme, ce qui peut retarder l'interruption pour les grandes tailles.
+    pub fn try_generate_controlled(&self, sz_b: u64, n_threads: u8, control: &GenControl) -> Result<(RsaKey, GenStats), Error>
+    {
+        if sz_b == 0 || n_threads == 0
         {
-            let g_rx_c = g_rx.clone();
-            let f_tx_c = f_tx.clone();
-            let f_rx_c = f_rx.clone();
-            let working_f_c = working.clone();
+            return Err(Error::InvalidParameter(String::from("Rsa.generate : la taille de clé et le nombre de threads doivent être non nuls.")));
+        }
+        if self.fips186
+        {
+            return self.generate_fips186(sz_b, &mut self.rng(), control);
+        }
+        let sizes = self.prime_bits(sz_b)?;
+        let start = Instant::now();
 
-            thread::spawn(move || {
-                let mut temp;
-                while working_f_c.load(atomic::Ordering::Relaxed)
+        // Les facteurs de même taille sont recherchés ensemble ; le dernier, plus grand lorsque la taille du module n'est pas divisible
+        // par le nombre de facteurs, fait l'objet d'une seconde recherche. Des tailles différentes garantissent des facteurs distincts.
+        let mut primes: Vec<BigUint> = Vec::with_capacity(self.primes);
+        let mut stats = GenStats { candidates: 0, prime_times: Vec::with_capacity(self.primes), elapsed: Duration::ZERO };
+        for group in sizes.chunk_by(| a, b | a == b)
+        {
+            let progress = control.observer.map(| inner | GenOffset { inner, found: primes.len(), total: self.primes, tested: stats.candidates });
+            let part = GenControl { observer: progress.as_ref().map(| p | p as &dyn GenObserver), cancel: control.cancel.clone(), deadline: control.deadline };
+            let found = search_primes(&self.rng, group.len(), n_threads, &part, | rng |
+            {
+                let mut candidate = maths::rand_primelike_bits_with(rng, group[0], self.prime_top());
+                if maths::miller_rabin_with(rng, &candidate, GEN_PRIME_ROUNDS)
                 {
-                    // On prend un nombre premier généré
-                    temp = g_rx_c.recv().unwrap();
-                    // On fait le test de primalité
-                    if maths::isprime(&temp)
+                    return Some(candidate);
+                }
+                maths::wipe(&mut candidate);
+                None
+            });
+            match found
+            {
+                Ok((found, part_stats)) => {
+                    primes.extend(found);
+                    stats.candidates += part_stats.candidates;
+                    stats.prime_times.extend(part_stats.prime_times);
+                },
+                Err(e) => {
+                    primes.iter_mut().for_each(maths::wipe);
+                    return Err(e);
+                }
+            }
+        }
+        let key = self.key_from_primes(primes)?;
+        stats.elapsed = start.elapsed();
+
+        Ok((key, stats))
+    }
+
+    /// Génère une clé principale dont les facteurs premiers sont prouvés (voir `provable`), plutôt que seulement probablement premiers,
+    /// et retourne le certificat de primalité de chacun d'eux, dans l'ordre des facteurs. La génération se fait dans le thread courant.
+    /// Les certificats contiennent les facteurs premiers du module : ils doivent rester aussi secrets que la clé privée.
+    pub fn try_generate_provable(&self, sz_b: u64) -> Result<(RsaKey, Vec<Certificate>), Error>
+    {
+        if self.fips186
+        {
+            return Err(Error::InvalidParameter(String::from("Rsa.generate_provable : le mode FIPS 186-5 utilise des nombres probablement premiers avec conditions.")));
+        }
+        let sizes = self.prime_bits(sz_b)?;
+        let mut rng = self.rng();
+
+        let mut certs: Vec<Certificate> = Vec::with_capacity(self.primes);
+        while certs.len() < self.primes
+        {
+            let cert = provable::provable_prime_top_with(&mut rng, sizes[certs.len()], self.prime_top())?;
+            if certs.iter().all(| c | c.prime() != cert.prime())
+            {
+                certs.push(cert);
+            }
+        }
+        let key = self.key_from_primes(certs.iter().map(| c | c.prime().clone()).collect())?;
+
+        Ok((key, certs))
+    }
+
+    /// Génère une clé principale dont les facteurs premiers sont prouvés, voir `try_generate_provable`.
+    pub fn generate_provable(&self, sz_b: u64) -> (RsaKey, Vec<Certificate>)
+    {
+        self.try_generate_provable(sz_b).expect("Rsa.generate_provable")
+    }
+
+    /// Génération FIPS 186-5 (annexe B.3.6), voir `with_fips186`. Le module fait `16 * sz_b` bits.
+    fn generate_fips186<R: RngCore + ?Sized>(&self, sz_b: u64, rng: &mut R, control: &GenControl) -> Result<(RsaKey, GenStats), Error>
+    {
+        let nlen = sz_b * 16;
+        let aux_bits = match RSA_FIPS_SIZES.iter().find(| &&(bits, _) | bits == nlen)
+        {
+            Some(&(_, aux_bits)) => aux_bits,
+            None => return Err(Error::InvalidParameter(format!("Rsa.generate : le mode FIPS 186-5 n'accepte que des modules de 2048, 3072 ou 4096 bits ({} bits demandés).", nlen)))
+        };
+        if self.primes != 2
+        {
+            return Err(Error::InvalidParameter(String::from("Rsa.generate : le mode FIPS 186-5 n'accepte que des clés à deux facteurs premiers.")));
+        }
+
+        self.policy.check_modulus_bits(nlen)?;
+
+        let half = nlen / 2;
+        let e = BigUint::from(RSA_FIPS_EXPONENT);
+        self.policy.check_exponent(&e)?;
+        // Les facteurs sont compris entre √2 * 2^(nlen/2 - 1) (arrondi à l'entier supérieur) et 2^(nlen/2) - 1, ce qui garantit un module de nlen bits exactement
+        let range = ((BigUint::one() << (nlen - 1)).sqrt() + 1u8, BigUint::one() << half);
+        let min_gap = BigUint::one() << (half - RSA_FIPS_MIN_GAP_SHIFT);
+        let dist = | a: &BigUint, b: &BigUint | if a > b { a - b } else { b - a };
+
+        let start = Instant::now();
+        let mut stats = GenStats::default();
+        loop
+        {
+            let mut last = Instant::now();
+            stats.prime_times.clear();
+            let (mut p, mut xp) = Rsa::fips186_prime(rng, &e, aux_bits, &range, control, &mut stats)?;
+            stats.prime_times.push(last.elapsed());
+            last = Instant::now();
+            control.prime_found(1, 2);
+            let (mut q, mut xq) = loop
+            {
+                let (mut q, mut xq) = Rsa::fips186_prime(rng, &e, aux_bits, &range, control, &mut stats)?;
+                // p et q, ainsi que les entiers aléatoires dont ils sont issus, doivent être suffisamment éloignés
+                if dist(&p, &q) > min_gap && dist(&xp, &xq) > min_gap
+                {
+                    break (q, xq);
+                }
+                maths::wipe(&mut q);
+                maths::wipe(&mut xq);
+            };
+            stats.prime_times.push(last.elapsed());
+            control.prime_found(2, 2);
+            maths::wipe(&mut xp);
+            maths::wipe(&mut xq);
+
+            // d = e^-1 mod ppcm(p - 1, q - 1) doit être supérieur à 2^(nlen/2), sinon une nouvelle paire de facteurs est générée
+            let mut lambda = (&p - 1u8).lcm(&(&q - 1u8));
+            let d = maths::modinv(&e, &lambda);
+            maths::wipe(&mut lambda);
+            let n = &p * &q;
+            maths::wipe(&mut p);
+            maths::wipe(&mut q);
+            match d
+            {
+                Some(d) if d > BigUint::one() << half => {
+                    stats.elapsed = start.elapsed();
+                    return Ok((KeyPair::from(
+                        KeyPair::from(NumKey::from(n.clone()), NumKey::from(e)),
+                        PrivateKey::from(NumKey::from(n), NumKey::from(d))
+                    ), stats));
+                },
+                Some(mut d) => maths::wipe(&mut d),
+                None => ()
+            }
+        }
+    }
+
+    /// Génère un facteur premier p à partir de deux nombres premiers auxiliaires r1 et r2 (FIPS 186-5, annexe C.9) : p - 1 est divisible par r1 et p + 1 par r2.
+    /// `range` donne les bornes (incluse, exclue) du facteur. Retourne le facteur et l'entier aléatoire X dont il est issu.
+    fn fips186_prime<R: RngCore + ?Sized>(rng: &mut R, e: &BigUint, aux_bits: u64, range: &(BigUint, BigUint), control: &GenControl, stats: &mut GenStats) -> Result<(BigUint, BigUint), Error>
+    {
+        let (lower, upper) = range;
+        loop
+        {
+            let mut r1 = Rsa::fips186_aux_prime(rng, aux_bits, control)?;
+            let mut r2 = Rsa::fips186_aux_prime(rng, aux_bits, control)?;
+            let r1_2 = &r1 * 2u8;
+            let m = &r1_2 * &r2;
+            // R = 1 mod 2 * r1 et R = -1 mod r2 (théorème des restes chinois)
+            let r = match (maths::modinv(&r2, &r1_2), maths::modinv(&r1_2, &r2))
+            {
+                (Some(a), Some(b)) => (a * &r2 + &m - b * &r1_2) % &m,
+                _ => continue
+            };
+            maths::wipe(&mut r1);
+            maths::wipe(&mut r2);
+
+            'x: loop
+            {
+                let mut x = rng.gen_biguint_range(lower, upper);
+                let mut y = &x + (&r + &m - &x % &m) % &m;
+                for _ in 0..5 * upper.bits()
+                {
+                    if &y >= upper
                     {
-                        // S'il est premier on l'envoie dans le canal de sortie
-                        f_tx_c.send(temp).expect("Rsa.generate : erreur dans le remplissage.");
+                        maths::wipe(&mut x);
+                        continue 'x;
                     }
-                    // Si deux entiers premiers sont disponibles, on sort (la génération de p et q est terminée)
-                    if f_rx_c.len() >= 2
+                    control.check()?;
+                    stats.candidates += 1;
+                    control.candidate_tested(stats.candidates);
+                    if (&y - 1u8).gcd(e).is_one() && maths::miller_rabin_with(rng, &y, RSA_FIPS_PRIME_ROUNDS)
                     {
-                        working_f_c.store(false, atomic::Ordering::Relaxed);
+                        return Ok((y, x));
                     }
+                    y += &m;
                 }
-            });
+                // Trop de candidats écartés : de nouveaux nombres premiers auxiliaires sont générés
+                maths::wipe(&mut x);
+                maths::wipe(&mut y);
+                break;
+            }
         }
+    }
 
-        // Cette boucle a lieu en parralèle des autres threads et ne s'arrête que lorsque les threads signalent que deux entiers premiers sont disponibles
-        // Pas besoin de join les threads
-        while working.load(atomic::Ordering::Relaxed)
+    /// Génère un nombre premier auxiliaire de `bits` bits exactement.
+    fn fips186_aux_prime<R: RngCore + ?Sized>(rng: &mut R, bits: u64, control: &GenControl) -> Result<BigUint, Error>
+    {
+        loop
         {
-            // On remplit le canal avec des entiers suscesptibles d'être premiers
-            g_tx.send(maths::rand_primelike(sz_b)).expect("Rsa.generate : erreur dans la génération.");
+            control.check()?;
+            let mut candidate = rng.gen_biguint(bits - 1) | (BigUint::one() << (bits - 1)) | BigUint::one();
+            if maths::miller_rabin_with(rng, &candidate, RSA_FIPS_AUX_ROUNDS)
+            {
+                return Ok(candidate);
+            }
+            maths::wipe(&mut candidate);
         }
+    }
 
-        let (p, q) = (f_rx.recv().unwrap(), f_rx.recv().unwrap());
-        let n = &p * &q;
-        let ind = (p - 1u8) * (q - 1u8);
-        let e = maths::expcode(&ind).unwrap();
-        let mut d = maths::euclide(&e.to_bigint().unwrap(), &ind.to_bigint().unwrap());
+    /// Taille en bits de chaque facteur premier pour un module d'exactement `16 * sz_b` bits, selon le nombre de facteurs :
+    /// chacun fait `16 * sz_b / k` bits, le dernier absorbant le reste de la division.
+    fn prime_bits(&self, sz_b: u64) -> Result<Vec<u64>, Error>
+    {
+        if sz_b == 0
+        {
+            return Err(Error::InvalidParameter(String::from("Rsa.generate : la taille de clé doit être non nulle.")));
+        }
+        if self.primes < 2 || self.primes > RSA_MAX_PRIMES
+        {
+            return Err(Error::InvalidParameter(format!("Rsa.generate : le nombre de facteurs premiers doit être compris entre 2 et {}.", RSA_MAX_PRIMES)));
+        }
+        let (nlen, k) = (sz_b * 16, self.primes as u64);
+        if nlen / k < RSA_MIN_PRIME_BITS
+        {
+            return Err(Error::InvalidParameter(format!("Rsa.generate : les facteurs premiers doivent faire au moins {} bits.", RSA_MIN_PRIME_BITS)));
+        }
+        self.policy.check_modulus_bits(nlen)?;
+
+        let mut sizes = vec![nlen / k; self.primes - 1];
+        sizes.push(nlen - (nlen / k) * (k - 1));
+        Ok(sizes)
+    }
+
+    /// Nombre de bits de poids fort mis à 1 dans chaque facteur premier, afin que leur produit ait exactement la taille voulue
+    /// (voir `maths::rand_primelike_bits_with`) : deux pour une clé à deux facteurs, trois au delà.
+    fn prime_top(&self) -> u64
+    {
+        if self.primes > 2 { 3 } else { 2 }
+    }
+
+    /// Construit une clé principale depuis ses facteurs premiers, qui sont effacés de la mémoire.
+    /// L'exposant public est le plus petit autorisé par la politique du moteur (voir `Policy::exponent_for`).
+    /// Les facteurs ne sont conservés dans la clé privée que pour les clés multi-premiers : une clé à deux facteurs garde le format (n, d) habituel.
+    fn key_from_primes(&self, mut primes: Vec<BigUint>) -> Result<RsaKey, Error>
+    {
+        let n = primes.iter().fold(BigUint::one(), | acc, p | acc * p);
+        // L'indicatrice d'Euler est secrète : elle est effacée dès qu'elle n'est plus utile
+        let mut ind = primes.iter().fold(BigUint::one(), | acc, p | acc * (p - 1u8));
+        let e = self.policy.exponent_for(&ind);
+        let d = e.as_ref().and_then(| e | maths::modinv(e, &ind));
+        maths::wipe(&mut ind);
+        let (e, d) = match (e, d)
+        {
+            (Some(e), Some(d)) => (e, d),
+            _ => {
+                primes.iter_mut().for_each(maths::wipe);
+                return Err(Error::Generation(String::from("Rsa.generate : aucun exposant convenable.")));
+            }
+        };
+
+        let primes =
+            if primes.len() > 2
+            {
+                primes.into_iter().map(NumKey::from).collect()
+            }
+            else
+            {
+                primes.iter_mut().for_each(maths::wipe);
+                Vec::new()
+            };
+
+        Ok(KeyPair::from(
+            KeyPair::from(NumKey::from(n.clone()), NumKey::from(e)),
+            PrivateKey::with_primes(NumKey::from(n), NumKey::from(d), primes)
+        ))
+    }
+
+    /// Vérifie qu'une clé publique respecte la politique du moteur (taille du module, exposant).
+    fn check_policy(&self, key: &PublicKey) -> Result<(), Error>
+    {
+        self.policy.check_modulus_bits(key.0.value().bits())?;
+        self.policy.check_exponent(key.1.value())
+    }
+
+    /// Vérifie que le module n'est pas nul et que le nombre à traiter lui est bien inférieur.
+    fn check_operand(num: &BigUint, n: &BigUint) -> Result<(), Error>
+    {
+        if n.is_zero()
+        {
+            return Err(Error::InvalidKey(String::from("Rsa : module nul.")));
+        }
+        if num >= n
+        {
+            return Err(Error::MessageTooLong);
+        }
+
+        Ok(())
+    }
+
+    /// Élève un nombre à la puissance de l'exposant public d'une clé (n, e) modulo n, en vérifiant que le nombre est bien inférieur au module.
+    fn modpow_checked(num: &mut BigUint, key: &PublicKey) -> Result<(), Error>
+    {
+        Rsa::check_operand(num, key.0.value())?;
+        *num = maths::fmodpow(num, key.1.value(), key.0.value());
+        Ok(())
+    }
+
+    /// Opération privée RSA (déchiffrement ou signature). Si les facteurs premiers de la clé sont connus, le calcul se fait modulo chaque facteur
+    /// puis les résultats sont recombinés par le théorème des restes chinois (algorithme de Garner) ; sinon, exponentiation modulaire classique.
+    fn private_op(num: &mut BigUint, key: &PrivateKey) -> Result<(), Error>
+    {
+        let (n, d) = (key.0.value(), key.1.value());
+        Rsa::check_operand(num, n)?;
+        if key.2.is_empty()
+        {
+            *num = maths::fmodpow(num, d, n);
+            return Ok(());
+        }
+
+        // acc est le résultat modulo prod, le produit des facteurs déjà traités
+        let (mut acc, mut prod) = (BigUint::zero(), BigUint::one());
+        for r in key.2.iter().map(NumKey::value)
+        {
+            let mut dr = d % (r - 1u8);
+            let mut mr = maths::fmodpow(&(&*num % r), &dr, r);
+            maths::wipe(&mut dr);
+
+            let inv = maths::modinv(&(&prod % r), r).ok_or_else(|| Error::InvalidKey(String::from("Rsa : facteurs premiers non premiers entre eux.")))?;
+            // h = (mr - acc) * prod^-1 mod r, puis acc = acc + prod * h
+            let mut h = ((&mr + r - (&acc % r)) * inv) % r;
+            acc += &prod * &h;
+            prod *= r;
+            maths::wipe(&mut mr);
+            maths::wipe(&mut h);
+        }
+        if &prod != n
+        {
+            maths::wipe(&mut acc);
+            return Err(Error::InvalidKey(String::from("Rsa : le produit des facteurs premiers ne correspond pas au module.")));
+        }
+
+        *num = acc;
+        Ok(())
+    }
+}
+
+impl Default for Rsa
+{
+    fn default() -> Self
+    {
+        Rsa::new()
+    }
+}
+
+/// Validation d'une moitié de clé RSA (n, exposant), publique ou privée : le module doit être impair et assez grand, l'exposant impair et compris entre 1 et n (exclus).
+fn validate_half(n: &BigUint, exp: &BigUint) -> Result<(), Error>
+{
+    if n.is_even()
+    {
+        return Err(Error::InvalidKey(String::from("le module doit être impair.")));
+    }
+    if exp.is_even() || exp <= &BigUint::from(1u8) || exp >= n
+    {
+        return Err(Error::InvalidKey(String::from("l'exposant doit être impair et compris strictement entre 1 et le module.")));
+    }
+    if n.bits() < RSA_MIN_MODULUS_BITS
+    {
+        return Err(Error::WeakKey(format!("module de {} bits, au moins {} bits sont nécessaires.", n.bits(), RSA_MIN_MODULUS_BITS)));
+    }
+
+    Ok(())
+}
 
-        if d.is_negative()
+/// Validation d'une clé publique RSA, voir `validate_half`.
+impl Validate for PublicKey
+{
+    fn validate(&self) -> Result<(), Error>
+    {
+        validate_half(self.0.value(), self.1.value())
+    }
+}
+
+/// Validation d'une clé privée RSA, voir `validate_half`. Si les facteurs premiers sont connus, vérifie aussi qu'ils sont premiers et que leur produit est le module.
+impl Validate for PrivateKey
+{
+    fn validate(&self) -> Result<(), Error>
+    {
+        let (n, primes) = (self.0.value(), &self.2);
+        if !primes.is_empty()
         {
-            d += ind.to_bigint().unwrap();
+            if primes.len() < 2 || primes.len() > RSA_MAX_PRIMES
+            {
+                return Err(Error::InvalidKey(format!("une clé multi-premiers doit avoir entre 2 et {} facteurs.", RSA_MAX_PRIMES)));
+            }
+            if &primes.iter().fold(BigUint::one(), | acc, r | acc * r.value()) != n
+            {
+                return Err(Error::InvalidKey(String::from("le produit des facteurs premiers ne correspond pas au module.")));
+            }
+            // Un facteur répété (n = p²q) passe les autres vérifications mais rend le déchiffrement par restes chinois incorrect
+            if primes.iter().enumerate().any(| (i, r) | primes[..i].iter().any(| s | s.value() == r.value()))
+            {
+                return Err(Error::InvalidKey(String::from("les facteurs premiers du module doivent être distincts.")));
+            }
+            if !primes.iter().all(| r | maths::miller_rabin_with(&mut OsRng, r.value(), RSA_VALIDATE_ROUNDS))
+            {
+                return Err(Error::InvalidKey(String::from("les facteurs du module ne sont pas premiers.")));
+            }
         }
 
-        KeyPair::from(
-            KeyPair::from(NumKey::from(n.clone()), NumKey::from(e)), 
-            KeyPair::from(NumKey::from(n), NumKey::from(d.to_biguint().unwrap()))
-        )
+        validate_half(n, self.1.value())
     }
+}
 
-    fn gen_def(&self) -> Self::MainKey 
+/// Validation d'une clé principale RSA : en plus de la validation de chaque moitié, vérifie qu'elles partagent le même module,
+/// que les facteurs premiers (ceux de la clé multi-premiers, ou retrouvés depuis les exposants) ont pour produit n et sont bien premiers, et que e * d ≡ 1 mod λ(n).
+impl Validate for RsaKey
+{
+    fn validate(&self) -> Result<(), Error>
     {
-        self.generate(RSA_DEF_PRIME_SIZEB, self.def_gthreads())  
+        let (n, e, d) = (self.0.0.value(), self.0.1.value(), self.1.1.value());
+
+        if n != self.1.0.value()
+        {
+            return Err(Error::InvalidKey(String::from("les modules des clés publique et privée diffèrent.")));
+        }
+        validate_deferred(self.0.validate(), || validate_deferred(self.1.validate(), ||
+        {
+            // Les facteurs d'une clé multi-premiers ont déjà été vérifiés avec la clé privée ; sinon, ils sont retrouvés depuis les exposants
+            let lambda =
+                if self.1.2.is_empty()
+                {
+                    let (p, q) = maths::factor_from_exponents(n, e, d)
+                        .ok_or_else(|| Error::InvalidKey(String::from("les exposants ne correspondent pas au module (e * d ≢ 1 mod λ(n)).")))?;
+                    let (p, q) = (NumKey::from(p), NumKey::from(q));
+                    if !maths::miller_rabin_with(&mut OsRng, p.value(), RSA_VALIDATE_ROUNDS) || !maths::miller_rabin_with(&mut OsRng, q.value(), RSA_VALIDATE_ROUNDS)
+                    {
+                        return Err(Error::InvalidKey(String::from("les facteurs du module ne sont pas premiers.")));
+                    }
+                    NumKey::from((p.value() - 1u8).lcm(&(q.value() - 1u8)))
+                }
+                else
+                {
+                    NumKey::from(self.1.2.iter().fold(BigUint::one(), | acc, r | acc.lcm(&(r.value() - 1u8))))
+                };
+            if !((e * d) % lambda.value()).is_one()
+            {
+                return Err(Error::InvalidKey(String::from("e * d ≢ 1 mod λ(n).")));
+            }
+
+            Ok(())
+        }))
     }
+}
 
-    fn run_crypt(&self, num: &mut BigUint, key: &Self::EncryptionKey) {
-        *num = maths::fmodpow(num, &key.1, &key.0);
+impl Engine for Rsa
+{
+    type EncryptionKey = PublicKey;
+    type DecryptionKey = PrivateKey;
+    type MainKey = RsaKey;
+
+    fn try_generate(&self, sz_b: u64, n_threads: u8) -> Result<Self::MainKey, Error>
+    {
+        self.try_generate_controlled(sz_b, n_threads, &GenControl::new()).map(| (key, _) | key)
+    }
+
+    fn try_gen_def(&self) -> Result<Self::MainKey, Error>
+    {
+        self.try_generate(RSA_DEF_PRIME_SIZEB, self.def_gthreads())  
+    }
+
+    fn try_run_crypt(&self, num: &mut BigUint, key: &Self::EncryptionKey) -> Result<(), Error> {
+        self.check_policy(key)?;
+        Rsa::modpow_checked(num, key)
+    }
+
+    fn try_run_decrypt(&self, num: &mut BigUint, key: &Self::DecryptionKey) -> Result<(), Error> {
+        Rsa::private_op(num, key)
+    }
+
+    fn rng(&self) -> RngHandle
+    {
+        self.rng.clone()
     }
 
-    fn run_decrypt(&self, num: &mut BigUint, key: &Self::DecryptionKey) {
-        *num = maths::fmodpow(num, &key.1, &key.0)
+    fn policy(&self) -> Policy
+    {
+        self.policy
+    }
+}
+
+impl Signer for Rsa
+{
+    fn try_run_sign(&self, num: &mut BigUint, key: &Self::DecryptionKey) -> Result<(), Error>
+    {
+        Rsa::private_op(num, key)
+    }
+
+    fn try_run_verify(&self, num: &mut BigUint, key: &Self::EncryptionKey) -> Result<(), Error>
+    {
+        self.check_policy(key)?;
+        Rsa::modpow_checked(num, key)
     }
 }
\ No newline at end of file
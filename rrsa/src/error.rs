@@ -0,0 +1,73 @@
+use std::{fmt::Display, string::FromUtf8Error};
+use num_bigint::ParseBigIntError;
+
+
+/// Erreur commune à toute la librairie, retournée par les variantes faillibles (`try_xx`) des constructeurs et des opérations des moteurs.
+#[derive(Debug)]
+pub enum Error
+{
+    /// Impossible de désérialiser une valeur (clé, message, encodage DER/PEM...). Contient éventuellement l'erreur de conversion d'entier sous-jacente.
+    Parse(String, Option<ParseBigIntError>),
+    /// Clé invalide ou incohérente (module nul, exposants ne correspondant pas au module...).
+    InvalidKey(String),
+    /// Clé cohérente mais trop faible pour être utilisée de manière sûre (module trop petit...).
+    WeakKey(String),
+    /// Paramètre invalide passé à une fonction (taille nulle, aucun thread...).
+    InvalidParameter(String),
+    /// Tentative de construction depuis un ensemble vide (message sans partie, texte vide...).
+    Empty(String),
+    /// Données impossibles à décoder (texte non UTF-8, chiffré altéré, phrase de passe incorrecte...).
+    Decoding(String),
+    /// Le nombre à chiffrer ou déchiffrer est trop grand pour la clé utilisée : il faut réduire la taille de bloc du message.
+    MessageTooLong,
+    /// La source d'aléa n'a pas pu fournir de valeurs aléatoires.
+    Rng(String),
+    /// La génération de clé a échoué (erreur de communication entre threads, aucun exposant convenable...).
+    Generation(String),
+    /// L'opération a été interrompue avant son terme (jeton d'annulation, délai dépassé...).
+    Cancelled(String),
+    /// Paramètre refusé par la politique de sécurité du moteur (module trop petit, exposant non autorisé, nonce trop petit...), voir `policy::Policy`.
+    Policy(String)
+}
+
+impl Display for Error
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        match self
+        {
+            Self::Parse(msg, Some(ul)) => write!(f, "Impossible de désérialiser : {} ({})", msg, ul),
+            Self::Parse(msg, None) => write!(f, "Impossible de désérialiser : {}", msg),
+            Self::InvalidKey(msg) => write!(f, "Clé invalide : {}", msg),
+            Self::WeakKey(msg) => write!(f, "Clé trop faible : {}", msg),
+            Self::InvalidParameter(msg) => write!(f, "Paramètre invalide : {}", msg),
+            Self::Empty(msg) => write!(f, "Ensemble vide : {}", msg),
+            Self::Decoding(msg) => write!(f, "Impossible de décoder : {}", msg),
+            Self::MessageTooLong => write!(f, "Message trop long pour la clé utilisée, la taille de bloc doit être réduite"),
+            Self::Rng(msg) => write!(f, "Erreur de la source d'aléa : {}", msg),
+            Self::Generation(msg) => write!(f, "Échec de la génération : {}", msg),
+            Self::Cancelled(msg) => write!(f, "Opération interrompue : {}", msg),
+            Self::Policy(msg) => write!(f, "Refusé par la politique de sécurité : {}", msg)
+        }
+    }
+}
+
+impl std::error::Error for Error
+{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)>
+    {
+        match self
+        {
+            Self::Parse(_, Some(ul)) => Some(ul),
+            _ => None
+        }
+    }
+}
+
+impl From<FromUtf8Error> for Error
+{
+    fn from(e: FromUtf8Error) -> Self
+    {
+        Error::Decoding(format!("texte UTF-8 invalide ({})", e))
+    }
+}
@@ -1,71 +1,64 @@
-use std::{error::Error, fmt::Display, ops::Deref};
-use num_bigint::{BigUint, ParseBigIntError};
+use num_bigint::BigUint;
 use num_traits::Num;
+use zeroize::Zeroizing;
+use crate::{Error, maths};
 
 
 /// Délimiteur des différentes parties des clés pour la sérialisation textuelle.
-const KEY_SERIAL_DELIMITER: &str = "::";
+pub const KEY_SERIAL_DELIMITER: &str = "::";
 /// Base (radix) à utiliser pour sérialiser les parties numériques des clés.
 const KEY_SERIAL_RADIX: u8 = 36;
 
 
-/// Structure à utiliser lorsqu'une erreur a lieu au traitement d'une clé (chargement depuis un fichier, désérialisation...)
-#[derive(Debug)]
-pub struct KeyParseError
+/// Trait à implémenter par toutes les clés, impose de définir certaines méthodes liées à la sérialisation.
+pub trait Key
 {
-    msg: String,
-    underlying: Option<ParseBigIntError>
+    /// Nombre de parties de la sérialisation textuelle de la clé, s'il est fixe (`None` si la clé a un nombre de parties variable).
+    /// Permet de découper la sérialisation d'une paire de clés lorsque l'une des deux a un nombre de parties variable.
+    const PARTS: Option<usize> = None;
+
+    /// Permet d'obtenir une clé depuis sa sérialisation textuelle.
+    fn from_str(val: String) -> Result<Self, Error> where Self : Sized;
+    /// Retourne la sérialisation textuelle de la clé.
+    fn serialize_str(&self) -> String; 
 }
 
-impl KeyParseError
+
+/// Trait à implémenter par les clés dont la cohérence peut être vérifiée (après chargement depuis un fichier par exemple).
+pub trait Validate
 {
-    fn from(msg: &str, underlying: Option<ParseBigIntError>) -> Self
-    {
-        KeyParseError
-        {
-            msg: String::from(msg),
-            underlying
-        }
-    }
+    /// Vérifie la cohérence de la clé. Retourne `Error::InvalidKey` si la clé est incohérente,
+    /// `Error::WeakKey` si elle est cohérente mais trop faible pour être utilisée de manière sûre.
+    fn validate(&self) -> Result<(), Error>;
 }
 
-impl Display for KeyParseError
+/// Termine la validation d'une clé dont une partie a déjà été validée (résultat `first`) par les vérifications de cohérence `rest`.
+/// Une clé trop faible (`Error::WeakKey` dans `first`) n'est signalée qu'à la fin, une fois sa cohérence complètement vérifiée :
+/// une clé incohérente est ainsi toujours rejetée comme telle, même si elle est aussi trop faible.
+pub(crate) fn validate_deferred<F>(first: Result<(), Error>, rest: F) -> Result<(), Error>
+where F : FnOnce() -> Result<(), Error>
 {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    let weak = match first
     {
-        let mut disp = String::from("Impossible de désérialiser une clé (KeyParseError) : ");
-        disp.push_str(&self.msg);
-
-        if let Some(ul) = &self.underlying
-        {
-            if let Err(e) = disp.fmt(f)
-            {
-                return Err(e);
-            }
-            return ul.fmt(f);
-        }
+        Err(Error::WeakKey(msg)) => Some(msg),
+        other => { other?; None }
+    };
+    rest()?;
 
-        disp.fmt(f)
+    match weak
+    {
+        Some(msg) => Err(Error::WeakKey(msg)),
+        None => Ok(())
     }
 }
 
-impl Error for KeyParseError {}
-
-/// Trait à implémenter par toutes les clés, impose de définir certaines méthodes liées à la sérialisation.
-pub trait Key
-{
-    /// Permet d'obtenir une clé depuis sa sérialisation textuelle.
-    fn from_str(val: String) -> Result<Self, KeyParseError> where Self : Sized;
-    /// Retourne la sérialisation textuelle de la clé.
-    fn serialize_str(&self) -> String; 
-}
-
 
 /// Clé numérique, contient juste un grand entier positif.
+/// La valeur pouvant être secrète (exposant privé, clé césar...), elle est effacée de la mémoire à la destruction de la clé.
+/// Pour la même raison, la clé n'implémente ni `Debug` ni `Deref` : la valeur n'est accessible qu'explicitement via `value`.
 pub struct NumKey
 {
-    /// Valeur numérique de la clé (grand entier).
-    pub value: BigUint
+    value: BigUint
 }
 
 impl NumKey
@@ -78,17 +71,25 @@ impl NumKey
             value
         }
     }
+
+    /// Valeur numérique de la clé (grand entier).
+    pub fn value(&self) -> &BigUint
+    {
+        &self.value
+    }
 }
 
 impl Key for NumKey
 {
-    fn from_str(val: String) -> Result<Self, KeyParseError>
+    const PARTS: Option<usize> = Some(1);
+
+    fn from_str(val: String) -> Result<Self, Error>
     {
         let value = BigUint::from_str_radix(&val, KEY_SERIAL_RADIX.into());
         match value
         {
             Ok(x) => Ok(NumKey { value: x }),
-            Err(e) => Err(KeyParseError::from("", Some(e)))
+            Err(e) => Err(Error::Parse(format!("NumKey::from_str : valeur numérique invalide en base {}", KEY_SERIAL_RADIX), Some(e)))
         }
     }
 
@@ -98,13 +99,11 @@ impl Key for NumKey
     }
 }
 
-/// L'implémentation de `Deref` permet de traiter la clé numérique comme un grand entier
-impl Deref for NumKey
+impl Drop for NumKey
 {
-    type Target = BigUint;
-
-    fn deref(&self) -> &Self::Target {
-        &self.value
+    fn drop(&mut self)
+    {
+        maths::wipe(&mut self.value);
     }
 }
 
@@ -123,22 +122,43 @@ impl<T : Key, U : Key> KeyPair<T, U>
 
 impl<T : Key, U : Key> Key for KeyPair<T, U>
 {
-    fn from_str(val: String) -> Result<Self, KeyParseError>
+    const PARTS: Option<usize> =
+        match (T::PARTS, U::PARTS)
+        {
+            (Some(t), Some(u)) => Some(t + u),
+            _ => None
+        };
+
+    fn from_str(val: String) -> Result<Self, Error>
     {
         // On découpe chaque partie en utilisant le séparateur.
         let parts: Vec<&str> = val.split(KEY_SERIAL_DELIMITER).collect();
         let len = parts.len();
 
-        // Puisqu'on traite une paire de clés, la longueur est forcément paire.
-        if len % 2 == 1
+        // Si l'une des deux clés a un nombre de parties fixe, on sait où couper ; sinon les deux clés sont supposées de même longueur.
+        let mid =
+            match (T::PARTS, U::PARTS)
+            {
+                (Some(t), _) => t,
+                (None, Some(u)) => len.saturating_sub(u),
+                (None, None) => {
+                    // Puisqu'on traite une paire de clés, la longueur est forcément paire.
+                    if len % 2 == 1
+                    {
+                        return Err(Error::Parse(String::from("KeyPair::from_str : impossible de traiter un nombre de parties impair."), None));
+                    }
+                    len / 2
+                }
+            };
+        if mid == 0 || mid >= len || Self::PARTS.is_some_and(| p | p != len)
         {
-            return Err(KeyParseError::from("KeyPair::from_str : impossible de traiter un nombre de parties impair.", None));
+            return Err(Error::Parse(format!("KeyPair::from_str : nombre de parties ({}) incompatible avec la paire de clés.", len), None));
         }
 
-        // On obtient la première clé depuis la première moitié du texte...
-        let ts = T::from_str(parts[..(len / 2)].join(KEY_SERIAL_DELIMITER))?;
-        // Puis la deuxième moitié.
-        let us = U::from_str(parts[(len / 2)..len].join(KEY_SERIAL_DELIMITER))?;
+        // On obtient la première clé depuis la première partie du texte...
+        let ts = T::from_str(parts[..mid].join(KEY_SERIAL_DELIMITER))?;
+        // Puis la deuxième.
+        let us = U::from_str(parts[mid..len].join(KEY_SERIAL_DELIMITER))?;
         // On retourne un résultat ok.
         Ok(KeyPair(ts, us))
     }
@@ -147,4 +167,23 @@ impl<T : Key, U : Key> Key for KeyPair<T, U>
     {
         format!("{}{}{}", self.0.serialize_str(), KEY_SERIAL_DELIMITER, self.1.serialize_str())
     }
-}
\ No newline at end of file
+}
+
+/// Sérialise en hexadécimal une clé dont l'encodage normalisé est une chaîne d'octets (clés de courbes elliptiques).
+pub(crate) fn hex_encode(bytes: &[u8]) -> String
+{
+    bytes.iter().map(| b | format!("{:02x}", b)).collect()
+}
+
+/// Décode la sérialisation hexadécimale d'une clé de `len` octets (voir `hex_encode`), `name` désignant la clé dans les messages d'erreur.
+/// Les octets décodés sont effacés de la mémoire après usage.
+pub(crate) fn hex_decode(val: &str, len: usize, name: &str) -> Result<Zeroizing<Vec<u8>>, Error>
+{
+    let val = val.trim();
+    if val.len() != 2 * len || !val.bytes().all(| b | b.is_ascii_hexdigit())
+    {
+        return Err(Error::Parse(format!("{}::from_str : {} octets en hexadécimal attendus.", name, len), None));
+    }
+
+    Ok(Zeroizing::new((0..len).map(| i | u8::from_str_radix(&val[2 * i..2 * i + 2], 16).unwrap()).collect()))
+}
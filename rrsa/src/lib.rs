@@ -1,8 +1,26 @@
 #[cfg(test)]
 mod tests;
 
-mod maths;
+mod asn1;
 
+pub mod attacks;
+pub mod audit;
+pub mod classical;
+pub mod cryptanalysis;
+pub mod curve25519;
+pub mod dh;
+pub mod elgamal;
 pub mod engines;
+pub mod error;
 pub mod keys;
-pub mod messages;
\ No newline at end of file
+pub mod maths;
+pub mod messages;
+pub mod p256;
+pub mod paillier;
+pub mod policy;
+pub mod provable;
+pub mod rabin;
+pub mod rng;
+pub mod storage;
+
+pub use error::Error;
\ No newline at end of file
@@ -1,7 +1,10 @@
 use num_bigint::{BigInt, BigUint, RandBigInt};
-use num_traits::{One, identities::Zero};
+use num_integer::Integer;
+use num_traits::{One, Signed, identities::Zero};
 use rand::Rng;
-use std::convert::TryInto;
+use std::{convert::TryInto, sync::atomic};
+use zeroize::Zeroizing;
+use crate::Error;
 
 
 /// Trait d'extension pour les grands entiers non signés. Permet notamment leur découpage et l'obtention de leur taille digitale.
@@ -12,7 +15,7 @@ pub trait NumUtil
     /// Permet d'obtenir le nombre d'octets utilisé par le grand entier.
     fn sz_b(&self) -> u32
     {
-        (self.sz(16) + 1) / 2
+        self.sz(16).div_ceil(2)
     }
 
     /// Remplit un vecteur de grands entiers en découpant le grand entier sur lequel est appliqué la méthode, chaque bloc de taille maximale `block_sz` octets.
@@ -53,17 +56,24 @@ impl NumUtil for BigUint
 /// Trait d'extension pour les vecteurs de grands entiers. Permet notamment la recomposition de grands nombres.
 pub trait VecNumUtil
 {
-    /// Recompose un grand nombre depuis ses parties (préalablement découpée avec `expl_f` ou `expl_r`)
-    fn rejoin(&self) -> BigUint;
+    /// Recompose un grand nombre depuis ses parties (préalablement découpée avec `expl_f` ou `expl_r`).
+    /// Retourne une erreur si le vecteur est vide.
+    fn try_rejoin(&self) -> Result<BigUint, Error>;
+    /// Recompose un grand nombre depuis ses parties (préalablement découpée avec `expl_f` ou `expl_r`).
+    /// Panique si le vecteur est vide, voir `try_rejoin` pour la variante faillible.
+    fn rejoin(&self) -> BigUint
+    {
+        self.try_rejoin().expect("VecNumUtil.rejoin")
+    }
 }
 
 impl VecNumUtil for Vec<BigUint>
 {
-    fn rejoin(&self) -> BigUint
+    fn try_rejoin(&self) -> Result<BigUint, Error>
     {
         if self.is_empty()
         {
-            panic!("VecNumUtil.join (BigUint) : vecteur vide");
+            return Err(Error::Empty(String::from("VecNumUtil.join (BigUint) : vecteur vide")));
         }
 
         let mut b = BigUint::from(0u8);
@@ -76,17 +86,17 @@ impl VecNumUtil for Vec<BigUint>
             b = &b * &mult + part;
         }
 
-        b
+        Ok(b)
     }
 }
 
 impl VecNumUtil for Vec<u8>
 {
-    fn rejoin(&self) -> BigUint
+    fn try_rejoin(&self) -> Result<BigUint, Error>
     {
         if self.is_empty()
         {
-            panic!("VecNumUtil.join (u8) : vecteur vide");
+            return Err(Error::Empty(String::from("VecNumUtil.join (u8) : vecteur vide")));
         }
 
         let mut b = BigUint::from(0u8);
@@ -97,7 +107,7 @@ impl VecNumUtil for Vec<u8>
             b = &b * &mult + part;
         }
 
-        b
+        Ok(b)
     }
 }
 
@@ -108,28 +118,80 @@ const PRIME_RN: u32 = 12737213u32;
 const PRIME_ROUNDS: u8 = 20;
 
 /// Fonction d'exponentiation rapide, très utile pour le RSA.
+/// L'exposant est parcouru bit à bit sans être copié, puisqu'il peut s'agir d'un exposant privé.
 pub fn fmodpow(base: &BigUint, exp: &BigUint, num: &BigUint) -> BigUint
 {
     let mut res = BigUint::from(1u8);
-    let mut exp_bin = exp.clone();
     let mut temp = base.clone();
-    let mut r;
 
-    while !exp_bin.is_zero()
+    for i in 0..exp.bits()
     {
-        r = &exp_bin % 2u8;
-        if r.is_one()
+        if exp.bit(i)
         {
             res = (&res * &temp) % num;
         }
 
-        exp_bin /= 2u8;
         temp = (&temp * &temp) % num;
     }
 
     res
 }
 
+/// Racine n-ième entière d'un grand entier, arrondie à l'inférieur (méthode de Newton). `n` doit être non nul.
+/// La racine est exacte si, élevée à la puissance `n`, elle redonne `num`.
+pub fn nth_root(num: &BigUint, n: u32) -> BigUint
+{
+    assert!(n > 0, "maths::nth_root : la racine 0-ième n'existe pas.");
+    if n == 1 || num.is_zero()
+    {
+        return num.clone();
+    }
+
+    // Point de départ supérieur à la racine : 2^ceil(bits / n)
+    let mut x = BigUint::one() << num.bits().div_ceil(u64::from(n));
+    loop
+    {
+        // x' = ((n - 1) * x + num / x^(n - 1)) / n, strictement décroissante tant que x est au-dessus de la racine
+        let y = (&x * (n - 1) + num / x.pow(n - 1)) / n;
+        if y >= x
+        {
+            return x;
+        }
+        x = y;
+    }
+}
+
+/// Efface un grand entier en écrasant ses chiffres par des zéros à l'emplacement mémoire qu'ils occupent, puis le ramène à zéro.
+/// À utiliser sur les valeurs secrètes (exposants privés, facteurs premiers...) avant leur libération.
+pub fn wipe(num: &mut BigUint)
+{
+    // `assign_from_slice` réutilise le tampon existant : des zéros sont donc écrits par dessus les anciens chiffres
+    let digits = num.bits().div_ceil(32) as usize;
+    num.assign_from_slice(&vec![0u32; digits]);
+    // Empêche le compilateur de supprimer l'écriture des zéros, considérée comme inutile
+    atomic::compiler_fence(atomic::Ordering::SeqCst);
+}
+
+/// Échange les grands entiers `a` et `b`, tous deux inférieurs à 2^`bits`, si `swap` est vrai. Aucun branchement ne dépend de `swap` :
+/// les chiffres des deux valeurs sont combinés par masque puis réécrits, que l'échange ait lieu ou non (voir les multiplications scalaires des courbes).
+pub fn cswap(a: &mut BigUint, b: &mut BigUint, swap: bool, bits: u64)
+{
+    let words = bits.div_ceil(32) as usize;
+    let mask = 0u32.wrapping_sub(swap as u32);
+    let (mut da, mut db) = (Zeroizing::new(a.to_u32_digits()), Zeroizing::new(b.to_u32_digits()));
+    da.resize(words, 0);
+    db.resize(words, 0);
+    for (x, y) in da.iter_mut().zip(db.iter_mut())
+    {
+        let t = mask & (*x ^ *y);
+        *x ^= t;
+        *y ^= t;
+    }
+    // Comme pour `wipe`, `assign_from_slice` écrit par dessus les anciens chiffres
+    a.assign_from_slice(&da);
+    b.assign_from_slice(&db);
+}
+
 /// Algorithme d'Euclide pour trouver le PGCD de deux nombres. Utile pour le RSA.
 pub fn euclide(a: &BigInt, b: &BigInt) -> BigInt
 {
@@ -156,6 +218,126 @@ pub fn euclide(a: &BigInt, b: &BigInt) -> BigInt
     u1
 }
 
+/// Calcule l'inverse modulaire de `a` modulo `m`, s'il existe (c'est-à-dire si `a` et `m` sont premiers entre eux).
+pub fn modinv(a: &BigUint, m: &BigUint) -> Option<BigUint>
+{
+    if !a.gcd(m).is_one()
+    {
+        return None;
+    }
+
+    let (a, m) = (BigInt::from(a.clone()), BigInt::from(m.clone()));
+    let mut inv = euclide(&a, &m) % &m;
+    if inv.is_negative()
+    {
+        inv += &m;
+    }
+
+    // Les copies signées peuvent contenir des valeurs secrètes (indicatrice d'Euler par exemple)
+    wipe(&mut a.into_parts().1);
+    wipe(&mut m.into_parts().1);
+
+    Some(inv.into_parts().1)
+}
+
+/// Retrouve les deux facteurs premiers `(p, q)` d'un module RSA `n` à partir de ses exposants public `e` et privé `d`.
+/// Repose sur le fait que `e * d - 1` est un multiple de λ(n) : on cherche une racine carrée non triviale de 1 modulo `n`.
+/// Retourne `None` si les exposants ne sont pas cohérents avec le module, ce qui est le cas d'un exposant ou d'un module nul.
+pub fn factor_from_exponents(n: &BigUint, e: &BigUint, d: &BigUint) -> Option<(BigUint, BigUint)>
+{
+    let ed = e * d;
+    if ed.is_zero() || n < &BigUint::from(3u8)
+    {
+        return None;
+    }
+    let k = ed - 1u8;
+    if k.is_zero() || k.is_odd()
+    {
+        return None;
+    }
+
+    // k = 2^s * t avec t impair
+    let s = k.trailing_zeros()?;
+    let t = &k >> s;
+    let n1 = n - 1u8;
+
+    for &a in EXPCODE_TAB.iter()
+    {
+        let mut x = fmodpow(&BigUint::from(a), &t, n);
+        if x.is_one() || x == n1
+        {
+            continue;
+        }
+
+        for _ in 0..s
+        {
+            let y = fmodpow(&x, &BigUint::from(2u8), n);
+            if y.is_one()
+            {
+                // x est une racine carrée non triviale de 1, x - 1 partage donc un facteur avec n
+                let p = (&x - 1u8).gcd(n);
+                let q = n / &p;
+                return Some((p, q));
+            }
+            if y == n1
+            {
+                break;
+            }
+            x = y;
+        }
+    }
+
+    None
+}
+
+/// Construit l'arbre des produits d'une liste d'entiers : le premier niveau contient les entiers eux-mêmes,
+/// chaque niveau suivant les produits deux à deux du précédent, et le dernier niveau le produit de tous les entiers.
+pub fn product_tree(nums: &[BigUint]) -> Vec<Vec<BigUint>>
+{
+    let mut tree = vec![nums.to_vec()];
+
+    while tree.last().is_some_and(| level | level.len() > 1)
+    {
+        let level = tree.last().unwrap();
+        let next = level.chunks(2)
+            .map(| pair | if pair.len() == 2 { &pair[0] * &pair[1] } else { pair[0].clone() })
+            .collect();
+        tree.push(next);
+    }
+
+    tree
+}
+
+/// PGCD par lots (batch GCD) : pour chaque entier `n_i` de la liste, retourne pgcd(n_i, produit des autres entiers).
+/// L'arbre des produits puis l'arbre des restes (réductions successives du produit total modulo le carré de chaque nœud)
+/// permettent un calcul en temps quasi-linéaire, au lieu de comparer chaque paire d'entiers.
+/// Un résultat différent de 1 indique un facteur partagé avec au moins un autre entier ; il vaut `n_i` si tous ses facteurs sont partagés.
+/// Les entiers inférieurs ou égaux à 1, qui n'ont pas de facteur et annuleraient le produit, sont écartés de l'arbre : leur résultat vaut 1.
+pub fn batch_gcd(nums: &[BigUint]) -> Vec<BigUint>
+{
+    let kept: Vec<BigUint> = nums.iter().filter(| n | n > &&BigUint::one()).cloned().collect();
+    if kept.len() < 2
+    {
+        return vec![BigUint::one(); nums.len()];
+    }
+
+    let tree = product_tree(&kept);
+    let mut rems = tree.last().unwrap().clone();
+    // On descend l'arbre : le reste d'un nœud est le reste de son parent modulo le carré du nœud
+    for level in tree.iter().rev().skip(1)
+    {
+        rems = level.iter().enumerate()
+            .map(| (i, node) | &rems[i / 2] % (node * node))
+            .collect();
+    }
+
+    // Au niveau des feuilles, rems[i] = P mod n_i², donc rems[i] / n_i = (P / n_i) mod n_i
+    let mut gcds = kept.iter().zip(rems).map(| (n, r) | (r / n).gcd(n));
+    nums.iter()
+        .map(| n | if n > &BigUint::one() { gcds.next().unwrap() } else { BigUint::one() })
+        .collect()
+}
+
 /// Retourne le code d'exposant d'un nombre.
 pub fn expcode(num: &BigUint) -> Option<BigUint>
 {
@@ -177,7 +359,7 @@ pub fn isprime(num: &BigUint) -> bool
     // Le test étant probabiliste, il faut faire plusieurs itérations pour être raisonnablement certain du résultat
     for _ in 0..PRIME_ROUNDS
     {
-        if !fmodpow(&(&PRIME_RN % num), &(num - 1u8), num).is_one()
+        if !fmodpow(&(PRIME_RN % num), &(num - 1u8), num).is_one()
         {
             return false;
         }
@@ -186,21 +368,133 @@ pub fn isprime(num: &BigUint) -> bool
     true
 }
 
+/// Test de primalité de Miller-Rabin, plus fiable que `isprime` (pas de faux positifs sur les nombres de Carmichael).
+/// Les témoins utilisés sont les `rounds` premiers nombres premiers (au plus 35) : fixes, ils ne garantissent rien face à un composé
+/// construit pour les tromper. Ce test ne sert donc que de filtre rapide ; pour une valeur non fiable, utiliser `miller_rabin_with`.
+pub fn miller_rabin(num: &BigUint, rounds: usize) -> bool
+{
+    if num < &BigUint::from(2u8)
+    {
+        return false;
+    }
+    // Les petits nombres sont testés par division
+    for &p in EXPCODE_TAB.iter()
+    {
+        if (num % p).is_zero()
+        {
+            return num == &BigUint::from(p);
+        }
+    }
+
+    EXPCODE_TAB.iter().take(rounds).all(| &a | miller_rabin_round(num, &BigUint::from(a)))
+}
+
+/// Test de primalité de Miller-Rabin avec `rounds` témoins tirés aléatoirement depuis la source d'aléa `rng`, comme l'exige FIPS 186-5 (annexe B.3).
+/// Contrairement à `miller_rabin`, le test ne peut pas être trompé par un nombre construit pour passer des témoins connus d'avance.
+pub fn miller_rabin_with<R: Rng + ?Sized>(rng: &mut R, num: &BigUint, rounds: usize) -> bool
+{
+    if num < &BigUint::from(2u8)
+    {
+        return false;
+    }
+    for &p in EXPCODE_TAB.iter()
+    {
+        if (num % p).is_zero()
+        {
+            return num == &BigUint::from(p);
+        }
+    }
+
+    // Les témoins sont tirés dans [2, num - 2]
+    let upper = num - 1u8;
+    (0..rounds).all(| _ | miller_rabin_round(num, &rng.gen_biguint_range(&BigUint::from(2u8), &upper)))
+}
+
+/// Tour du test de Miller-Rabin de l'entier impair `num` avec le témoin `a` : retourne faux si `a` prouve que `num` est composé.
+fn miller_rabin_round(num: &BigUint, a: &BigUint) -> bool
+{
+    // num - 1 = 2^s * t avec t impair
+    let n1 = num - 1u8;
+    let s = n1.trailing_zeros().unwrap_or(0);
+    let t = &n1 >> s;
+
+    let mut x = fmodpow(a, &t, num);
+    if x.is_one() || x == n1
+    {
+        return true;
+    }
+    for _ in 1..s
+    {
+        x = fmodpow(&x, &BigUint::from(2u8), num);
+        if x == n1
+        {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Petits nombres premiers strictement inférieurs à `limit` (crible d'Ératosthène), utilisés pour écarter rapidement les candidats composés.
+pub fn small_primes(limit: u32) -> Vec<u32>
+{
+    let mut composite = vec![false; limit as usize];
+    let mut primes = Vec::new();
+    for i in 2..limit as usize
+    {
+        if !composite[i]
+        {
+            primes.push(i as u32);
+            (i * i..limit as usize).step_by(i).for_each(| j | composite[j] = true);
+        }
+    }
+
+    primes
+}
+
 /// Retourne un grand entier constitué de `szb` octets avec une bonne probabilité qu'il soit premier.
+/// Ses deux bits de poids fort sont à 1 : le produit de deux tels entiers fait donc exactement `2 * szb` octets.
 pub fn rand_primelike(szb: u64) -> BigUint
 {
-    let mut b = rand::thread_rng().gen_biguint(szb * 8);
+    rand_primelike_with(&mut rand::thread_rng(), szb)
+}
+
+/// Identique à `rand_primelike`, mais tire l'entier depuis la source d'aléa `rng` : la même source initialisée de la même manière donne donc le même entier.
+pub fn rand_primelike_with<R: Rng + ?Sized>(rng: &mut R, szb: u64) -> BigUint
+{
+    rand_primelike_bits_with(rng, szb * 8, 2)
+}
+
+/// Retourne un grand entier d'exactement `bits` bits, tiré depuis la source d'aléa `rng`, avec une bonne probabilité qu'il soit premier.
+/// Ses `top` bits de poids fort sont à 1 : le produit de k tels entiers fait exactement la somme de leurs tailles tant que (1 - 2^-top)^k >= 1/2,
+/// soit `top` = 2 pour deux entiers et `top` = 3 jusqu'à quatre entiers.
+pub fn rand_primelike_bits_with<R: Rng + ?Sized>(rng: &mut R, bits: u64, top: u64) -> BigUint
+{
+    let mut b = rng.gen_biguint(bits);
+    let top = top.min(bits);
+    let low = ((BigUint::one() << top) - 1u8) << (bits - top);
+    b |= &low;
     // On met le dernier chiffre à zéro
     b /= 10u8;
     b *= 10u8;
 
     // On génère un chiffre impair qui n'est pas 5 afin d'augmenter les chances que le nombre soit premier
     let mut digit = 0u8;
-    while digit % 2 == 0 || digit == 5
+    while digit.is_multiple_of(2) || digit == 5
     {
-        digit = rand::thread_rng().gen_range(1..10);
+        digit = rng.gen_range(1..10);
     }
     b += digit;
 
+    // Le changement de chiffre peut faire sortir l'entier de l'intervalle [low, 2^bits[ : on le décale alors d'une dizaine
+    if b.bits() > bits
+    {
+        b -= 10u8;
+    }
+    else if b < low && bits > 4
+    {
+        b += 10u8;
+    }
+
     b
-}
\ No newline at end of file
+}
@@ -1,7 +1,7 @@
 use std::string::FromUtf8Error;
 use num_bigint::BigUint;
 use num_traits::{Num, ToPrimitive};
-use crate::{maths::{NumUtil, VecNumUtil}, engines};
+use crate::{Error, maths::{NumUtil, VecNumUtil}, engines};
 
 /// Base (radix) à utiliser pour la transformation d'un message en sa version textuelle numérique (`to_nstr`).
 const NUM_STRING_RADIX: u32 = 36;
@@ -36,22 +36,43 @@ impl MessageBuilder
     }
 
     /// Construit le message avec les options indiquées.
+    /// Panique si les options sont invalides, voir `try_build` pour la variante faillible.
     pub fn build(self) -> Message
+    {
+        self.try_build().expect("MessageBuilder::build")
+    }
+
+    /// Construit le message avec les options indiquées.
+    /// Retourne une erreur si le texte ou la valeur numérique est vide, ou si la taille de bloc est nulle.
+    pub fn try_build(self) -> Result<Message, Error>
     {
         let bsize = if let Some(bsize) = self.bsize { bsize } else { engines::BSIZE_DEF };
+        if bsize == 0
+        {
+            return Err(Error::InvalidParameter(String::from("MessageBuilder::build : la taille de bloc ne peut pas être nulle.")));
+        }
         // La valeur numérique est soit celle indiquée dans le builder, soit la recomposition de la valeur textuelle (seul cas où la valeur numérique n'est pas calculée)
-        let nval = if let Some(nval) = self.nval { nval } else { self.strv.unwrap().into_bytes().rejoin() };
+        let nval = match (self.nval, self.strv)
+        {
+            (Some(nval), _) => nval,
+            (None, Some(strv)) => strv.into_bytes().try_rejoin()?,
+            (None, None) => return Err(Error::Empty(String::from("MessageBuilder::build : aucune valeur indiquée.")))
+        };
         // Les parties sont donc soit déjà présentes, soit elles sont calculées
         let parts = if let Some(parts) = self.parts { parts } else { nval.expl_r(bsize) };
+        if parts.is_empty()
+        {
+            return Err(Error::Empty(String::from("MessageBuilder::build : impossible de construire un message de valeur nulle.")));
+        }
 
-        Message
+        Ok(Message
         {
             nval,
             parts,
             bsize,
             padsize: if let Some(padsize) = self.padsize { padsize } else { engines::PADSIZE_DEF },
-            encrypted: self.encrypted.unwrap()
-        }
+            encrypted: self.encrypted.unwrap_or(false)
+        })
     }
 }
 
@@ -100,38 +121,69 @@ impl Message
 
     /// Initialise la construction d'un message depuis la représentation textuelle de sa valeur numérique.
     /// Le booléen `encrypted` permet d'indiquer si le nombre est déjà, ou non, chiffré.
+    /// Panique si la représentation textuelle est invalide, voir `try_nstr` pour la variante faillible.
     pub fn nstr(nstr: String, encrypted: bool) -> MessageBuilder
     {
-        Message::num(BigUint::from_str_radix(nstr.as_str(), NUM_STRING_RADIX).unwrap(), encrypted)
+        Message::try_nstr(nstr, encrypted).expect("Message::nstr")
+    }
+
+    /// Initialise la construction d'un message depuis la représentation textuelle de sa valeur numérique.
+    /// Le booléen `encrypted` permet d'indiquer si le nombre est déjà, ou non, chiffré.
+    pub fn try_nstr(nstr: String, encrypted: bool) -> Result<MessageBuilder, Error>
+    {
+        match BigUint::from_str_radix(nstr.as_str(), NUM_STRING_RADIX)
+        {
+            Ok(num) => Ok(Message::num(num, encrypted)),
+            Err(e) => Err(Error::Parse(String::from("Message::nstr : valeur numérique invalide."), Some(e)))
+        }
     }
 
     /// Initialise la construction d'un message depuis ses parties.
     /// Le booléen `encrypted` indique si oui ou non les parties sont déjà chiffrées.
+    /// Panique si l'ensemble des parties est vide, voir `try_parts` pour la variante faillible.
     pub fn parts(parts: Vec<BigUint>, encrypted: bool) -> MessageBuilder
+    {
+        Message::try_parts(parts, encrypted).expect("Message::parts")
+    }
+
+    /// Initialise la construction d'un message depuis ses parties.
+    /// Le booléen `encrypted` indique si oui ou non les parties sont déjà chiffrées.
+    pub fn try_parts(parts: Vec<BigUint>, encrypted: bool) -> Result<MessageBuilder, Error>
     {
         if parts.is_empty()
         {
-            panic!("Message::parts : impossible de construire un message depuis un ensemble vide.");
+            return Err(Error::Empty(String::from("Message::parts : impossible de construire un message depuis un ensemble vide.")));
         }
 
-        MessageBuilder
+        Ok(MessageBuilder
         {
-            nval: Some(parts.rejoin()),
+            nval: Some(parts.try_rejoin()?),
             parts: Some(parts),
             strv: None,
             bsize: None,
             padsize: None,
             encrypted: Some(encrypted)
-        }
+        })
     }
 
     /// Initialise la construction d'un message depuis la représentation textuelle de ses parties.
     /// Le booléen `encrypted` indique si oui ou non les parties sont déjà chiffrées.
+    /// Panique si la représentation textuelle est invalide, voir `try_parts_str` pour la variante faillible.
     pub fn parts_str(pstr: String, encrypted: bool) -> MessageBuilder
     {
-        Message::parts(pstr.split(PARTS_STR_SEP).map(| ps | {
-            BigUint::from_str_radix(ps, NUM_STRING_RADIX).unwrap()
-        }).collect(), encrypted)
+        Message::try_parts_str(pstr, encrypted).expect("Message::parts_str")
+    }
+
+    /// Initialise la construction d'un message depuis la représentation textuelle de ses parties.
+    /// Le booléen `encrypted` indique si oui ou non les parties sont déjà chiffrées.
+    pub fn try_parts_str(pstr: String, encrypted: bool) -> Result<MessageBuilder, Error>
+    {
+        let parts = pstr.trim().split(PARTS_STR_SEP).map(| ps | {
+            BigUint::from_str_radix(ps, NUM_STRING_RADIX)
+                .map_err(| e | Error::Parse(String::from("Message::parts_str : partie invalide."), Some(e)))
+        }).collect::<Result<Vec<BigUint>, Error>>()?;
+
+        Message::try_parts(parts, encrypted)
     }
 
     /// Retourne une référence sur la partie à l'indice donné.
@@ -146,6 +198,14 @@ impl Message
         self.nval = self.parts.rejoin();
     }
 
+    /// Rafraîchit la valeur numérique du message depuis les parties (si ces dernières ont été modifiées).
+    /// Retourne une erreur si le message ne contient aucune partie.
+    pub fn try_refresh_nval(&mut self) -> Result<(), Error>
+    {
+        self.nval = self.parts.try_rejoin()?;
+        Ok(())
+    }
+
     /// Rafraîchit les parties du message depuis sa valeur numérique (si cette dernière a été modifiée).
     pub fn refresh_parts(&mut self)
     {
@@ -0,0 +1,560 @@
+use crate::{Error, asn1::{self, Reader}, engines::DetachedSigner, keys::*, maths, rng::{HmacDrbg, RngHandle}};
+use num_bigint::{BigUint, RandBigInt};
+use num_traits::{Num, One, Zero};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+
+/// Taille en octets d'un élément du corps et d'un scalaire de P-256.
+pub const P256_FIELD_SIZE: usize = 32;
+/// Paramètres de la courbe P-256 (FIPS 186-5, SP 800-186) : y² = x³ - 3x + b modulo p, point de base G d'ordre premier n.
+const P256_P: &str = "ffffffff00000001000000000000000000000000ffffffffffffffffffffffff";
+const P256_B: &str = "5ac635d8aa3a93e7b3ebbd55769886bc651d06b0cc53b0f63bce3c3e27d2604b";
+const P256_N: &str = "ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551";
+const P256_GX: &str = "6b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296";
+const P256_GY: &str = "4fe342e2fe1a7f9b8ee7eb4a7c0f9e162bce33576b315ececbb6406837bf51f5";
+/// OID des clés publiques de courbe elliptique et de la courbe P-256 (RFC 5480).
+const OID_EC_PUBLIC_KEY: [u64; 6] = [1, 2, 840, 10045, 2, 1];
+const OID_PRIME256V1: [u64; 7] = [1, 2, 840, 10045, 3, 1, 7];
+/// Version de la structure ECPrivateKey (SEC1, RFC 5915).
+const EC_PRIVATE_KEY_VERSION: u8 = 1;
+
+
+/// Point de P-256 en coordonnées jacobiennes (X : Y : Z), avec x = X / Z² et y = Y / Z³ ; Z = 0 représente le point à l'infini.
+/// La suite des opérations de `Curve::scalar_mul` ne dépend pas du scalaire ; les grands entiers n'étant toutefois pas à temps constant,
+/// cette implémentation ne protège qu'en partie contre les attaques par canaux auxiliaires temporels.
+struct Point
+{
+    x: BigUint,
+    y: BigUint,
+    z: BigUint
+}
+
+/// Paramètres de P-256 et arithmétique des points.
+struct Curve
+{
+    p: BigUint,
+    b: BigUint,
+    n: BigUint,
+    g: Point
+}
+
+impl Curve
+{
+    fn new() -> Curve
+    {
+        let hex = | s | BigUint::from_str_radix(s, 16).expect("paramètre P-256");
+        Curve
+        {
+            p: hex(P256_P),
+            b: hex(P256_B),
+            n: hex(P256_N),
+            g: Point { x: hex(P256_GX), y: hex(P256_GY), z: BigUint::one() }
+        }
+    }
+
+    fn sub(&self, a: &BigUint, b: &BigUint) -> BigUint
+    {
+        (a + &self.p - b) % &self.p
+    }
+
+    fn mul(&self, a: &BigUint, b: &BigUint) -> BigUint
+    {
+        (a * b) % &self.p
+    }
+
+    fn infinity() -> Point
+    {
+        Point { x: BigUint::one(), y: BigUint::one(), z: BigUint::zero() }
+    }
+
+    /// Doublement (formules « dbl-2001-b » pour a = -3).
+    fn double(&self, p: &Point) -> Point
+    {
+        if p.z.is_zero() || p.y.is_zero()
+        {
+            return Curve::infinity();
+        }
+        let delta = self.mul(&p.z, &p.z);
+        let gamma = self.mul(&p.y, &p.y);
+        let beta = self.mul(&p.x, &gamma);
+        let alpha = self.mul(&(3u8 * self.sub(&p.x, &delta)), &((&p.x + &delta) % &self.p));
+        let x = self.sub(&self.mul(&alpha, &alpha), &((8u8 * &beta) % &self.p));
+        let yz = (&p.y + &p.z) % &self.p;
+        let z = self.sub(&self.sub(&self.mul(&yz, &yz), &gamma), &delta);
+        let y = self.sub(&self.mul(&alpha, &self.sub(&((4u8 * &beta) % &self.p), &x)), &((8u8 * self.mul(&gamma, &gamma)) % &self.p));
+
+        Point { x, y, z }
+    }
+
+    /// Addition de deux points (formules « add-2007-bl », le doublement et le point à l'infini étant traités à part).
+    fn add(&self, p: &Point, q: &Point) -> Point
+    {
+        if p.z.is_zero()
+        {
+            return Point { x: q.x.clone(), y: q.y.clone(), z: q.z.clone() };
+        }
+        if q.z.is_zero()
+        {
+            return Point { x: p.x.clone(), y: p.y.clone(), z: p.z.clone() };
+        }
+        let (z1z1, z2z2) = (self.mul(&p.z, &p.z), self.mul(&q.z, &q.z));
+        let (u1, u2) = (self.mul(&p.x, &z2z2), self.mul(&q.x, &z1z1));
+        let (s1, s2) = (self.mul(&self.mul(&p.y, &q.z), &z2z2), self.mul(&self.mul(&q.y, &p.z), &z1z1));
+        let (h, r) = (self.sub(&u2, &u1), self.sub(&s2, &s1));
+        if h.is_zero()
+        {
+            return if r.is_zero() { self.double(p) } else { Curve::infinity() };
+        }
+
+        let hh = self.mul(&h, &h);
+        let hhh = self.mul(&h, &hh);
+        let v = self.mul(&u1, &hh);
+        let x = self.sub(&self.sub(&self.mul(&r, &r), &hhh), &((2u8 * &v) % &self.p));
+        let y = self.sub(&self.mul(&r, &self.sub(&v, &x)), &self.mul(&s1, &hhh));
+        let z = self.mul(&self.mul(&p.z, &q.z), &h);
+
+        Point { x, y, z }
+    }
+
+    /// Échange conditionnel de deux points, sans branchement (voir `maths::cswap`).
+    fn cswap(p: &mut Point, q: &mut Point, swap: bool)
+    {
+        let bits = (P256_FIELD_SIZE * 8) as u64;
+        maths::cswap(&mut p.x, &mut q.x, swap, bits);
+        maths::cswap(&mut p.y, &mut q.y, swap, bits);
+        maths::cswap(&mut p.z, &mut q.z, swap, bits);
+    }
+
+    /// Multiplication d'un point par un scalaire (échelle de Montgomery), pour que le temps de calcul ne laisse pas fuir le nonce des signatures.
+    /// Le scalaire, réduit modulo n, est remplacé par k + n ou k + 2n, dont le bit de poids fort est toujours le bit 256 : chacune des 256 itérations
+    /// fait alors une addition et un doublement, les points étant échangés selon les bits du scalaire par `cswap`.
+    fn scalar_mul(&self, k: &BigUint, p: &Point) -> Point
+    {
+        let bits = (P256_FIELD_SIZE * 8) as u64;
+        let mut k1 = k % &self.n + &self.n;
+        let mut k2 = &k1 + &self.n;
+        let short = !k1.bit(bits);
+        maths::cswap(&mut k1, &mut k2, short, bits + 32);
+        maths::wipe(&mut k2);
+
+        let mut r0 = Point { x: p.x.clone(), y: p.y.clone(), z: p.z.clone() };
+        let mut r1 = self.double(p);
+        for i in (0..bits).rev()
+        {
+            let bit = k1.bit(i);
+            Curve::cswap(&mut r0, &mut r1, bit);
+            r1 = self.add(&r0, &r1);
+            r0 = self.double(&r0);
+            Curve::cswap(&mut r0, &mut r1, bit);
+        }
+        maths::wipe(&mut k1);
+        r0
+    }
+
+    /// Coordonnées affines d'un point, `None` pour le point à l'infini.
+    fn affine(&self, p: &Point) -> Option<(BigUint, BigUint)>
+    {
+        if p.z.is_zero()
+        {
+            return None;
+        }
+        let zi = maths::fmodpow(&p.z, &(&self.p - 2u8), &self.p);
+        let zi2 = self.mul(&zi, &zi);
+        Some((self.mul(&p.x, &zi2), self.mul(&p.y, &self.mul(&zi2, &zi))))
+    }
+
+    /// Membre de droite de l'équation de la courbe : x³ - 3x + b.
+    fn rhs(&self, x: &BigUint) -> BigUint
+    {
+        self.sub(&((self.mul(&self.mul(x, x), x) + &self.b) % &self.p), &((3u8 * x) % &self.p))
+    }
+
+    /// Inverse modulo n (premier) par le petit théorème de Fermat.
+    fn inv_n(&self, k: &BigUint) -> BigUint
+    {
+        maths::fmodpow(k, &(&self.n - 2u8), &self.n)
+    }
+}
+
+/// Encode un entier sur `P256_FIELD_SIZE` octets en gros boutiste (int2octets de la RFC 6979, coordonnées SEC1).
+fn to_be32(num: &BigUint) -> Zeroizing<[u8; P256_FIELD_SIZE]>
+{
+    let mut bytes = Zeroizing::new([0u8; P256_FIELD_SIZE]);
+    let be = Zeroizing::new(num.to_bytes_be());
+    bytes[P256_FIELD_SIZE - be.len()..].copy_from_slice(&be);
+    bytes
+}
+
+
+/// Clé publique P-256 : point Q = [d]G, en coordonnées affines. Sérialisation textuelle : encodage SEC1 non compressé en hexadécimal.
+pub struct P256PublicKey
+{
+    x: BigUint,
+    y: BigUint
+}
+
+impl P256PublicKey
+{
+    /// Abscisse du point.
+    pub fn x(&self) -> &BigUint
+    {
+        &self.x
+    }
+
+    /// Ordonnée du point.
+    pub fn y(&self) -> &BigUint
+    {
+        &self.y
+    }
+
+    /// Encode le point au format SEC1 (section 2.3.3) : 0x04 suivi des deux coordonnées, ou 0x02 / 0x03 (parité de y) suivi de l'abscisse s'il est compressé.
+    pub fn to_sec1(&self, compressed: bool) -> Vec<u8>
+    {
+        if compressed
+        {
+            [&[if self.y.bit(0) { 0x03 } else { 0x02 }], to_be32(&self.x).as_ref()].concat()
+        }
+        else
+        {
+            [&[0x04], to_be32(&self.x).as_ref(), to_be32(&self.y).as_ref()].concat()
+        }
+    }
+
+    /// Décode un point encodé au format SEC1, compressé ou non (section 2.3.4). Le point n'est pas validé, voir `Validate`.
+    pub fn from_sec1(bytes: &[u8]) -> Result<P256PublicKey, Error>
+    {
+        let invalid = || Error::Parse(String::from("P256PublicKey::from_sec1 : encodage SEC1 invalide."), None);
+        match (bytes.first(), bytes.len())
+        {
+            (Some(0x04), 65) => Ok(P256PublicKey
+            {
+                x: BigUint::from_bytes_be(&bytes[1..33]),
+                y: BigUint::from_bytes_be(&bytes[33..])
+            }),
+            (Some(prefix @ (0x02 | 0x03)), 33) => {
+                // p ≡ 3 (mod 4) : la racine carrée de y² est (y²)^((p + 1) / 4)
+                let c = Curve::new();
+                let x = BigUint::from_bytes_be(&bytes[1..]);
+                if x >= c.p
+                {
+                    return Err(invalid());
+                }
+                let y2 = c.rhs(&x);
+                let mut y = maths::fmodpow(&y2, &((&c.p + 1u8) >> 2u8), &c.p);
+                if c.mul(&y, &y) != y2
+                {
+                    return Err(Error::InvalidKey(String::from("l'abscisse ne correspond à aucun point de P-256.")));
+                }
+                if y.bit(0) != (*prefix == 0x03)
+                {
+                    y = &c.p - y;
+                }
+                Ok(P256PublicKey { x, y })
+            },
+            _ => Err(invalid())
+        }
+    }
+}
+
+impl Key for P256PublicKey
+{
+    const PARTS: Option<usize> = Some(1);
+
+    fn from_str(val: String) -> Result<Self, Error>
+    {
+        P256PublicKey::from_sec1(&hex_decode(&val, val.trim().len() / 2, "P256PublicKey")?)
+    }
+
+    fn serialize_str(&self) -> String
+    {
+        hex_encode(&self.to_sec1(false))
+    }
+}
+
+/// Validation d'une clé publique (SEC1, section 3.2.2.1) : coordonnées réduites et point sur la courbe. Le cofacteur valant 1, tout point de la courbe
+/// autre que le point à l'infini (non représentable ici) est d'ordre n.
+impl Validate for P256PublicKey
+{
+    fn validate(&self) -> Result<(), Error>
+    {
+        let c = Curve::new();
+        if self.x >= c.p || self.y >= c.p || c.mul(&self.y, &self.y) != c.rhs(&self.x)
+        {
+            return Err(Error::InvalidKey(String::from("le point n'appartient pas à la courbe P-256.")));
+        }
+
+        Ok(())
+    }
+}
+
+/// Clé privée P-256 : scalaire d dans [1, n - 1], effacé de la mémoire à la destruction de la clé. Sérialisation textuelle : 32 octets en hexadécimal.
+pub struct P256PrivateKey(NumKey);
+
+impl P256PrivateKey
+{
+    /// Construit une clé privée depuis son scalaire.
+    pub fn from(d: BigUint) -> P256PrivateKey
+    {
+        P256PrivateKey(NumKey::from(d))
+    }
+
+    /// Scalaire secret d.
+    pub fn value(&self) -> &BigUint
+    {
+        self.0.value()
+    }
+
+    /// Clé publique correspondante : Q = [d]G.
+    pub fn public(&self) -> P256PublicKey
+    {
+        let c = Curve::new();
+        // Une clé privée nulle ou multiple de n donnerait le point à l'infini, rejeté ensuite par la validation de la clé publique
+        let (x, y) = c.affine(&c.scalar_mul(self.value(), &c.g)).unwrap_or_default();
+        P256PublicKey { x, y }
+    }
+
+    /// Encode la clé au format SEC1 (ECPrivateKey, RFC 5915, DER), avec les paramètres de courbe et la clé publique, comme le fait OpenSSL.
+    pub fn to_sec1(&self) -> Zeroizing<Vec<u8>>
+    {
+        self.ec_private_key(true)
+    }
+
+    /// Décode une clé depuis le format SEC1 (ECPrivateKey, RFC 5915, DER). Les paramètres de courbe, s'ils sont présents, doivent désigner P-256,
+    /// et la clé publique éventuelle correspondre à la clé privée.
+    pub fn from_sec1(der: &[u8]) -> Result<P256PrivateKey, Error>
+    {
+        let mut seq = Reader::from(der).read_sequence()?;
+        if seq.read_integer()? != BigUint::from(EC_PRIVATE_KEY_VERSION)
+        {
+            return Err(Error::Parse(String::from("P256PrivateKey::from_sec1 : version ECPrivateKey non supportée."), None));
+        }
+        let d = seq.read_octets()?;
+        if d.len() != P256_FIELD_SIZE
+        {
+            return Err(Error::Parse(String::from("P256PrivateKey::from_sec1 : taille de clé invalide."), None));
+        }
+        if seq.peek_tag() == Some(asn1::TAG_CONTEXT) && seq.read_explicit(0)?.read_oid()? != OID_PRIME256V1
+        {
+            return Err(Error::Parse(String::from("P256PrivateKey::from_sec1 : courbe non supportée (P-256 attendue)."), None));
+        }
+
+        let key = P256PrivateKey::from(BigUint::from_bytes_be(d));
+        if seq.peek_tag() == Some(asn1::TAG_CONTEXT + 1)
+        {
+            let (q, stored) = (key.public(), P256PublicKey::from_sec1(seq.read_explicit(1)?.read_bit_string()?)?);
+            if q.x != stored.x || q.y != stored.y
+            {
+                return Err(Error::InvalidKey(String::from("la clé publique SEC1 ne correspond pas à la clé privée.")));
+            }
+        }
+
+        Ok(key)
+    }
+
+    /// Encode la clé au format PKCS#8 (PrivateKeyInfo, DER) de la RFC 5915 : la courbe figure dans l'algorithme, la structure SEC1 imbriquée ne la répète pas.
+    pub fn to_pkcs8(&self) -> Zeroizing<Vec<u8>>
+    {
+        let items = Zeroizing::new(vec![
+            asn1::integer(&BigUint::zero()),
+            asn1::sequence(&[asn1::oid(&OID_EC_PUBLIC_KEY), asn1::oid(&OID_PRIME256V1)]),
+            asn1::octets(&self.ec_private_key(false))
+        ]);
+
+        Zeroizing::new(asn1::sequence(&items))
+    }
+
+    /// Décode une clé depuis le format PKCS#8 (PrivateKeyInfo, DER) de la RFC 5915.
+    pub fn from_pkcs8(der: &[u8]) -> Result<P256PrivateKey, Error>
+    {
+        let mut seq = Reader::from(der).read_sequence()?;
+        seq.read_integer()?;
+        let mut alg = seq.read_sequence()?;
+        if alg.read_oid()? != OID_EC_PUBLIC_KEY || alg.read_oid()? != OID_PRIME256V1
+        {
+            return Err(Error::Parse(String::from("P256PrivateKey::from_pkcs8 : algorithme de clé non supporté (ECDSA P-256 attendu)."), None));
+        }
+
+        P256PrivateKey::from_sec1(seq.read_octets()?)
+    }
+
+    /// Structure ECPrivateKey, avec ou sans les paramètres de courbe.
+    fn ec_private_key(&self, params: bool) -> Zeroizing<Vec<u8>>
+    {
+        let mut items = Zeroizing::new(vec![
+            asn1::integer(&BigUint::from(EC_PRIVATE_KEY_VERSION)),
+            asn1::octets(to_be32(self.value()).as_ref())
+        ]);
+        if params
+        {
+            items.push(asn1::explicit(0, &asn1::oid(&OID_PRIME256V1)));
+        }
+        items.push(asn1::explicit(1, &asn1::bit_string(&self.public().to_sec1(false))));
+
+        Zeroizing::new(asn1::sequence(&items))
+    }
+}
+
+impl Key for P256PrivateKey
+{
+    const PARTS: Option<usize> = Some(1);
+
+    fn from_str(val: String) -> Result<Self, Error>
+    {
+        Ok(P256PrivateKey::from(BigUint::from_bytes_be(&hex_decode(&val, P256_FIELD_SIZE, "P256PrivateKey")?)))
+    }
+
+    fn serialize_str(&self) -> String
+    {
+        hex_encode(to_be32(self.value()).as_ref())
+    }
+}
+
+/// Validation d'une clé privée : le scalaire doit être dans [1, n - 1].
+impl Validate for P256PrivateKey
+{
+    fn validate(&self) -> Result<(), Error>
+    {
+        if self.value().is_zero() || *self.value() >= Curve::new().n
+        {
+            return Err(Error::InvalidKey(String::from("scalaire P-256 hors de l'intervalle [1, n - 1].")));
+        }
+
+        Ok(())
+    }
+}
+
+/// Clé principale P-256 : clé publique et clé privée.
+pub type P256Key = KeyPair<P256PublicKey, P256PrivateKey>;
+
+/// Validation d'une clé principale : les deux clés doivent être valides et la clé publique correspondre à la clé privée.
+impl Validate for P256Key
+{
+    fn validate(&self) -> Result<(), Error>
+    {
+        self.1.validate()?;
+        self.0.validate()?;
+        let q = self.1.public();
+        if q.x != self.0.x || q.y != self.0.y
+        {
+            return Err(Error::InvalidKey(String::from("la clé publique P-256 ne correspond pas à la clé privée.")));
+        }
+
+        Ok(())
+    }
+}
+
+
+/// Signature ECDSA sur la courbe P-256 avec SHA-256 (FIPS 186-5). Les nonces sont déterministes (RFC 6979) : dérivés de la clé privée et du condensat
+/// du message par un HMAC-DRBG, ils ne dépendent pas de la qualité de la source d'aléa, qui ne sert qu'à la génération de clé.
+pub struct P256
+{
+    rng: RngHandle
+}
+
+impl P256
+{
+    /// Construit un moteur de signature utilisant la source d'aléa du système.
+    pub fn new() -> P256
+    {
+        P256::with_rng(RngHandle::os())
+    }
+
+    /// Construit un moteur de signature utilisant la source d'aléa donnée, qui doit être cryptographiquement sûre (voir `RngHandle`).
+    pub fn with_rng(rng: RngHandle) -> P256
+    {
+        P256
+        {
+            rng
+        }
+    }
+
+    /// Donne la source d'aléa utilisée.
+    pub fn rng(&self) -> RngHandle
+    {
+        self.rng.clone()
+    }
+
+    /// Génère une clé principale : scalaire d tiré dans [1, n - 1] et point Q = [d]G.
+    pub fn try_generate(&self) -> Result<P256Key, Error>
+    {
+        let prk = P256PrivateKey::from(self.rng().gen_biguint_range(&BigUint::one(), &Curve::new().n));
+        Ok(KeyPair(prk.public(), prk))
+    }
+
+    /// Génère une clé principale, voir `try_generate`. Panique en cas d'erreur.
+    pub fn generate(&self) -> P256Key
+    {
+        self.try_generate().expect("P256.generate")
+    }
+}
+
+impl Default for P256
+{
+    fn default() -> Self
+    {
+        P256::new()
+    }
+}
+
+/// Signatures encodées en DER (séquence des entiers r et s), comme le prévoient la RFC 5480 et les outils courants.
+impl DetachedSigner for P256
+{
+    type SigningKey = P256PrivateKey;
+    type VerifyingKey = P256PublicKey;
+
+    fn try_sign_detached(&self, message: &[u8], key: &P256PrivateKey) -> Result<Vec<u8>, Error>
+    {
+        key.validate()?;
+        let c = Curve::new();
+        let d = key.value();
+        // Le condensat fait exactement la taille de n : bits2int se réduit à la conversion en entier
+        let e = BigUint::from_bytes_be(&Sha256::digest(message));
+
+        // RFC 6979, section 3.3 : le nonce est tiré d'un HMAC-DRBG instancié avec int2octets(d) et bits2octets(h), sans personnalisation
+        let mut drbg = HmacDrbg::instantiate(to_be32(d).as_ref(), to_be32(&(&e % &c.n)).as_ref(), &[]);
+        let mut t = Zeroizing::new([0u8; P256_FIELD_SIZE]);
+        loop
+        {
+            drbg.generate(t.as_mut(), &[])?;
+            let mut k = BigUint::from_bytes_be(t.as_ref());
+            if k.is_zero() || k >= c.n
+            {
+                continue;
+            }
+
+            let (x, _) = c.affine(&c.scalar_mul(&k, &c.g)).expect("[k]G n'est pas à l'infini pour 0 < k < n");
+            let r = x % &c.n;
+            let s = (c.inv_n(&k) * ((&e + &r * d) % &c.n)) % &c.n;
+            maths::wipe(&mut k);
+            if !r.is_zero() && !s.is_zero()
+            {
+                return Ok(asn1::sequence(&[asn1::integer(&r), asn1::integer(&s)]));
+            }
+        }
+    }
+
+    fn try_verify_detached(&self, message: &[u8], signature: &[u8], key: &P256PublicKey) -> Result<(), Error>
+    {
+        let invalid = || Error::Decoding(String::from("P256.verify : signature invalide."));
+        key.validate()?;
+        let mut outer = Reader::from(signature);
+        let mut seq = outer.read_sequence().map_err(|_| invalid())?;
+        let (r, s) = (seq.read_integer().map_err(|_| invalid())?, seq.read_integer().map_err(|_| invalid())?);
+        let c = Curve::new();
+        if !seq.is_empty() || !outer.is_empty() || r.is_zero() || s.is_zero() || r >= c.n || s >= c.n
+        {
+            return Err(invalid());
+        }
+
+        let e = BigUint::from_bytes_be(&Sha256::digest(message));
+        let w = c.inv_n(&s);
+        let (u1, u2) = ((&e * &w) % &c.n, (&r * &w) % &c.n);
+        let q = Point { x: key.x.clone(), y: key.y.clone(), z: BigUint::one() };
+        match c.affine(&c.add(&c.scalar_mul(&u1, &c.g), &c.scalar_mul(&u2, &q)))
+        {
+            Some((x, _)) if &x % &c.n == r => Ok(()),
+            _ => Err(invalid())
+        }
+    }
+}
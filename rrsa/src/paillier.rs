@@ -0,0 +1,263 @@
+use crate::{Error, engines::{self, Engine, GenControl, GenStats, RSA_MIN_MODULUS_BITS}, keys::*, maths, policy::{Padding, Policy}, rng::RngHandle};
+use std::time::Instant;
+use num_bigint::{BigUint, RandBigInt};
+use num_integer::Integer;
+use num_traits::{One, Zero};
+
+
+/// Taille par défaut en octets des facteurs premiers p et q des clés Paillier à générer (module de 2048 bits).
+pub const PAILLIER_DEF_PRIME_SIZEB: u64 = 128;
+
+
+/// Alias de type pour les clés de chiffrement Paillier : le module n seul, le générateur étant toujours g = n + 1.
+pub type PaillierPublicKey = NumKey;
+/// Alias de type pour les clés de déchiffrement Paillier, qui sont des paires de clés numériques (n, λ), λ étant l'indicatrice de Carmichael de n.
+pub type PaillierPrivateKey = KeyPair<NumKey, NumKey>;
+/// Alias de type pour les clés principales Paillier, paires composées d'une clé de chiffrement et de déchiffrement.
+pub type PaillierKey = KeyPair<PaillierPublicKey, PaillierPrivateKey>;
+
+
+/// Implémentation d'un moteur cryptographique de Paillier, additivement homomorphe : le produit de deux chiffrés modulo n² est un chiffré
+/// de la somme des clairs modulo n (voir `add_ciphertexts`), et la puissance k-ième d'un chiffré est un chiffré du clair multiplié par k (voir `mul_plaintext_scalar`).
+/// Le chiffrement, c = (1 + m * n) * r^n mod n² avec r aléatoire, est probabiliste : le nonce des messages est donc inutile et n'est pas ajouté,
+/// ce qui préserve les opérations homomorphes sur les nombres chiffrés.
+pub struct Paillier
+{
+    rng: RngHandle,
+    policy: Policy
+}
+
+impl Paillier
+{
+    /// Construit un moteur Paillier utilisant la source d'aléa du système.
+    pub fn new() -> Paillier
+    {
+        Paillier::with_rng(RngHandle::os())
+    }
+
+    /// Construit un moteur Paillier utilisant la source d'aléa donnée, qui doit être cryptographiquement sûre (voir `RngHandle`).
+    pub fn with_rng(rng: RngHandle) -> Paillier
+    {
+        Paillier
+        {
+            rng,
+            policy: Policy::new()
+        }
+    }
+
+    /// Modifie la politique de sécurité du moteur, stricte par défaut (voir `Policy::new`) : elle s'applique à la taille du module n
+    /// lors de la génération et du chiffrement.
+    pub fn with_policy(mut self, policy: Policy) -> Paillier
+    {
+        self.policy = policy;
+        self
+    }
+
+    /// Génère une clé principale sur `n_threads` threads avec la recherche parallèle de nombres premiers des moteurs, et retourne les statistiques de la génération.
+    /// `sz_b` est la taille en octets des facteurs premiers p et q : le module n = p * q fait donc `2 * sz_b` octets.
+    pub fn try_generate_controlled(&self, sz_b: u64, n_threads: u8, control: &GenControl) -> Result<(PaillierKey, GenStats), Error>
+    {
+        if sz_b == 0
+        {
+            return Err(Error::InvalidParameter(String::from("Paillier.generate : la taille de clé doit être non nulle.")));
+        }
+        self.policy.check_modulus_bits(sz_b * 16)?;
+        let start = Instant::now();
+
+        let (mut primes, mut stats) = engines::search_primes(&self.rng, 2, n_threads, control, | rng |
+        {
+            let mut candidate = maths::rand_primelike_with(rng, sz_b);
+            if maths::miller_rabin_with(rng, &candidate, engines::GEN_PRIME_ROUNDS)
+            {
+                return Some(candidate);
+            }
+            maths::wipe(&mut candidate);
+            None
+        })?;
+        let n = &primes[0] * &primes[1];
+        // p et q ayant la même taille, pgcd(n, (p - 1) * (q - 1)) = 1 : λ est donc inversible modulo n
+        let lambda = (&primes[0] - 1u8).lcm(&(&primes[1] - 1u8));
+        primes.iter_mut().for_each(maths::wipe);
+        stats.elapsed = start.elapsed();
+
+        Ok((KeyPair::from(NumKey::from(n.clone()), KeyPair::from(NumKey::from(n), NumKey::from(lambda))), stats))
+    }
+
+    /// Additionne homomorphiquement deux nombres chiffrés avec la clé `key` : le résultat est un chiffré de la somme des clairs, modulo n.
+    /// Retourne `Error::Decoding` si l'un des nombres n'est pas un chiffré valide (supérieur ou égal à n²).
+    pub fn add_ciphertexts(&self, a: &BigUint, b: &BigUint, key: &PaillierPublicKey) -> Result<BigUint, Error>
+    {
+        let n2 = Paillier::square_modulus(key)?;
+        Paillier::check_ciphertext(a, &n2)?;
+        Paillier::check_ciphertext(b, &n2)?;
+
+        Ok((a * b) % n2)
+    }
+
+    /// Multiplie homomorphiquement le clair d'un nombre chiffré avec la clé `key` par le scalaire `k` (en clair) : le résultat est un chiffré de k * m, modulo n.
+    /// Retourne `Error::Decoding` si le nombre n'est pas un chiffré valide (supérieur ou égal à n²).
+    pub fn mul_plaintext_scalar(&self, c: &BigUint, k: &BigUint, key: &PaillierPublicKey) -> Result<BigUint, Error>
+    {
+        let n2 = Paillier::square_modulus(key)?;
+        Paillier::check_ciphertext(c, &n2)?;
+
+        Ok(maths::fmodpow(c, k, &n2))
+    }
+
+    /// Carré du module d'une clé, qui doit être non trivial.
+    fn square_modulus(n: &NumKey) -> Result<BigUint, Error>
+    {
+        if n.value() <= &BigUint::one()
+        {
+            return Err(Error::InvalidKey(String::from("Paillier : module invalide.")));
+        }
+
+        Ok(n.value() * n.value())
+    }
+
+    /// Vérifie qu'un nombre chiffré est bien inférieur à n².
+    fn check_ciphertext(c: &BigUint, n2: &BigUint) -> Result<(), Error>
+    {
+        if c.is_zero() || c >= n2
+        {
+            return Err(Error::Decoding(String::from("Paillier : nombre chiffré invalide.")));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Paillier
+{
+    fn default() -> Self
+    {
+        Paillier::new()
+    }
+}
+
+/// Vérifie qu'un module Paillier est impair. Retourne `Error::WeakKey` s'il fait moins de `RSA_MIN_MODULUS_BITS` bits.
+fn validate_modulus(n: &BigUint) -> Result<(), Error>
+{
+    if n.is_even() || n <= &BigUint::one()
+    {
+        return Err(Error::InvalidKey(String::from("le module doit être impair.")));
+    }
+    if n.bits() < RSA_MIN_MODULUS_BITS
+    {
+        return Err(Error::WeakKey(format!("module de {} bits, au moins {} bits sont nécessaires.", n.bits(), RSA_MIN_MODULUS_BITS)));
+    }
+
+    Ok(())
+}
+
+/// Validation d'une clé principale Paillier : en plus de la validation du module (voir `validate_modulus`), vérifie que les modules sont identiques,
+/// que λ est inversible modulo n et qu'il annule bien le groupe multiplicatif modulo n (2^λ = 1 mod n).
+impl Validate for PaillierKey
+{
+    fn validate(&self) -> Result<(), Error>
+    {
+        let (n, lambda) = (self.0.value(), self.1.1.value());
+        if n != self.1.0.value()
+        {
+            return Err(Error::InvalidKey(String::from("les modules des clés publique et privée diffèrent.")));
+        }
+        validate_deferred(validate_modulus(n), ||
+        {
+            if lambda.is_zero() || !lambda.gcd(n).is_one() || !maths::fmodpow(&BigUint::from(2u8), lambda, n).is_one()
+            {
+                return Err(Error::InvalidKey(String::from("λ ne correspond pas au module.")));
+            }
+
+            Ok(())
+        })
+    }
+}
+
+impl Engine for Paillier
+{
+    type EncryptionKey = PaillierPublicKey;
+    type DecryptionKey = PaillierPrivateKey;
+    type MainKey = PaillierKey;
+
+    fn try_generate(&self, sz_b: u64, n_threads: u8) -> Result<Self::MainKey, Error>
+    {
+        self.try_generate_controlled(sz_b, n_threads, &GenControl::new()).map(| (key, _) | key)
+    }
+
+    fn try_gen_def(&self) -> Result<Self::MainKey, Error>
+    {
+        self.try_generate(PAILLIER_DEF_PRIME_SIZEB, engines::def_gthreads())
+    }
+
+    fn try_run_crypt(&self, num: &mut BigUint, key: &Self::EncryptionKey) -> Result<(), Error>
+    {
+        let n = key.value();
+        self.policy.check_modulus_bits(n.bits())?;
+        let n2 = Paillier::square_modulus(key)?;
+        if &*num >= n
+        {
+            return Err(Error::MessageTooLong);
+        }
+
+        // r doit être inversible modulo n ; il est éphémère et doit rester secret
+        let mut rng = self.rng();
+        let mut r = loop
+        {
+            let r = rng.gen_biguint_range(&BigUint::one(), n);
+            if r.gcd(n).is_one()
+            {
+                break r;
+            }
+        };
+        // g^m = (1 + n)^m = 1 + m * n mod n²
+        let gm = (&*num * n + 1u8) % &n2;
+        let mut rn = maths::fmodpow(&r, n, &n2);
+        *num = (gm * &rn) % n2;
+        maths::wipe(&mut r);
+        maths::wipe(&mut rn);
+        Ok(())
+    }
+
+    fn try_run_decrypt(&self, num: &mut BigUint, key: &Self::DecryptionKey) -> Result<(), Error>
+    {
+        let (n, lambda) = (key.0.value(), key.1.value());
+        let n2 = Paillier::square_modulus(&key.0)?;
+        Paillier::check_ciphertext(num, &n2)?;
+        // μ = L(g^λ mod n²)^-1 mod n, soit λ^-1 mod n puisque g = n + 1
+        let mut mu = maths::modinv(&(lambda % n), n).ok_or_else(|| Error::InvalidKey(String::from("Paillier : λ n'est pas inversible modulo n.")))?;
+
+        // L(x) = (x - 1) / n
+        let mut u = maths::fmodpow(num, lambda, &n2);
+        if u.is_zero()
+        {
+            return Err(Error::Decoding(String::from("Paillier.run_decrypt : nombre chiffré invalide.")));
+        }
+        let mut l = (&u - 1u8) / n;
+        *num = (&l * &mu) % n;
+        maths::wipe(&mut mu);
+        maths::wipe(&mut u);
+        maths::wipe(&mut l);
+        Ok(())
+    }
+
+    fn rng(&self) -> RngHandle
+    {
+        self.rng.clone()
+    }
+
+    fn policy(&self) -> Policy
+    {
+        self.policy
+    }
+
+    /// Le chiffrement étant déjà probabiliste, aucun nonce n'est ajouté : les opérations homomorphes portent ainsi directement sur les nombres clairs.
+    fn pad(&self, _num: &mut BigUint, _padsize: u32) {}
+
+    /// Aucun nonce n'ayant été ajouté, rien n'est retiré.
+    fn unpad(&self, _num: &mut BigUint, _padsize: u32) {}
+
+    fn padding(&self, _padsize: u32) -> Padding
+    {
+        Padding::Probabilistic
+    }
+}
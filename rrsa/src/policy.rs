@@ -0,0 +1,225 @@
+use std::fmt::Display;
+use num_bigint::BigUint;
+use num_integer::Integer;
+use num_traits::{One, Zero};
+use crate::Error;
+
+
+/// Taille minimale en bits du module imposée par la politique par défaut (NIST SP 800-131A).
+pub const POLICY_MIN_MODULUS_BITS: u64 = 2048;
+/// Plus petit exposant public autorisé par la politique par défaut (FIPS 186-5 : e > 2^16).
+pub const POLICY_MIN_EXPONENT: u64 = 65537;
+/// Taille minimale en octets du nonce (padding) des messages imposée par la politique par défaut.
+pub const POLICY_MIN_PADSIZE: u32 = 1;
+
+
+/// Schéma de padding appliqué aux messages par un moteur avant le chiffrement ou la signature (voir `Engine::padding`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Padding
+{
+    /// Aucun padding : le chiffrement est déterministe (RSA « textbook » avec un nonce nul, chiffrements classiques).
+    Raw,
+    /// Nonce aléatoire ajouté au nombre avant le chiffrement (voir `Engine::pad`).
+    Nonce,
+    /// Aucun padding, le chiffrement étant probabiliste par construction (Paillier).
+    Probabilistic
+}
+
+impl Padding
+{
+    /// Bit représentant le schéma dans l'ensemble des schémas autorisés d'une politique.
+    fn flag(self) -> u8
+    {
+        match self
+        {
+            Self::Raw => 1,
+            Self::Nonce => 2,
+            Self::Probabilistic => 4
+        }
+    }
+}
+
+impl Display for Padding
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        match self
+        {
+            Self::Raw => write!(f, "sans padding (déterministe)"),
+            Self::Nonce => write!(f, "nonce aléatoire"),
+            Self::Probabilistic => write!(f, "chiffrement probabiliste")
+        }
+    }
+}
+
+
+/// Politique de sécurité consultée par les moteurs lors de la génération de clés, du chiffrement et de la vérification de signatures :
+/// taille minimale du module, exposants publics autorisés (impairs et au moins égaux à un minimum), schémas de padding autorisés et taille minimale du nonce des messages.
+/// La politique par défaut (`Policy::new`) rejette les paramètres hérités ; `Policy::legacy` les autorise explicitement.
+/// Le déchiffrement n'est pas soumis à la politique, afin que les données chiffrées avec d'anciens paramètres restent lisibles.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Policy
+{
+    min_modulus_bits: u64,
+    min_exponent: u64,
+    min_padsize: u32,
+    paddings: u8
+}
+
+impl Policy
+{
+    /// Construit la politique par défaut : module d'au moins `POLICY_MIN_MODULUS_BITS` bits, exposant d'au moins `POLICY_MIN_EXPONENT`,
+    /// padding par nonce d'au moins `POLICY_MIN_PADSIZE` octet ou chiffrement probabiliste (les messages sans padding sont refusés).
+    pub fn new() -> Policy
+    {
+        Policy
+        {
+            min_modulus_bits: POLICY_MIN_MODULUS_BITS,
+            min_exponent: POLICY_MIN_EXPONENT,
+            min_padsize: POLICY_MIN_PADSIZE,
+            paddings: Padding::Nonce.flag() | Padding::Probabilistic.flag()
+        }
+    }
+
+    /// Construit une politique autorisant les paramètres hérités : toute taille de module, tout exposant impair à partir de 3, tout schéma de padding et messages sans nonce.
+    /// À réserver à la compatibilité avec d'anciennes clés et aux tests.
+    pub fn legacy() -> Policy
+    {
+        Policy
+        {
+            min_modulus_bits: 0,
+            min_exponent: 3,
+            min_padsize: 0,
+            paddings: Padding::Raw.flag() | Padding::Nonce.flag() | Padding::Probabilistic.flag()
+        }
+    }
+
+    /// Modifie la taille minimale en bits du module.
+    pub fn with_min_modulus_bits(mut self, bits: u64) -> Policy
+    {
+        self.min_modulus_bits = bits;
+        self
+    }
+
+    /// Modifie le plus petit exposant public autorisé (au moins 3).
+    pub fn with_min_exponent(mut self, e: u64) -> Policy
+    {
+        self.min_exponent = e.max(3);
+        self
+    }
+
+    /// Modifie la taille minimale en octets du nonce des messages.
+    pub fn with_min_padsize(mut self, padsize: u32) -> Policy
+    {
+        self.min_padsize = padsize;
+        self
+    }
+
+    /// Autorise ou interdit un schéma de padding.
+    pub fn with_padding(mut self, padding: Padding, allowed: bool) -> Policy
+    {
+        if allowed
+        {
+            self.paddings |= padding.flag();
+        }
+        else
+        {
+            self.paddings &= !padding.flag();
+        }
+        self
+    }
+
+    /// Indique si le schéma de padding donné est autorisé.
+    pub fn allows_padding(&self, padding: Padding) -> bool
+    {
+        self.paddings & padding.flag() != 0
+    }
+
+    /// Taille minimale en bits du module.
+    pub fn min_modulus_bits(&self) -> u64
+    {
+        self.min_modulus_bits
+    }
+
+    /// Plus petit exposant public autorisé.
+    pub fn min_exponent(&self) -> u64
+    {
+        self.min_exponent
+    }
+
+    /// Taille minimale en octets du nonce des messages.
+    pub fn min_padsize(&self) -> u32
+    {
+        self.min_padsize
+    }
+
+    /// Vérifie la taille en bits d'un module. Retourne `Error::Policy` si elle est inférieure au minimum.
+    pub fn check_modulus_bits(&self, bits: u64) -> Result<(), Error>
+    {
+        if bits < self.min_modulus_bits
+        {
+            return Err(Error::Policy(format!("module de {} bits, au moins {} bits sont exigés.", bits, self.min_modulus_bits)));
+        }
+        Ok(())
+    }
+
+    /// Vérifie un exposant public. Retourne `Error::Policy` s'il est pair ou inférieur au minimum.
+    pub fn check_exponent(&self, e: &BigUint) -> Result<(), Error>
+    {
+        if e.is_even() || e < &BigUint::from(self.min_exponent)
+        {
+            return Err(Error::Policy(format!("exposant public {} non autorisé, il doit être impair et au moins égal à {}.", e, self.min_exponent)));
+        }
+        Ok(())
+    }
+
+    /// Vérifie la taille en octets du nonce d'un message. Retourne `Error::Policy` si elle est inférieure au minimum.
+    pub fn check_padsize(&self, padsize: u32) -> Result<(), Error>
+    {
+        if padsize < self.min_padsize
+        {
+            return Err(Error::Policy(format!("nonce de {} octet(s), au moins {} sont exigés.", padsize, self.min_padsize)));
+        }
+        Ok(())
+    }
+
+    /// Vérifie le schéma de padding d'un message et, pour un nonce, sa taille en octets (voir `check_padsize`).
+    /// Retourne `Error::Policy` si le schéma n'est pas autorisé ou si le nonce est trop petit.
+    pub fn check_padding(&self, padding: Padding, padsize: u32) -> Result<(), Error>
+    {
+        if !self.allows_padding(padding)
+        {
+            return Err(Error::Policy(format!("schéma de padding non autorisé : {}.", padding)));
+        }
+        if padding == Padding::Nonce
+        {
+            self.check_padsize(padsize)?;
+        }
+        Ok(())
+    }
+
+    /// Choisit le plus petit exposant public autorisé premier avec `phi` (indicatrice d'Euler ou de Carmichael du module).
+    /// Retourne `None` si `phi` est nul.
+    pub fn exponent_for(&self, phi: &BigUint) -> Option<BigUint>
+    {
+        if phi.is_zero()
+        {
+            return None;
+        }
+        let mut e = BigUint::from(self.min_exponent | 1);
+        while !e.gcd(phi).is_one()
+        {
+            e += 2u8;
+        }
+
+        Some(e)
+    }
+}
+
+impl Default for Policy
+{
+    fn default() -> Self
+    {
+        Policy::new()
+    }
+}
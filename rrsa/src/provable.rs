@@ -0,0 +1,236 @@
+use num_bigint::{BigUint, RandBigInt};
+use num_integer::Integer;
+use num_traits::{One, ToPrimitive, Zero};
+use rand::Rng;
+use crate::{Error, keys::{Key, KEY_SERIAL_DELIMITER, NumKey}, maths};
+
+
+/// Taille maximale en bits d'un nombre premier prouvé par simple division (base de la récurrence de Maurer).
+pub const PROVABLE_BASE_BITS: u64 = 32;
+/// Taille minimale en bits d'un nombre premier prouvé.
+pub const PROVABLE_MIN_BITS: u64 = 2;
+/// Borne des petits nombres premiers utilisés pour écarter rapidement les candidats composés avant le test de Pocklington.
+const PROVABLE_SIEVE_LIMIT: u32 = 2048;
+/// Plus grand témoin essayé pour le test de Pocklington d'un candidat, avant de passer au candidat suivant.
+const PROVABLE_MAX_WITNESS: u32 = 32;
+
+
+/// Étape d'un certificat de primalité : le nombre `n = 2 * k * q + 1` est premier d'après le théorème de Pocklington,
+/// `q` étant le nombre premier prouvé à l'étape précédente (q² > n), et `witness` un témoin a tel que a^(n - 1) = 1 mod n et pgcd(a^((n - 1) / q) - 1, n) = 1.
+/// Le nombre de la dernière étape est un facteur de la clé : comme les clés, une étape n'est ni copiable ni affichable, et elle est effacée de la mémoire à sa destruction.
+pub struct Step
+{
+    /// Nombre premier prouvé par cette étape.
+    pub n: BigUint,
+    /// Témoin de Pocklington.
+    pub witness: BigUint
+}
+
+/// Certificat de primalité d'un nombre premier prouvé (algorithme de Maurer simplifié) : un petit nombre premier `base`, vérifiable par division,
+/// puis une chaîne d'étapes de Pocklington dont chacune s'appuie sur la précédente. Le nombre prouvé est celui de la dernière étape.
+/// Sérialisation textuelle : `base::n1::a1::n2::a2::...`, dans la même base que les clés.
+/// Le certificat contient le facteur premier qu'il prouve : il doit rester aussi secret que la clé privée, et il est effacé de la mémoire à sa destruction.
+pub struct Certificate
+{
+    /// Petit nombre premier (au plus `PROVABLE_BASE_BITS` bits) à la base de la chaîne.
+    pub base: BigUint,
+    /// Étapes de Pocklington, de la plus petite à la plus grande.
+    pub steps: Vec<Step>
+}
+
+impl Drop for Step
+{
+    fn drop(&mut self)
+    {
+        maths::wipe(&mut self.n);
+        maths::wipe(&mut self.witness);
+    }
+}
+
+impl Drop for Certificate
+{
+    fn drop(&mut self)
+    {
+        maths::wipe(&mut self.base);
+    }
+}
+
+impl Certificate
+{
+    /// Nombre premier prouvé par le certificat (dernière étape, ou base s'il n'y en a aucune).
+    pub fn prime(&self) -> &BigUint
+    {
+        self.steps.last().map_or(&self.base, | s | &s.n)
+    }
+
+    /// Vérifie entièrement le certificat, indépendamment de la manière dont il a été produit, et retourne le nombre premier prouvé.
+    /// Retourne `Error::InvalidParameter` si la base n'est pas première ou si l'une des étapes ne satisfait pas le théorème de Pocklington.
+    pub fn verify(&self) -> Result<BigUint, Error>
+    {
+        if self.base.bits() > PROVABLE_BASE_BITS || !self.base.to_u64().is_some_and(is_small_prime)
+        {
+            return Err(Error::InvalidParameter(String::from("Certificate.verify : la base du certificat n'est pas un petit nombre premier.")));
+        }
+
+        let mut q = &self.base;
+        for (i, step) in self.steps.iter().enumerate()
+        {
+            if !pocklington(&step.n, q, &step.witness)
+            {
+                return Err(Error::InvalidParameter(format!("Certificate.verify : l'étape n°{} ne satisfait pas le théorème de Pocklington.", i)));
+            }
+            q = &step.n;
+        }
+
+        Ok(q.clone())
+    }
+}
+
+/// Sérialisation textuelle d'un certificat, voir `Certificate`.
+impl Key for Certificate
+{
+    /// Le certificat n'est pas vérifié lors de la désérialisation (voir `verify`).
+    fn from_str(val: String) -> Result<Certificate, Error>
+    {
+        let mut nums = Vec::new();
+        for part in val.trim().split(KEY_SERIAL_DELIMITER)
+        {
+            nums.push(NumKey::from_str(String::from(part))?.value().clone());
+        }
+        if nums.len() % 2 == 0
+        {
+            return Err(Error::Parse(format!("Certificate::from_str : nombre de parties ({}) invalide pour un certificat.", nums.len()), None));
+        }
+
+        let mut nums = nums.into_iter();
+        let base = nums.next().unwrap();
+        let mut steps = Vec::new();
+        while let (Some(n), Some(witness)) = (nums.next(), nums.next())
+        {
+            steps.push(Step { n, witness });
+        }
+
+        Ok(Certificate { base, steps })
+    }
+
+    fn serialize_str(&self) -> String
+    {
+        let mut parts = vec![NumKey::from(self.base.clone()).serialize_str()];
+        for step in &self.steps
+        {
+            parts.push(NumKey::from(step.n.clone()).serialize_str());
+            parts.push(NumKey::from(step.witness.clone()).serialize_str());
+        }
+
+        parts.join(KEY_SERIAL_DELIMITER)
+    }
+}
+
+
+/// Génère un nombre premier prouvé d'exactement `bits` bits depuis la source d'aléa `rng`, avec son certificat de primalité.
+/// Ses deux bits de poids fort sont à 1 : le produit de deux tels nombres fait donc exactement `2 * bits` bits.
+/// Chaque étape double environ la taille du nombre premier de l'étape précédente ; la base est prouvée par division.
+pub fn provable_prime_with<R: Rng + ?Sized>(rng: &mut R, bits: u64) -> Result<Certificate, Error>
+{
+    provable_prime_top_with(rng, bits, 2)
+}
+
+/// Identique à `provable_prime_with`, mais avec les `top` bits de poids fort à 1 (voir `maths::rand_primelike_bits_with`),
+/// pour que le produit de trois ou quatre nombres premiers fasse exactement la somme de leurs tailles.
+pub(crate) fn provable_prime_top_with<R: Rng + ?Sized>(rng: &mut R, bits: u64, top: u64) -> Result<Certificate, Error>
+{
+    if bits < PROVABLE_MIN_BITS.max(top)
+    {
+        return Err(Error::InvalidParameter(format!("provable_prime : la taille doit être d'au moins {} bits.", PROVABLE_MIN_BITS.max(top))));
+    }
+    if bits <= PROVABLE_BASE_BITS
+    {
+        loop
+        {
+            let candidate = rng.gen_range((((1u64 << top) - 1) << (bits - top))..(1u64 << bits));
+            if is_small_prime(candidate)
+            {
+                return Ok(Certificate { base: BigUint::from(candidate), steps: Vec::new() });
+            }
+        }
+    }
+
+    // q a strictement plus de la moitié des bits de n, ce qui garantit q² > n
+    let mut cert = provable_prime_with(rng, bits.div_ceil(2) + 1)?;
+    let q = cert.prime().clone();
+    let sieve = maths::small_primes(PROVABLE_SIEVE_LIMIT);
+
+    // n = 2 * k * q + 1 doit être compris entre (2^top - 1) * 2^(bits - top) et 2^bits - 1
+    let two_q = &q * 2u8;
+    let k_min = (((BigUint::one() << top) - 1u8) << (bits - top)).div_ceil(&two_q);
+    let k_max = ((BigUint::one() << bits) - 2u8) / &two_q;
+    loop
+    {
+        let k = rng.gen_biguint_range(&k_min, &(&k_max + 1u8));
+        let n = &two_q * &k + 1u8;
+        if sieve.iter().any(| &p | (&n % p).is_zero() && n != BigUint::from(p))
+        {
+            continue;
+        }
+        for a in 2..PROVABLE_MAX_WITNESS
+        {
+            let witness = BigUint::from(a);
+            if !maths::fmodpow(&witness, &(&n - 1u8), &n).is_one()
+            {
+                // n est composé, inutile d'essayer d'autres témoins
+                break;
+            }
+            if pocklington(&n, &q, &witness)
+            {
+                cert.steps.push(Step { n, witness });
+                return Ok(cert);
+            }
+        }
+    }
+}
+
+/// Vérifie le théorème de Pocklington pour `n`, avec un facteur premier `q` de n - 1 tel que q² > n et le témoin `a`.
+fn pocklington(n: &BigUint, q: &BigUint, a: &BigUint) -> bool
+{
+    if n < &BigUint::from(3u8) || q.is_zero() || &(q * q) <= n
+    {
+        return false;
+    }
+    let n1 = n - 1u8;
+    let (m, r) = n1.div_rem(q);
+    if !r.is_zero() || a.is_zero() || a >= n
+    {
+        return false;
+    }
+    if !maths::fmodpow(a, &n1, n).is_one()
+    {
+        return false;
+    }
+    let am = maths::fmodpow(a, &m, n);
+
+    !am.is_zero() && ((am + n - 1u8) % n).gcd(n).is_one()
+}
+
+/// Teste la primalité d'un petit entier par division.
+fn is_small_prime(n: u64) -> bool
+{
+    if n < 2
+    {
+        return false;
+    }
+    if n.is_multiple_of(2)
+    {
+        return n == 2;
+    }
+    let mut d = 3u64;
+    while d * d <= n
+    {
+        if n.is_multiple_of(d)
+        {
+            return false;
+        }
+        d += 2;
+    }
+
+    true
+}
@@ -0,0 +1,294 @@
+use crate::{Error, engines::{self, Engine, GenControl, GenStats, RSA_MIN_MODULUS_BITS}, keys::*, maths, policy::Policy, rng::RngHandle};
+use std::time::Instant;
+use num_bigint::BigUint;
+use num_integer::Integer;
+use num_traits::{One, Zero};
+use rand::rngs::OsRng;
+
+
+/// Taille par défaut en octets des facteurs premiers p et q des clés Rabin à générer (module de 2048 bits).
+pub const RABIN_DEF_PRIME_SIZEB: u64 = 128;
+/// Nombre de bits de redondance ajoutés aux nombres à chiffrer : leurs derniers bits sont répétés, ce qui permet de reconnaître
+/// la bonne racine carrée parmi les quatre lors du déchiffrement. Une mauvaise racine n'est retenue qu'avec une probabilité de 2^-64.
+pub const RABIN_REDUNDANCY_BITS: u64 = 64;
+/// Nombre de tours de Miller-Rabin pour la validation des facteurs premiers lors de la vérification d'une clé.
+const RABIN_VALIDATE_ROUNDS: usize = 20;
+
+
+/// Clé de chiffrement Rabin : le module n = p * q.
+pub struct RabinPublicKey(pub NumKey);
+
+impl RabinPublicKey
+{
+    /// Construit une clé de chiffrement depuis le module n.
+    pub fn from(n: NumKey) -> RabinPublicKey
+    {
+        RabinPublicKey(n)
+    }
+}
+
+impl Key for RabinPublicKey
+{
+    const PARTS: Option<usize> = Some(1);
+
+    fn from_str(val: String) -> Result<Self, Error>
+    {
+        Ok(RabinPublicKey(NumKey::from_str(val)?))
+    }
+
+    fn serialize_str(&self) -> String
+    {
+        self.0.serialize_str()
+    }
+}
+
+/// Clé de déchiffrement Rabin : les facteurs premiers (p, q) du module, tous deux congrus à 3 modulo 4.
+/// Sérialisation textuelle : `p::q`.
+pub struct RabinPrivateKey(pub NumKey, pub NumKey);
+
+impl RabinPrivateKey
+{
+    /// Construit une clé de déchiffrement depuis les facteurs premiers p et q.
+    pub fn from(p: NumKey, q: NumKey) -> RabinPrivateKey
+    {
+        RabinPrivateKey(p, q)
+    }
+}
+
+impl Key for RabinPrivateKey
+{
+    const PARTS: Option<usize> = Some(2);
+
+    fn from_str(val: String) -> Result<Self, Error>
+    {
+        let parts: Vec<&str> = val.split(KEY_SERIAL_DELIMITER).collect();
+        if parts.len() != 2
+        {
+            return Err(Error::Parse(format!("RabinPrivateKey::from_str : nombre de parties ({}) invalide pour une clé privée Rabin.", parts.len()), None));
+        }
+
+        Ok(RabinPrivateKey(NumKey::from_str(String::from(parts[0]))?, NumKey::from_str(String::from(parts[1]))?))
+    }
+
+    fn serialize_str(&self) -> String
+    {
+        format!("{}{}{}", self.0.serialize_str(), KEY_SERIAL_DELIMITER, self.1.serialize_str())
+    }
+}
+
+/// Alias de type pour les clés principales Rabin, paires composées d'une clé de chiffrement et de déchiffrement.
+pub type RabinKey = KeyPair<RabinPublicKey, RabinPrivateKey>;
+
+
+/// Implémentation d'un moteur cryptographique de Rabin, à titre pédagogique et de comparaison avec `Rsa` : le chiffré d'un nombre m est c = m² mod n.
+/// Déchiffrer revient à extraire une racine carrée modulo n, aussi difficile que de factoriser n. Des quatre racines carrées de c, seule celle
+/// dont les `RABIN_REDUNDANCY_BITS` derniers bits sont répétés (voir `RABIN_REDUNDANCY_BITS`) est retenue : le nombre chiffré est donc un peu plus grand que le message.
+pub struct Rabin
+{
+    rng: RngHandle,
+    policy: Policy
+}
+
+impl Rabin
+{
+    /// Construit un moteur Rabin utilisant la source d'aléa du système.
+    pub fn new() -> Rabin
+    {
+        Rabin::with_rng(RngHandle::os())
+    }
+
+    /// Construit un moteur Rabin utilisant la source d'aléa donnée, qui doit être cryptographiquement sûre (voir `RngHandle`).
+    pub fn with_rng(rng: RngHandle) -> Rabin
+    {
+        Rabin
+        {
+            rng,
+            policy: Policy::new()
+        }
+    }
+
+    /// Modifie la politique de sécurité du moteur, stricte par défaut (voir `Policy::new`) : elle s'applique à la taille du module n
+    /// lors de la génération et du chiffrement, ainsi qu'au nonce des messages.
+    pub fn with_policy(mut self, policy: Policy) -> Rabin
+    {
+        self.policy = policy;
+        self
+    }
+
+    /// Génère une clé principale sur `n_threads` threads avec la recherche parallèle de nombres premiers des moteurs, et retourne les statistiques de la génération.
+    /// `sz_b` est la taille en octets des facteurs premiers p et q, congrus à 3 modulo 4 : le module n = p * q fait donc `2 * sz_b` octets.
+    pub fn try_generate_controlled(&self, sz_b: u64, n_threads: u8, control: &GenControl) -> Result<(RabinKey, GenStats), Error>
+    {
+        if sz_b == 0
+        {
+            return Err(Error::InvalidParameter(String::from("Rabin.generate : la taille de clé doit être non nulle.")));
+        }
+        self.policy.check_modulus_bits(sz_b * 16)?;
+        let start = Instant::now();
+
+        let (mut primes, mut stats) = engines::search_primes(&self.rng, 2, n_threads, control, | rng |
+        {
+            let mut candidate = maths::rand_primelike_with(rng, sz_b) | BigUint::from(3u8);
+            if maths::miller_rabin_with(rng, &candidate, engines::GEN_PRIME_ROUNDS)
+            {
+                return Some(candidate);
+            }
+            maths::wipe(&mut candidate);
+            None
+        })?;
+        let q = primes.pop().unwrap();
+        let p = primes.pop().unwrap();
+        stats.elapsed = start.elapsed();
+
+        Ok((KeyPair::from(RabinPublicKey::from(NumKey::from(&p * &q)), RabinPrivateKey::from(NumKey::from(p), NumKey::from(q))), stats))
+    }
+
+    /// Indique si une racine carrée porte la redondance attendue, c'est-à-dire si ses `RABIN_REDUNDANCY_BITS` derniers bits sont répétés juste avant.
+    fn redundant(root: &BigUint) -> bool
+    {
+        let mask = (BigUint::one() << RABIN_REDUNDANCY_BITS) - 1u8;
+        ((root >> RABIN_REDUNDANCY_BITS) & &mask) == (root & &mask)
+    }
+}
+
+impl Default for Rabin
+{
+    fn default() -> Self
+    {
+        Rabin::new()
+    }
+}
+
+/// Validation d'une clé publique Rabin : le module doit être impair. Retourne `Error::WeakKey` s'il fait moins de `RSA_MIN_MODULUS_BITS` bits.
+impl Validate for RabinPublicKey
+{
+    fn validate(&self) -> Result<(), Error>
+    {
+        let n = self.0.value();
+        if n.is_even() || n <= &BigUint::one()
+        {
+            return Err(Error::InvalidKey(String::from("le module doit être impair.")));
+        }
+        if n.bits() < RSA_MIN_MODULUS_BITS
+        {
+            return Err(Error::WeakKey(format!("module de {} bits, au moins {} bits sont nécessaires.", n.bits(), RSA_MIN_MODULUS_BITS)));
+        }
+
+        Ok(())
+    }
+}
+
+/// Validation d'une clé principale Rabin : en plus de la validation de la clé publique, vérifie que les facteurs sont premiers, distincts,
+/// congrus à 3 modulo 4 et que leur produit est le module.
+impl Validate for RabinKey
+{
+    fn validate(&self) -> Result<(), Error>
+    {
+        let (n, p, q) = (self.0.0.value(), self.1.0.value(), self.1.1.value());
+        validate_deferred(self.0.validate(), ||
+        {
+            if &(p * q) != n || p == q
+            {
+                return Err(Error::InvalidKey(String::from("le produit des facteurs premiers ne correspond pas au module.")));
+            }
+            if [p, q].iter().any(| r | r.mod_floor(&BigUint::from(4u8)) != BigUint::from(3u8) || !maths::miller_rabin_with(&mut OsRng, r, RABIN_VALIDATE_ROUNDS))
+            {
+                return Err(Error::InvalidKey(String::from("les facteurs du module doivent être premiers et congrus à 3 modulo 4.")));
+            }
+
+            Ok(())
+        })
+    }
+}
+
+impl Engine for Rabin
+{
+    type EncryptionKey = RabinPublicKey;
+    type DecryptionKey = RabinPrivateKey;
+    type MainKey = RabinKey;
+
+    fn try_generate(&self, sz_b: u64, n_threads: u8) -> Result<Self::MainKey, Error>
+    {
+        self.try_generate_controlled(sz_b, n_threads, &GenControl::new()).map(| (key, _) | key)
+    }
+
+    fn try_gen_def(&self) -> Result<Self::MainKey, Error>
+    {
+        self.try_generate(RABIN_DEF_PRIME_SIZEB, engines::def_gthreads())
+    }
+
+    fn try_run_crypt(&self, num: &mut BigUint, key: &Self::EncryptionKey) -> Result<(), Error>
+    {
+        let n = key.0.value();
+        self.policy.check_modulus_bits(n.bits())?;
+        if n.is_zero()
+        {
+            return Err(Error::InvalidKey(String::from("Rabin : module nul.")));
+        }
+
+        // Les derniers bits du nombre sont répétés à sa suite
+        let mask = (BigUint::one() << RABIN_REDUNDANCY_BITS) - 1u8;
+        let redundant = (&*num << RABIN_REDUNDANCY_BITS) | (&*num & mask);
+        if &redundant >= n
+        {
+            return Err(Error::MessageTooLong);
+        }
+
+        *num = maths::fmodpow(&redundant, &BigUint::from(2u8), n);
+        Ok(())
+    }
+
+    fn try_run_decrypt(&self, num: &mut BigUint, key: &Self::DecryptionKey) -> Result<(), Error>
+    {
+        let (p, q) = (key.0.value(), key.1.value());
+        let n = p * q;
+        if *num >= n
+        {
+            return Err(Error::Decoding(String::from("Rabin.run_decrypt : valeur chiffrée supérieure au module.")));
+        }
+        let (yp, yq) = match (maths::modinv(p, q), maths::modinv(q, p))
+        {
+            (Some(yp), Some(yq)) => (yp, yq),
+            _ => return Err(Error::InvalidKey(String::from("Rabin : facteurs premiers non premiers entre eux.")))
+        };
+
+        // Racines carrées modulo p et q (p ≡ q ≡ 3 mod 4), puis les quatre racines modulo n par le théorème des restes chinois
+        let mut mp = maths::fmodpow(&(&*num % p), &((p + 1u8) >> 2u8), p);
+        let mut mq = maths::fmodpow(&(&*num % q), &((q + 1u8) >> 2u8), q);
+        let (mut cp, mut cq) = (&mp * q * &yq, &mq * p * &yp);
+        let r1 = (&cp + &cq) % &n;
+        let r2 = (&cp + (&n - &cq % &n)) % &n;
+        // Deux racines distinctes dont la somme n'est pas n permettent de factoriser n : toutes sont effacées une fois la bonne retenue
+        let mut roots = vec![(&n - &r1) % &n, (&n - &r2) % &n, r1, r2];
+        roots.sort();
+        roots.dedup();
+        maths::wipe(&mut mp);
+        maths::wipe(&mut mq);
+        maths::wipe(&mut cp);
+        maths::wipe(&mut cq);
+
+        let found: Vec<usize> = (0..roots.len()).filter(| &i | Rabin::redundant(&roots[i])).collect();
+        let outcome = match found[..]
+        {
+            [i] => {
+                *num = &roots[i] >> RABIN_REDUNDANCY_BITS;
+                Ok(())
+            },
+            [] => Err(Error::Decoding(String::from("Rabin.run_decrypt : aucune racine carrée ne porte la redondance."))),
+            _ => Err(Error::Decoding(String::from("Rabin.run_decrypt : plusieurs racines carrées portent la redondance.")))
+        };
+        roots.iter_mut().for_each(maths::wipe);
+
+        outcome
+    }
+
+    fn rng(&self) -> RngHandle
+    {
+        self.rng.clone()
+    }
+
+    fn policy(&self) -> Policy
+    {
+        self.policy
+    }
+}
@@ -0,0 +1,349 @@
+use std::sync::{Arc, Mutex};
+use hmac::{Hmac, Mac};
+use rand::{CryptoRng, RngCore, rngs::OsRng};
+use sha2::Sha256;
+use zeroize::Zeroizing;
+use crate::{Error, keys::hex_decode};
+
+
+/// Intervalle de réensemencement par défaut du HMAC-DRBG : nombre de requêtes de génération au delà duquel un réensemencement est nécessaire.
+/// SP 800-90A autorise jusqu'à 2^48 requêtes.
+pub const DRBG_RESEED_INTERVAL_DEF: u64 = 1 << 20;
+/// Intervalle de réensemencement maximal autorisé par SP 800-90A.
+const DRBG_RESEED_INTERVAL_MAX: u64 = 1 << 48;
+/// Nombre maximal d'octets produits par requête de génération (2^19 bits selon SP 800-90A). Les requêtes plus grandes sont découpées.
+const DRBG_MAX_REQUEST_BYTES: usize = 1 << 16;
+/// Taille de l'entrée d'entropie, en octets : 256 bits pour la force de sécurité maximale de HMAC-SHA-256.
+const DRBG_ENTROPY_BYTES: usize = 32;
+/// Taille du nonce d'instanciation, en octets (la moitié de la force de sécurité).
+const DRBG_NONCE_BYTES: usize = 16;
+/// Taille d'un bloc de sortie (condensat SHA-256), en octets.
+const DRBG_OUTLEN: usize = 32;
+
+
+/// Poignée partagée vers une source d'aléa, détenue par les moteurs cryptographiques (génération de clés, padding...).
+/// Seules les sources cryptographiquement sûres (implémentant `CryptoRng`) sont acceptées : une clé de production ne doit jamais être générée depuis une source prévisible.
+/// Pour des tests reproductibles, il suffit d'injecter une source sûre initialisée depuis une graine fixe (`ChaCha20Rng::from_seed` par exemple).
+/// La poignée peut être clonée : les clones partagent la même source, protégée par un verrou.
+#[derive(Clone)]
+pub struct RngHandle
+{
+    rng: Arc<Mutex<dyn RngCore + Send>>
+}
+
+impl RngHandle
+{
+    /// Construit une poignée vers la source d'aléa du système d'exploitation (`OsRng`). Il s'agit de la source par défaut.
+    pub fn os() -> RngHandle
+    {
+        RngHandle::from(OsRng)
+    }
+
+    /// Construit une poignée vers une source d'aléa cryptographiquement sûre fournie par l'appelant (DRBG, source de test initialisée depuis une graine...).
+    pub fn from<R: RngCore + CryptoRng + Send + 'static>(rng: R) -> RngHandle
+    {
+        RngHandle
+        {
+            rng: Arc::new(Mutex::new(rng))
+        }
+    }
+
+    /// Exécute `f` sur la source d'aléa verrouillée. Un verrou empoisonné (panique d'un autre thread) n'empêche pas l'accès à la source.
+    fn with<T>(&self, f: impl FnOnce(&mut dyn RngCore) -> T) -> T
+    {
+        let mut rng = self.rng.lock().unwrap_or_else(| e | e.into_inner());
+        f(&mut *rng)
+    }
+}
+
+impl Default for RngHandle
+{
+    fn default() -> Self
+    {
+        RngHandle::os()
+    }
+}
+
+impl RngCore for RngHandle
+{
+    fn next_u32(&mut self) -> u32
+    {
+        self.with(| rng | rng.next_u32())
+    }
+
+    fn next_u64(&mut self) -> u64
+    {
+        self.with(| rng | rng.next_u64())
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8])
+    {
+        self.with(| rng | rng.fill_bytes(dest))
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error>
+    {
+        self.with(| rng | rng.try_fill_bytes(dest))
+    }
+}
+
+// Seules des sources implémentant `CryptoRng` peuvent être placées dans la poignée
+impl CryptoRng for RngHandle {}
+
+
+/// Générateur pseudo-aléatoire déterministe HMAC-DRBG (NIST SP 800-90A rév. 1, HMAC-SHA-256), ensemencé depuis une source d'entropie (celle du système par défaut).
+/// Le générateur est réensemencé automatiquement tous les `reseed_interval` appels, ou avant chaque requête si la résistance à la prédiction est activée.
+/// Tests de santé : un test à réponse connue (vecteur NIST CAVP) est exécuté à l'instanciation depuis une source d'entropie, et des tests continus rejettent
+/// toute entrée d'entropie ou tout bloc de sortie identique au précédent. Après un échec, le générateur refuse toute nouvelle requête.
+/// L'état interne (K, V) est effacé de la mémoire à la destruction du générateur.
+pub struct HmacDrbg
+{
+    k: Zeroizing<[u8; DRBG_OUTLEN]>,
+    v: Zeroizing<[u8; DRBG_OUTLEN]>,
+    reseed_counter: u64,
+    reseed_interval: u64,
+    prediction_resistance: bool,
+    entropy: Option<Box<dyn RngCore + Send>>,
+    last_entropy: Option<Zeroizing<Vec<u8>>>,
+    last_block: Option<Zeroizing<[u8; DRBG_OUTLEN]>>,
+    failed: bool
+}
+
+impl HmacDrbg
+{
+    /// Instancie un générateur ensemencé depuis la source d'entropie du système (`OsRng`), avec une chaîne de personnalisation optionnelle (éventuellement vide).
+    pub fn new(personalization: &[u8]) -> Result<HmacDrbg, Error>
+    {
+        HmacDrbg::with_entropy(OsRng, personalization)
+    }
+
+    /// Instancie un générateur ensemencé depuis la source d'entropie donnée, qui sera aussi utilisée pour les réensemencements.
+    /// Le test à réponse connue est exécuté au préalable : une erreur est retournée s'il échoue.
+    pub fn with_entropy<E: RngCore + CryptoRng + Send + 'static>(entropy: E, personalization: &[u8]) -> Result<HmacDrbg, Error>
+    {
+        HmacDrbg::self_test()?;
+
+        let mut source: Box<dyn RngCore + Send> = Box::new(entropy);
+        let mut seed = Zeroizing::new(vec![0u8; DRBG_ENTROPY_BYTES + DRBG_NONCE_BYTES]);
+        source.try_fill_bytes(&mut seed).map_err(| e | Error::Rng(e.to_string()))?;
+
+        let mut drbg = HmacDrbg::instantiate(&seed[..DRBG_ENTROPY_BYTES], &seed[DRBG_ENTROPY_BYTES..], personalization);
+        drbg.last_entropy = Some(Zeroizing::new(seed[..DRBG_ENTROPY_BYTES].to_vec()));
+        drbg.entropy = Some(source);
+
+        Ok(drbg)
+    }
+
+    /// Instancie un générateur depuis une entrée d'entropie, un nonce et une chaîne de personnalisation fournis explicitement (tests à réponse connue).
+    /// Sans source d'entropie, le générateur ne peut être réensemencé qu'avec `reseed_with` : une requête nécessitant un réensemencement automatique échoue.
+    pub fn instantiate(entropy_input: &[u8], nonce: &[u8], personalization: &[u8]) -> HmacDrbg
+    {
+        let mut drbg = HmacDrbg
+        {
+            k: Zeroizing::new([0x00; DRBG_OUTLEN]),
+            v: Zeroizing::new([0x01; DRBG_OUTLEN]),
+            reseed_counter: 1,
+            reseed_interval: DRBG_RESEED_INTERVAL_DEF,
+            prediction_resistance: false,
+            entropy: None,
+            last_entropy: None,
+            last_block: None,
+            failed: false
+        };
+        drbg.update(&[entropy_input, nonce, personalization]);
+
+        drbg
+    }
+
+    /// Active ou désactive la résistance à la prédiction : le générateur est alors réensemencé depuis sa source d'entropie avant chaque requête.
+    pub fn with_prediction_resistance(mut self, enabled: bool) -> HmacDrbg
+    {
+        self.prediction_resistance = enabled;
+        self
+    }
+
+    /// Modifie l'intervalle de réensemencement (nombre de requêtes entre deux réensemencements), borné entre 1 et 2^48.
+    pub fn with_reseed_interval(mut self, interval: u64) -> HmacDrbg
+    {
+        self.reseed_interval = interval.clamp(1, DRBG_RESEED_INTERVAL_MAX);
+        self
+    }
+
+    /// Compteur de réensemencement : nombre de requêtes de génération depuis le dernier (ré)ensemencement, plus un.
+    pub fn reseed_counter(&self) -> u64
+    {
+        self.reseed_counter
+    }
+
+    /// Réensemence le générateur depuis sa source d'entropie, avec une entrée additionnelle optionnelle (éventuellement vide).
+    pub fn reseed(&mut self, additional: &[u8]) -> Result<(), Error>
+    {
+        let mut entropy_input = Zeroizing::new(vec![0u8; DRBG_ENTROPY_BYTES]);
+        self.entropy.as_mut()
+            .ok_or_else(|| Error::Rng(String::from("HmacDrbg.reseed : aucune source d'entropie, le générateur doit être réensemencé explicitement.")))?
+            .try_fill_bytes(&mut entropy_input)
+            .map_err(| e | Error::Rng(e.to_string()))?;
+
+        // Test de santé continu de la source d'entropie : deux entrées successives identiques indiquent une source défaillante
+        if self.last_entropy.as_ref().is_some_and(| last | **last == *entropy_input)
+        {
+            self.failed = true;
+            return Err(Error::Rng(String::from("HmacDrbg.reseed : échec du test de santé continu, la source d'entropie a produit deux fois la même valeur.")));
+        }
+        self.reseed_with(&entropy_input, additional)?;
+        self.last_entropy = Some(entropy_input);
+
+        Ok(())
+    }
+
+    /// Réensemence le générateur depuis une entrée d'entropie fournie explicitement.
+    pub fn reseed_with(&mut self, entropy_input: &[u8], additional: &[u8]) -> Result<(), Error>
+    {
+        self.check_state()?;
+        self.update(&[entropy_input, additional]);
+        self.reseed_counter = 1;
+
+        Ok(())
+    }
+
+    /// Remplit `out` d'octets pseudo-aléatoires, avec une entrée additionnelle optionnelle (éventuellement vide).
+    /// Le générateur est réensemencé au préalable si la résistance à la prédiction est activée ou si l'intervalle de réensemencement est atteint.
+    pub fn generate(&mut self, out: &mut [u8], additional: &[u8]) -> Result<(), Error>
+    {
+        for chunk in out.chunks_mut(DRBG_MAX_REQUEST_BYTES)
+        {
+            self.generate_request(chunk, additional)?;
+        }
+
+        Ok(())
+    }
+
+    /// Test à réponse connue (vecteur NIST CAVP HMAC_DRBG SHA-256, sans résistance à la prédiction, COUNT = 0) : instanciation puis deux générations de 1024 bits.
+    pub fn self_test() -> Result<(), Error>
+    {
+        let entropy_input = hex_decode("ca851911349384bffe89de1cbdc46e6831e44d34a4fb935ee285dd14b71a7488", 32, "HmacDrbg")?;
+        let nonce = hex_decode("659ba96c601dc69fc902940805ec0ca8", 16, "HmacDrbg")?;
+        let expected = hex_decode("e528e9abf2dece54d47c7e75e5fe302149f817ea9fb4bee6f4199697d04d5b89d54fbb978a15b5c443c9ec21036d2460b6f73ebad0dc2aba6e624abf07745bc1\
+            07694bb7547bb0995f70de25d6b29e2d3011bb19d27676c07162c8b5ccde0668961df86803482cb37ed6d5c0bb8d50cf1f50d476aa0458bdaba806f48be9dcb8", 128, "HmacDrbg")?;
+
+        let mut drbg = HmacDrbg::instantiate(&entropy_input, &nonce, &[]);
+        let mut out = vec![0u8; expected.len()];
+        drbg.generate(&mut out, &[])?;
+        drbg.generate(&mut out, &[])?;
+
+        if out != *expected
+        {
+            return Err(Error::Rng(String::from("HmacDrbg.self_test : échec du test à réponse connue.")));
+        }
+
+        Ok(())
+    }
+
+    /// Requête de génération d'au plus `DRBG_MAX_REQUEST_BYTES` octets (algorithme HMAC_DRBG_Generate).
+    fn generate_request(&mut self, out: &mut [u8], additional: &[u8]) -> Result<(), Error>
+    {
+        self.check_state()?;
+        let mut additional = additional;
+        if self.prediction_resistance || self.reseed_counter > self.reseed_interval
+        {
+            self.reseed(additional)?;
+            // L'entrée additionnelle a été consommée par le réensemencement
+            additional = &[];
+        }
+        if !additional.is_empty()
+        {
+            self.update(&[additional]);
+        }
+
+        for chunk in out.chunks_mut(DRBG_OUTLEN)
+        {
+            let block = self.hmac(&[self.v.as_slice()]);
+            *self.v = *block;
+            // Test de santé continu de la sortie : deux blocs successifs identiques indiquent un état défaillant
+            if self.last_block.as_ref().is_some_and(| last | **last == *block)
+            {
+                self.failed = true;
+                return Err(Error::Rng(String::from("HmacDrbg.generate : échec du test de santé continu, deux blocs de sortie successifs sont identiques.")));
+            }
+            chunk.copy_from_slice(&block[..chunk.len()]);
+            self.last_block = Some(block);
+        }
+
+        self.update(&[additional]);
+        self.reseed_counter += 1;
+
+        Ok(())
+    }
+
+    /// Fonction de mise à jour de l'état interne (HMAC_DRBG_Update), les données fournies étant la concaténation de `provided`.
+    fn update(&mut self, provided: &[&[u8]])
+    {
+        let empty = provided.iter().all(| p | p.is_empty());
+
+        for round in [0x00u8, 0x01]
+        {
+            if round == 0x01 && empty
+            {
+                break;
+            }
+            let round = [round];
+            let mut parts: Vec<&[u8]> = vec![self.v.as_slice(), &round];
+            parts.extend_from_slice(provided);
+            self.k = self.hmac(&parts);
+            self.v = self.hmac(&[self.v.as_slice()]);
+        }
+    }
+
+    /// HMAC-SHA-256 avec la clé K courante sur la concaténation de `parts`.
+    fn hmac(&self, parts: &[&[u8]]) -> Zeroizing<[u8; DRBG_OUTLEN]>
+    {
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(self.k.as_slice()).expect("HMAC accepte toutes les tailles de clé");
+        for part in parts
+        {
+            mac.update(part);
+        }
+
+        Zeroizing::new(mac.finalize().into_bytes().into())
+    }
+
+    /// Vérifie que le générateur n'est pas dans l'état d'erreur consécutif à l'échec d'un test de santé.
+    fn check_state(&self) -> Result<(), Error>
+    {
+        if self.failed
+        {
+            return Err(Error::Rng(String::from("HmacDrbg : générateur hors service suite à l'échec d'un test de santé.")));
+        }
+
+        Ok(())
+    }
+}
+
+impl RngCore for HmacDrbg
+{
+    fn next_u32(&mut self) -> u32
+    {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64
+    {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8])
+    {
+        self.generate(dest, &[]).expect("HmacDrbg.fill_bytes")
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error>
+    {
+        self.generate(dest, &[]).map_err(rand::Error::new)
+    }
+}
+
+// Générateur conforme à SP 800-90A, ensemencé depuis une source sûre
+impl CryptoRng for HmacDrbg {}
@@ -0,0 +1,330 @@
+use aes_gcm::{Aes256Gcm, Nonce, aead::{Aead, KeyInit}};
+use cbc::cipher::{BlockDecryptMut, KeyIvInit, block_padding::Pkcs7};
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use num_bigint::BigUint;
+use rand::RngCore;
+use sha2::Sha256;
+use zeroize::Zeroizing;
+use crate::{Error, asn1::{self, Reader}, engines::{PrivateKey, RsaKey}, keys::{KeyPair, NumKey}, maths};
+
+
+/// Nombre d'itérations PBKDF2 par défaut pour la dérivation de la clé de chiffrement depuis la phrase de passe.
+pub const PBKDF2_ITER_DEF: u32 = 600_000;
+/// Nombre d'itérations PBKDF2 maximal accepté, pour qu'un en-tête forgé ne bloque pas le déchiffrement pendant des heures.
+pub const PBKDF2_ITER_MAX: u32 = 10 * PBKDF2_ITER_DEF;
+/// Taille en octets du sel aléatoire utilisé par PBKDF2.
+const SALT_SIZE: usize = 16;
+/// Taille en octets du nonce AES-GCM.
+const NONCE_SIZE: usize = 12;
+/// Taille en octets de la clé AES-256.
+const AES_KEY_SIZE: usize = 32;
+/// Taille en octets du tag d'authentification AES-GCM.
+const TAG_SIZE: usize = 16;
+/// Nombre de caractères par ligne dans un fichier PEM.
+const PEM_LINE_LEN: usize = 64;
+/// Libellé PEM d'une clé privée chiffrée (PKCS#8 EncryptedPrivateKeyInfo).
+const PEM_ENCRYPTED_LABEL: &str = "ENCRYPTED PRIVATE KEY";
+
+const OID_RSA_ENCRYPTION: [u64; 7] = [1, 2, 840, 113549, 1, 1, 1];
+const OID_PBES2: [u64; 7] = [1, 2, 840, 113549, 1, 5, 13];
+const OID_PBKDF2: [u64; 7] = [1, 2, 840, 113549, 1, 5, 12];
+const OID_HMAC_SHA256: [u64; 6] = [1, 2, 840, 113549, 2, 9];
+const OID_AES256_GCM: [u64; 9] = [2, 16, 840, 1, 101, 3, 4, 1, 46];
+const OID_AES256_CBC: [u64; 9] = [2, 16, 840, 1, 101, 3, 4, 1, 42];
+
+type Aes256CbcDec = cbc::Decryptor<aes::Aes256>;
+
+
+/// Indique si le contenu d'un fichier clé correspond à une clé privée chiffrée (PEM PKCS#8).
+pub fn is_encrypted(contents: &str) -> bool
+{
+    contents.trim_start().starts_with(&format!("-----BEGIN {}-----", PEM_ENCRYPTED_LABEL))
+}
+
+/// Encode une clé principale RSA au format PKCS#1 (RSAPrivateKey, DER).
+/// Pour une clé à deux facteurs, les facteurs premiers et paramètres CRT, absents du format rrsa, sont retrouvés depuis les exposants.
+/// Une clé multi-premiers est encodée en version 1, les facteurs au delà des deux premiers figurant dans `otherPrimeInfos` (RFC 8017, A.1.2).
+pub fn to_pkcs1(key: &RsaKey) -> Result<Zeroizing<Vec<u8>>, Error>
+{
+    let (n, e, d) = (key.0.0.value(), key.0.1.value(), key.1.1.value());
+    let invalid = || Error::InvalidKey(String::from("to_pkcs1 : facteurs invalides."));
+    let multi = key.1.2.len() > 2;
+    // Les valeurs secrètes sont conservées dans des clés numériques afin d'être effacées après usage
+    let (p, q) =
+        if multi
+        {
+            (NumKey::from(key.1.2[0].value().clone()), NumKey::from(key.1.2[1].value().clone()))
+        }
+        else
+        {
+            let (p, q) = maths::factor_from_exponents(n, e, d).ok_or_else(|| Error::InvalidKey(String::from("to_pkcs1 : exposants incohérents avec le module.")))?;
+            // Par convention, p est le plus grand des deux facteurs
+            if p > q { (NumKey::from(p), NumKey::from(q)) } else { (NumKey::from(q), NumKey::from(p)) }
+        };
+    let qinv = NumKey::from(maths::modinv(q.value(), p.value()).ok_or_else(invalid)?);
+    let dp = NumKey::from(d % (p.value() - 1u8));
+    let dq = NumKey::from(d % (q.value() - 1u8));
+
+    let mut items = Zeroizing::new(vec![
+        asn1::integer(&BigUint::from(if multi { 1u8 } else { 0u8 })),
+        asn1::integer(n),
+        asn1::integer(e),
+        asn1::integer(d),
+        asn1::integer(p.value()),
+        asn1::integer(q.value()),
+        asn1::integer(dp.value()),
+        asn1::integer(dq.value()),
+        asn1::integer(qinv.value())
+    ]);
+
+    if multi
+    {
+        // Chaque facteur supplémentaire r_i est accompagné de d mod (r_i - 1) et de l'inverse du produit des facteurs précédents modulo r_i
+        let mut prod = NumKey::from(p.value() * q.value());
+        let mut infos = Zeroizing::new(Vec::with_capacity(key.1.2.len() - 2));
+        for r in key.1.2[2..].iter().map(NumKey::value)
+        {
+            let dr = NumKey::from(d % (r - 1u8));
+            let t = NumKey::from(maths::modinv(&(prod.value() % r), r).ok_or_else(invalid)?);
+            infos.push(asn1::sequence(&Zeroizing::new(vec![asn1::integer(r), asn1::integer(dr.value()), asn1::integer(t.value())])));
+            prod = NumKey::from(prod.value() * r);
+        }
+        items.push(asn1::sequence(&infos));
+    }
+
+    Ok(Zeroizing::new(asn1::sequence(&items)))
+}
+
+/// Décode une clé principale RSA depuis le format PKCS#1 (RSAPrivateKey, DER).
+pub fn from_pkcs1(der: &[u8]) -> Result<RsaKey, Error>
+{
+    let mut seq = Reader::from(der).read_sequence()?;
+    let version = seq.read_integer()?;
+    if version > BigUint::from(1u8)
+    {
+        return Err(Error::Parse(String::from("from_pkcs1 : version de clé non supportée."), None));
+    }
+    let (n, e, d) = (seq.read_integer()?, seq.read_integer()?, seq.read_integer()?);
+    let (n, e, d) = (NumKey::from(n), NumKey::from(e), NumKey::from(d));
+
+    // Une clé à deux facteurs (version 0) garde le format rrsa habituel ; les facteurs d'une clé multi-premiers (version 1) sont conservés
+    let mut primes = Vec::new();
+    if version == BigUint::from(1u8)
+    {
+        primes.push(NumKey::from(seq.read_integer()?));
+        primes.push(NumKey::from(seq.read_integer()?));
+        // dp, dq et qinv sont recalculés lors des opérations privées : ils sont lus puis effacés
+        for _ in 0..3
+        {
+            NumKey::from(seq.read_integer()?);
+        }
+        let mut infos = seq.read_sequence()?;
+        while !infos.is_empty()
+        {
+            let mut info = infos.read_sequence()?;
+            primes.push(NumKey::from(info.read_integer()?));
+            NumKey::from(info.read_integer()?);
+            NumKey::from(info.read_integer()?);
+        }
+    }
+
+    Ok(KeyPair::from(
+        KeyPair::from(NumKey::from(n.value().clone()), e),
+        PrivateKey::with_primes(n, d, primes)
+    ))
+}
+
+/// Encode une clé principale RSA au format PKCS#8 (PrivateKeyInfo, DER).
+pub fn to_pkcs8(key: &RsaKey) -> Result<Zeroizing<Vec<u8>>, Error>
+{
+    let items = Zeroizing::new(vec![
+        asn1::integer(&BigUint::from(0u8)),
+        asn1::sequence(&[asn1::oid(&OID_RSA_ENCRYPTION), asn1::null()]),
+        asn1::octets(&to_pkcs1(key)?)
+    ]);
+
+    Ok(Zeroizing::new(asn1::sequence(&items)))
+}
+
+/// Décode une clé principale RSA depuis le format PKCS#8 (PrivateKeyInfo, DER).
+pub fn from_pkcs8(der: &[u8]) -> Result<RsaKey, Error>
+{
+    let mut seq = Reader::from(der).read_sequence()?;
+    seq.read_integer()?;
+    let mut alg = seq.read_sequence()?;
+    if alg.read_oid()? != OID_RSA_ENCRYPTION
+    {
+        return Err(Error::Parse(String::from("from_pkcs8 : algorithme de clé non supporté."), None));
+    }
+    alg.read_null()?;
+
+    from_pkcs1(seq.read_octets()?)
+}
+
+/// Chiffre une clé principale RSA avec une phrase de passe et retourne le fichier PEM correspondant (PKCS#8 EncryptedPrivateKeyInfo), voir `encrypt_pkcs8`.
+pub fn encrypt_key(key: &RsaKey, passphrase: &str, iterations: u32) -> Result<String, Error>
+{
+    encrypt_pkcs8(&to_pkcs8(key)?, passphrase, iterations)
+}
+
+/// Chiffre une clé privée déjà encodée au format PKCS#8 (PrivateKeyInfo, DER) avec une phrase de passe et retourne le fichier PEM correspondant (EncryptedPrivateKeyInfo).
+/// La clé de chiffrement AES-256-GCM est dérivée par PBKDF2-HMAC-SHA256 avec `iterations` itérations ; le sel, le nombre d'itérations et le nonce sont stockés dans l'en-tête.
+pub fn encrypt_pkcs8(der: &[u8], passphrase: &str, iterations: u32) -> Result<String, Error>
+{
+    if iterations == 0 || iterations > PBKDF2_ITER_MAX
+    {
+        return Err(Error::InvalidParameter(format!("encrypt_pkcs8 : le nombre d'itérations doit être compris entre 1 et {}.", PBKDF2_ITER_MAX)));
+    }
+    let mut salt = [0u8; SALT_SIZE];
+    let mut nonce = [0u8; NONCE_SIZE];
+    rand::thread_rng().try_fill_bytes(&mut salt).map_err(| e | Error::Rng(e.to_string()))?;
+    rand::thread_rng().try_fill_bytes(&mut nonce).map_err(| e | Error::Rng(e.to_string()))?;
+
+    let aes_key = derive(passphrase, &salt, iterations);
+    let cipher = Aes256Gcm::new_from_slice(aes_key.as_slice()).map_err(|_| Error::InvalidParameter(String::from("encrypt_pkcs8 : clé AES invalide.")))?;
+    // Le tag d'authentification est ajouté à la fin du chiffré, comme le prévoit la RFC 5084
+    let encrypted = cipher.encrypt(Nonce::from_slice(&nonce), der)
+        .map_err(|_| Error::InvalidParameter(String::from("encrypt_pkcs8 : échec du chiffrement.")))?;
+
+    let der = asn1::sequence(&[
+        asn1::sequence(&[
+            asn1::oid(&OID_PBES2),
+            asn1::sequence(&[
+                asn1::sequence(&[
+                    asn1::oid(&OID_PBKDF2),
+                    asn1::sequence(&[
+                        asn1::octets(&salt),
+                        asn1::integer(&BigUint::from(iterations)),
+                        asn1::integer(&BigUint::from(AES_KEY_SIZE)),
+                        asn1::sequence(&[asn1::oid(&OID_HMAC_SHA256), asn1::null()])
+                    ])
+                ]),
+                asn1::sequence(&[
+                    asn1::oid(&OID_AES256_GCM),
+                    asn1::sequence(&[
+                        asn1::octets(&nonce),
+                        asn1::integer(&BigUint::from(TAG_SIZE))
+                    ])
+                ])
+            ])
+        ]),
+        asn1::octets(&encrypted)
+    ]);
+
+    Ok(pem_encode(PEM_ENCRYPTED_LABEL, &der))
+}
+
+/// Déchiffre une clé principale RSA depuis un fichier PEM chiffré (PKCS#8 EncryptedPrivateKeyInfo) et sa phrase de passe, voir `decrypt_pkcs8`.
+pub fn decrypt_key(pem: &str, passphrase: &str) -> Result<RsaKey, Error>
+{
+    from_pkcs8(&decrypt_pkcs8(pem, passphrase)?)
+}
+
+/// Déchiffre un fichier PEM chiffré (PKCS#8 EncryptedPrivateKeyInfo) avec sa phrase de passe et retourne la clé privée encodée au format PKCS#8 (PrivateKeyInfo, DER).
+/// Les fichiers chiffrés en AES-256-CBC (tels que ceux produits par `openssl pkcs8 -topk8 -v2 aes-256-cbc`) sont également acceptés.
+/// Échoue si la phrase de passe est incorrecte ou si le fichier a été altéré.
+pub fn decrypt_pkcs8(pem: &str, passphrase: &str) -> Result<Zeroizing<Vec<u8>>, Error>
+{
+    let der = pem_decode(PEM_ENCRYPTED_LABEL, pem)?;
+    let mut epki = Reader::from(&der).read_sequence()?;
+    let mut alg = epki.read_sequence()?;
+    if alg.read_oid()? != OID_PBES2
+    {
+        return Err(Error::Parse(String::from("decrypt_pkcs8 : schéma de chiffrement non supporté (PBES2 attendu)."), None));
+    }
+    let mut pbes2 = alg.read_sequence()?;
+
+    // Fonction de dérivation : PBKDF2-HMAC-SHA256
+    let mut kdf = pbes2.read_sequence()?;
+    if kdf.read_oid()? != OID_PBKDF2
+    {
+        return Err(Error::Parse(String::from("decrypt_pkcs8 : fonction de dérivation non supportée (PBKDF2 attendu)."), None));
+    }
+    let mut kdf_params = kdf.read_sequence()?;
+    let salt = kdf_params.read_octets()?;
+    let iterations = kdf_params.read_integer()?.to_u32_digits();
+    let iterations = match iterations.as_slice()
+    {
+        [it] if *it <= PBKDF2_ITER_MAX => *it,
+        _ => return Err(Error::Parse(String::from("decrypt_pkcs8 : nombre d'itérations invalide ou trop élevé."), None))
+    };
+    if kdf_params.peek_tag() == Some(asn1::TAG_INTEGER) && kdf_params.read_integer()? != BigUint::from(AES_KEY_SIZE)
+    {
+        return Err(Error::Parse(String::from("decrypt_pkcs8 : taille de clé dérivée non supportée."), None));
+    }
+    if kdf_params.is_empty() || kdf_params.read_sequence()?.read_oid()? != OID_HMAC_SHA256
+    {
+        return Err(Error::Parse(String::from("decrypt_pkcs8 : pseudo-aléa PBKDF2 non supporté (HMAC-SHA256 attendu)."), None));
+    }
+
+    let aes_key = derive(passphrase, salt, iterations);
+    let encrypted = epki.read_octets()?;
+    let mut scheme = pbes2.read_sequence()?;
+    let scheme_oid = scheme.read_oid()?;
+
+    let plain = Zeroizing::new(
+        if scheme_oid == OID_AES256_GCM
+        {
+            // Schéma de chiffrement authentifié : AES-256-GCM, celui utilisé par `encrypt_key`
+            let mut gcm_params = scheme.read_sequence()?;
+            let nonce = gcm_params.read_octets()?;
+            if nonce.len() != NONCE_SIZE || (!gcm_params.is_empty() && gcm_params.read_integer()? != BigUint::from(TAG_SIZE))
+            {
+                return Err(Error::Parse(String::from("decrypt_pkcs8 : paramètres AES-GCM non supportés."), None));
+            }
+
+            let cipher = Aes256Gcm::new_from_slice(aes_key.as_slice()).map_err(|_| Error::InvalidParameter(String::from("decrypt_pkcs8 : clé AES invalide.")))?;
+            cipher.decrypt(Nonce::from_slice(nonce), encrypted)
+                .map_err(|_| Error::Decoding(String::from("decrypt_pkcs8 : phrase de passe incorrecte ou fichier altéré.")))?
+        }
+        else if scheme_oid == OID_AES256_CBC
+        {
+            // AES-256-CBC n'est accepté qu'en lecture, pour les clés produites par d'autres outils (OpenSSL notamment)
+            let iv = scheme.read_octets()?;
+            let cipher = Aes256CbcDec::new_from_slices(aes_key.as_slice(), iv).map_err(|_| Error::Parse(String::from("decrypt_pkcs8 : paramètres AES-CBC non supportés."), None))?;
+            cipher.decrypt_padded_vec_mut::<Pkcs7>(encrypted)
+                .map_err(|_| Error::Decoding(String::from("decrypt_pkcs8 : phrase de passe incorrecte ou fichier altéré.")))?
+        }
+        else
+        {
+            return Err(Error::Parse(String::from("decrypt_pkcs8 : algorithme de chiffrement non supporté (AES-256-GCM ou AES-256-CBC attendu)."), None));
+        });
+
+    Ok(plain)
+}
+
+/// Dérive la clé AES depuis la phrase de passe avec PBKDF2-HMAC-SHA256.
+fn derive(passphrase: &str, salt: &[u8], iterations: u32) -> Zeroizing<[u8; AES_KEY_SIZE]>
+{
+    let mut key = Zeroizing::new([0u8; AES_KEY_SIZE]);
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, iterations, key.as_mut());
+
+    key
+}
+
+/// Encode des données DER en PEM (base64 découpé en lignes, encadré par le libellé).
+fn pem_encode(label: &str, der: &[u8]) -> String
+{
+    let b64 = STANDARD.encode(der);
+    let mut pem = format!("-----BEGIN {}-----\n", label);
+
+    for line in b64.as_bytes().chunks(PEM_LINE_LEN)
+    {
+        pem.push_str(&String::from_utf8_lossy(line));
+        pem.push('\n');
+    }
+    pem.push_str(&format!("-----END {}-----\n", label));
+
+    pem
+}
+
+/// Décode un fichier PEM portant le libellé `label` et retourne les données DER.
+fn pem_decode(label: &str, pem: &str) -> Result<Vec<u8>, Error>
+{
+    let (begin, end) = (format!("-----BEGIN {}-----", label), format!("-----END {}-----", label));
+    let start = pem.find(&begin).ok_or_else(|| Error::Parse(String::from("pem_decode : en-tête PEM introuvable."), None))? + begin.len();
+    let stop = pem[start..].find(&end).ok_or_else(|| Error::Parse(String::from("pem_decode : fin PEM introuvable."), None))? + start;
+    let b64: String = pem[start..stop].chars().filter(| c | !c.is_whitespace()).collect();
+
+    STANDARD.decode(b64).map_err(|_| Error::Parse(String::from("pem_decode : base64 invalide."), None))
+}
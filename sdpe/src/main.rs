@@ -49,7 +49,7 @@ fn main()
                 .short("s")
                 .long("ks")
                 .value_name("TAILLE_CLE_OCTETS")
-                .help("Taille en octets de la clé pour la génération. Pour le RSA, il faut multiplier par 16 (RSA2048 = 128). Si omis, une valeur par défaut sera utilisée")
+                .help("Taille en octets de la clé pour la génération. Pour le RSA, il faut multiplier par 16 (RSA2048 = 128) ; pour ElGamal, par 8 (module de 2048 bits = 256) ; pour Vigenère, longueur du mot-clé ; pour Hill, taille de la matrice ; pour dh-derive, taille du secret. Si omis, une valeur par défaut sera utilisée")
                 .takes_value(true)
         )
         .arg(
@@ -60,6 +60,44 @@ fn main()
                 .help("Nombre de threads à utiliser pour la génération de clés. Particulièrement utile pour RSA par exemple. Si omis, un nombre par défaut sera utilisé")
                 .takes_value(true)
         )
+        .arg(
+            clap::Arg::with_name("primes")
+                .long("primes")
+                .value_name("NOMBRE_PREMIERS")
+                .help("Nombre de facteurs premiers des clés RSA générées, de 2 (par défaut) à 4 (RSA multi-premiers). La taille du module reste la même")
+                .takes_value(true)
+        )
+        .arg(
+            clap::Arg::with_name("legacy")
+                .long("legacy")
+                .help(
+                    "Si présent, autorise les paramètres hérités refusés par la politique de sécurité par défaut : \
+                    clés de moins de 2048 bits, petits exposants publics, messages sans padding (sans nonce). À réserver aux anciennes clés"
+                )
+        )
+        .arg(
+            clap::Arg::with_name("fips")
+                .long("fips")
+                .help(
+                    "Si présent, les clés RSA générées sont conformes à FIPS 186-5 (annexe B.3.6) : exposant 65537, facteurs construits à partir de nombres premiers auxiliaires. \
+                    Seules les tailles de clé 128, 192 et 256 (RSA-2048, 3072 et 4096) sont alors acceptées"
+                )
+        )
+        .arg(
+            clap::Arg::with_name("provable")
+                .long("provable")
+                .help(
+                    "Si présent, les facteurs premiers des clés RSA générées sont prouvés (certificats de Pocklington) plutôt que seulement probablement premiers. \
+                    La génération se fait alors sur un seul thread ; si un fichier en sortie (--out) est indiqué, les certificats y sont écrits en clair, ce qui n'est accepté qu'avec --nopass"
+                )
+        )
+        .arg(
+            clap::Arg::with_name("timeout")
+                .long("timeout")
+                .value_name("SECONDES")
+                .help("Délai maximal de génération de clé, en secondes. La génération est interrompue s'il est dépassé")
+                .takes_value(true)
+        )
         .arg(
             clap::Arg::with_name("input")
                 .short("i")
@@ -72,6 +110,8 @@ fn main()
                     ("operation", "decrypt"),
                     ("operation", "sign"),
                     ("operation", "verify"),
+                    ("operation", "crack"),
+                    ("operation", "dh-derive"),
                 ])
         )
         .arg(
@@ -85,8 +125,23 @@ fn main()
                     ("operation", "decrypt"),
                     ("operation", "sign"),
                     ("operation", "verify"),
+                    ("operation", "dh-derive"),
                 ])
         )
+        .arg(
+            clap::Arg::with_name("group")
+                .long("group")
+                .value_name("GROUPE")
+                .help("Groupe de l'opération dh-gen : ffdhe2048 (par défaut), ffdhe3072, ffdhe4096, ffdhe6144, ffdhe8192 (RFC 7919) ou custom (nouveau groupe de --ks octets)")
+                .takes_value(true)
+        )
+        .arg(
+            clap::Arg::with_name("lang")
+                .long("lang")
+                .value_name("LANGUE")
+                .help("Langue supposée du texte clair pour l'opération crack des moteurs classiques : fr (par défaut) ou en")
+                .takes_value(true)
+        )
         .arg(
             clap::Arg::with_name("list")
                 .short("l")
@@ -105,6 +160,14 @@ fn main()
                     auquel cas il faut toujours l'indiquer, sauf pour le premier chiffrement et le dernier déchiffrement"
                 )
         )
+        .arg(
+            clap::Arg::with_name("nopass")
+                .long("nopass")
+                .help(
+                    "Si présent, les clés privées et principales générées ou exportées sont écrites en clair. \
+                    Par défaut, elles sont chiffrées avec une phrase de passe demandée lors de l'opération (format PKCS#8 chiffré)"
+                )
+        )
         .get_matches();
 
     if matches.is_present("list") 
@@ -117,11 +180,11 @@ fn main()
         }
         println!();
         println!("Opérations disponibles pour tous les moteurs :");
-        println!("- gen : Génère une clé principale dans le fichier de clé spécifié avec [keyfile]");
+        println!("- gen : Génère une clé principale dans le fichier de clé spécifié avec [keyfile], protégée par une phrase de passe pour RSA, x25519, ed25519 et p256 (sauf avec --nopass, obligatoire pour elgamal et rabin)");
         println!("- encrypt : Chiffre le message écrit dans [input] avec [keyfile] puis l'écrit dans [output]");
         println!("- decrypt : Déchiffre le message chiffré écrit dans [input] avec [keyfile] puis l'écrit dans [output]");
-        println!("- sign : Signe le message écrit dans [input] avec [keyfile] puis écrit le message signé dans [output]");
-        println!("- verify : Déchiffre un message signé dans [input] avec [keyfile] puis écrit le résultat pour vérification dans [output]");
+        println!("- sign : Signe le message écrit dans [input] avec [keyfile] puis écrit le message signé dans [output] (moteurs de signature : rsa, ed25519, p256)");
+        println!("- verify : Déchiffre un message signé dans [input] avec [keyfile] puis écrit le résultat pour vérification dans [output] (moteurs de signature : rsa, ed25519, p256)");
         for engine in GenEngine::list()
         {
             println!();
@@ -1,10 +1,1228 @@
-use std::{fs::{read_to_string, write}, io::Write, sync::{Arc, Mutex}, thread, time};
-use rrsa::{engines::*, keys::*, messages::Message};
+use std::{fs::{read_dir, read_to_string, write}, io::Write, path::Path, sync::atomic::{AtomicUsize, Ordering}, time};
+use rrsa::{Error, attacks::Attack, audit, classical::*, cryptanalysis::{self, Candidate, Language}, curve25519::*, dh::*, elgamal::*, engines::*, keys::*, messages::Message, p256::*, policy::Policy, provable, rabin::*, storage};
+use zeroize::Zeroizing;
+
+
+/// Demande une phrase de passe à l'utilisateur, sans l'afficher. Si `confirm` est vrai, elle est demandée une seconde fois pour confirmation.
+/// La phrase de passe est effacée de la mémoire une fois utilisée.
+fn ask_passphrase(confirm: bool) -> Zeroizing<String>
+{
+    loop
+    {
+        let pass = Zeroizing::new(rpassword::prompt_password("  Phrase de passe : ").expect("-> Impossible de lire la phrase de passe."));
+        if !confirm || *pass == *Zeroizing::new(rpassword::prompt_password("  Confirmation : ").expect("-> Impossible de lire la phrase de passe."))
+        {
+            return pass;
+        }
+        println!("- Les phrases de passe ne correspondent pas, veuillez recommencer.");
+    }
+}
+
+/// Charge une clé principale RSA depuis le contenu d'un fichier clé, en demandant la phrase de passe si elle est chiffrée.
+fn load_rsa_main(contents: String) -> RsaKey
+{
+    if storage::is_encrypted(&contents)
+    {
+        println!("  La clé est protégée par une phrase de passe.");
+        storage::decrypt_key(&contents, &ask_passphrase(false)).expect("-> Impossible de déchiffrer la clé.")
+    }
+    else
+    {
+        RsaKey::from_str(contents).expect("-> Impossible de charger la clé.")
+    }
+}
+
+/// Clé RSA chargée depuis un fichier clé, selon le type de clé indiqué.
+enum LoadedRsaKey
+{
+    Main(RsaKey),
+    Public(PublicKey),
+    Private(PrivateKey)
+}
+
+impl LoadedRsaKey
+{
+    /// Valide la clé chargée, voir `check_key`.
+    fn check(&self) -> bool
+    {
+        match self
+        {
+            Self::Main(k) => check_key(k),
+            Self::Public(k) => check_key(k),
+            Self::Private(k) => check_key(k)
+        }
+    }
+
+    /// Sépare la clé chargée en ses éventuelles moitiés publique et privée.
+    fn split(self) -> (Option<PublicKey>, Option<PrivateKey>)
+    {
+        match self
+        {
+            Self::Main(k) => (Some(k.0), Some(k.1)),
+            Self::Public(k) => (Some(k), None),
+            Self::Private(k) => (None, Some(k))
+        }
+    }
+}
+
+/// Charge une clé RSA du type `kt` (main, public ou private) depuis le contenu d'un fichier clé.
+/// Retourne `None` si le type de clé est invalide.
+fn load_rsa(kt: &str, contents: String) -> Option<LoadedRsaKey>
+{
+    match kt
+    {
+        "main" | "pair" => Some(LoadedRsaKey::Main(load_rsa_main(contents))),
+        "public" | "publ" => Some(LoadedRsaKey::Public(PublicKey::from_str(contents).expect("-> Impossible de charger la clé."))),
+        "private" | "priv" => {
+            if storage::is_encrypted(&contents)
+            {
+                Some(LoadedRsaKey::Private(load_rsa_main(contents).1))
+            }
+            else
+            {
+                Some(LoadedRsaKey::Private(PrivateKey::from_str(contents).expect("-> Impossible de charger la clé.")))
+            }
+        },
+        _ => {
+            eprintln!("-> Type de clé invalide : {}.", kt);
+            None
+        }
+    }
+}
+
+/// Valide une clé chargée : retourne faux (et affiche l'erreur) si la clé est incohérente.
+/// Une clé trop faible est acceptée, mais un avertissement est affiché.
+fn check_key<K: Validate>(key: &K) -> bool
+{
+    match key.validate()
+    {
+        Ok(()) => true,
+        Err(Error::WeakKey(msg)) => {
+            println!("- Attention : clé trop faible, {}", msg);
+            true
+        },
+        Err(e) => {
+            eprintln!("-> Clé rejetée. {}", e);
+            false
+        }
+    }
+}
+
+/// Affiche la progression d'une génération de clé sur une seule ligne, réécrite au fil des candidats testés.
+struct GenProgress
+{
+    found: AtomicUsize,
+    total: usize
+}
+
+impl GenProgress
+{
+    /// Construit un affichage de progression pour la recherche de `total` facteurs premiers.
+    fn new(total: usize) -> GenProgress
+    {
+        GenProgress
+        {
+            found: AtomicUsize::new(0),
+            total
+        }
+    }
+
+    /// Efface la ligne de progression.
+    fn clear(&self)
+    {
+        print!("\r{:60}\r", "");
+        std::io::stdout().flush().ok();
+    }
+}
+
+impl GenObserver for GenProgress
+{
+    fn candidate_tested(&self, tested: u64)
+    {
+        print!("\r  {} candidats testés, {}/{} facteurs premiers trouvés", tested, self.found.load(Ordering::Relaxed), self.total);
+        std::io::stdout().flush().ok();
+    }
+
+    fn prime_found(&self, found: usize, _total: usize)
+    {
+        self.found.store(found, Ordering::Relaxed);
+    }
+}
+
+/// Sérialise une clé principale RSA pour l'écrire dans un fichier : chiffrée avec une phrase de passe demandée à l'utilisateur, ou en clair si `clear` est vrai.
+fn store_rsa_main(key: &RsaKey, clear: bool) -> String
+{
+    if clear
+    {
+        return key.serialize_str();
+    }
+
+    println!("  Choisissez une phrase de passe pour protéger la clé.");
+    storage::encrypt_key(key, &ask_passphrase(true), storage::PBKDF2_ITER_DEF).expect("-> Impossible de chiffrer la clé.")
+}
+
+/// Construit le contrôle d'une génération de clé depuis les arguments : délai maximal en secondes indiqué avec --timeout.
+/// Retourne `None` (et affiche l'erreur) si le délai est invalide.
+fn gen_control(args: &clap::ArgMatches) -> Option<GenControl<'static>>
+{
+    match args.value_of("timeout").map(str::parse)
+    {
+        Some(Ok(secs)) => Some(GenControl::new().with_timeout(time::Duration::from_secs(secs))),
+        Some(Err(_)) => {
+            eprintln!("-> Le délai de génération spécifié n'est pas valide.");
+            None
+        },
+        None => Some(GenControl::new())
+    }
+}
+
+/// Charge une paire de clés (publique `T`, privée `U`) d'un moteur autre que RSA depuis le contenu d'un fichier clé, selon le type de clé `kt`,
+/// et la sépare en ses moitiés publique et privée. Ces clés sont stockées en clair. Les clés principales et publiques sont validées, voir `check_key`.
+/// Retourne `None` si le type de clé est invalide ou si la clé est rejetée.
+fn load_pair<T: Key + Validate, U: Key>(kt: &str, contents: String) -> Option<(Option<T>, Option<U>)>
+where KeyPair<T, U> : Validate
+{
+    match kt
+    {
+        "main" | "pair" => {
+            let k = KeyPair::<T, U>::from_str(String::from(contents.trim())).expect("-> Impossible de charger la clé.");
+            if check_key(&k) { Some((Some(k.0), Some(k.1))) } else { None }
+        },
+        "public" | "publ" => {
+            let k = T::from_str(String::from(contents.trim())).expect("-> Impossible de charger la clé.");
+            if check_key(&k) { Some((Some(k), None)) } else { None }
+        },
+        "private" | "priv" => Some((None, Some(U::from_str(String::from(contents.trim())).expect("-> Impossible de charger la clé.")))),
+        _ => {
+            eprintln!("-> Type de clé invalide : {}.", kt);
+            None
+        }
+    }
+}
+
+/// Chiffre (`encrypt`) ou déchiffre (`decrypt`) le fichier en entrée avec le moteur et les clés donnés, puis écrit le résultat dans le fichier de sortie.
+fn run_crypt<E: Engine>(engine: &E, op: &str, args: &clap::ArgMatches, puk: Option<E::EncryptionKey>, prk: Option<E::DecryptionKey>)
+{
+    let msg = read_to_string(args.value_of("input").unwrap()).expect("-> Impossible de lire le fichier en entrée.");
+    let outpath = args.value_of("output").unwrap();
+
+    println!("  Fichier en entrée : {}", args.value_of("input").unwrap());
+    println!("  Fichier de sortie : {}", outpath);
+    println!();
+
+    match op
+    {
+        "encrypt" => {
+            let msg = if args.is_present("raw") { Message::try_parts_str(msg, false).and_then(| b | b.try_build()) } else { Message::str(msg).try_build() };
+            let mut msg = match msg
+            {
+                Ok(msg) => msg,
+                Err(e) => {
+                    eprintln!("-> Impossible de lire le message : {}", e);
+                    return;
+                }
+            };
+            if let Err(e) = engine.try_encrypt(&mut msg, &puk.expect("-> Impossible d'encrypter sans clé publique."))
+            {
+                eprintln!("-> Impossible de chiffrer le message : {}", e);
+                return;
+            }
+
+            write(outpath, msg.to_parts_str()).expect("-> Impossible d'écrire dans le fichier de sortie.");
+            println!("+> Message chiffré et écrit avec succès.");
+        },
+        "decrypt" => {
+            let mut msg = match Message::try_parts_str(msg, true).and_then(| b | b.try_build())
+            {
+                Ok(msg) => msg,
+                Err(e) => {
+                    eprintln!("-> Impossible de lire le message chiffré : {}", e);
+                    return;
+                }
+            };
+            if let Err(e) = engine.try_decrypt(&mut msg, &prk.expect("-> Impossible de décrypter sans clé privée."))
+            {
+                eprintln!("-> Impossible de déchiffrer le message : {}", e);
+                return;
+            }
+
+            let contents = if args.is_present("raw") { msg.to_parts_str() } else { msg.to_str().expect("-> Impossible de convertir le message.") };
+            write(outpath, contents).expect("-> Impossible d'écrire dans le fichier de sortie.");
+            println!("+> Message déchiffré et écrit avec succès.");
+        },
+        _ => eprintln!("-> Opération {} non reconnue pour ce moteur.", op)
+    }
+}
+
+/// Moteur dont la clé principale est une paire (clé publique, clé privée) stockée en clair (avec --nopass seulement), géré de manière générique par `pair_op`.
+trait PairEngine : Engine + Sized
+{
+    /// Nom du moteur affiché à l'utilisateur.
+    const NAME: &'static str;
+    /// Taille de clé par défaut, en octets.
+    const DEF_SIZEB: u64;
+    /// Nombre de nombres premiers recherchés lors de la génération d'une clé.
+    const PRIMES: usize;
+
+    /// Décrit la clé générée pour une taille donnée en octets.
+    fn size_desc(sz_b: u64) -> String;
+    /// Construit un moteur partageant la source d'aléa de celui-ci, avec la politique de sécurité donnée.
+    fn with_policy_of(&self, policy: Policy) -> Self;
+    /// Génère une clé principale, voir `Rsa::try_generate_controlled`.
+    fn generate_controlled(&self, sz_b: u64, n_threads: u8, control: &GenControl) -> Result<(Self::MainKey, GenStats), Error>;
+    /// Taille en bits du module d'une clé publique.
+    fn modulus_bits(key: &Self::EncryptionKey) -> u64;
+}
+
+impl PairEngine for ElGamal
+{
+    const NAME: &'static str = "ElGamal";
+    const DEF_SIZEB: u64 = ELGAMAL_DEF_SIZEB;
+    const PRIMES: usize = 1;
+
+    fn size_desc(sz_b: u64) -> String
+    {
+        format!("clé ElGamal de taille {} octets, soit un module premier sûr de {} bits", sz_b, sz_b * 8)
+    }
+
+    fn with_policy_of(&self, policy: Policy) -> Self
+    {
+        ElGamal::with_rng(self.rng()).with_policy(policy)
+    }
+
+    fn generate_controlled(&self, sz_b: u64, n_threads: u8, control: &GenControl) -> Result<(Self::MainKey, GenStats), Error>
+    {
+        self.try_generate_controlled(sz_b, n_threads, control)
+    }
+
+    fn modulus_bits(key: &Self::EncryptionKey) -> u64
+    {
+        key.0.value().bits()
+    }
+}
+
+impl PairEngine for Rabin
+{
+    const NAME: &'static str = "Rabin";
+    const DEF_SIZEB: u64 = RABIN_DEF_PRIME_SIZEB;
+    const PRIMES: usize = 2;
+
+    fn size_desc(sz_b: u64) -> String
+    {
+        format!("clé Rabin de taille {} octets, soit un module de {} bits", sz_b, sz_b * 16)
+    }
+
+    fn with_policy_of(&self, policy: Policy) -> Self
+    {
+        Rabin::with_rng(self.rng()).with_policy(policy)
+    }
+
+    fn generate_controlled(&self, sz_b: u64, n_threads: u8, control: &GenControl) -> Result<(Self::MainKey, GenStats), Error>
+    {
+        self.try_generate_controlled(sz_b, n_threads, control)
+    }
+
+    fn modulus_bits(key: &Self::EncryptionKey) -> u64
+    {
+        key.0.value().bits()
+    }
+}
+
+/// Opérations spécifiques d'un moteur à paire de clés (voir `PairEngine`) : export et vérification de clé.
+fn pair_oplist(check_desc: &str) -> Vec<(String, String, String)>
+{
+    vec![
+        (String::from("export"),
+        String::from("export : Extrait la clé publique (PUBLIC) ou privée (PRIVATE) d'une clé principale et l'écrit dans le fichier de sortie (--output).\n\
+    Ces clés ne pouvant pas être protégées par une phrase de passe, la clé privée n'est exportée qu'avec --nopass.\n"),
+        String::from("Export")),
+        (String::from("check-key"),
+        format!("check-key : {}\n\
+    Le type de clé indique le contenu du fichier clé : PUBLIC ou MAIN.\n", check_desc),
+        String::from("Vérification de clé"))]
+}
+
+/// Exécute une opération avec un moteur à paire de clés (voir `PairEngine`) : génération, chiffrement, déchiffrement, export et vérification de clé.
+/// La protection par phrase de passe (PKCS#8) n'étant disponible que pour RSA et les courbes elliptiques, les clés privées et principales de ces moteurs
+/// ne peuvent être écrites qu'en clair : leur génération et leur export sont refusés sans --nopass.
+fn pair_op<E, T, U>(engine: &E, op: &str, args: &clap::ArgMatches)
+where E : PairEngine<EncryptionKey = T, DecryptionKey = U, MainKey = KeyPair<T, U>>, T : Key + Validate, U : Key, KeyPair<T, U> : Validate
+{
+    println!("{}", E::NAME);
+    let kpath = args.value_of("keyfile").unwrap();
+    println!("  Fichier clé : {}", kpath);
+    let engine = &
+        if args.is_present("legacy")
+        {
+            println!("  Politique de sécurité : paramètres hérités autorisés (--legacy)");
+            engine.with_policy_of(Policy::legacy())
+        }
+        else
+        {
+            engine.with_policy_of(engine.policy())
+        };
+    match op
+    {
+        "gen" => {
+            let ksize: u64 =
+                if args.is_present("keysize")
+                {
+                    args.value_of("keysize").unwrap().parse().expect("La taille de clé spécifiée n'est pas valide.")
+                }
+                else
+                {
+                    E::DEF_SIZEB
+                };
+            let nthreads: u8 =
+                if args.is_present("genthreads")
+                {
+                    args.value_of("genthreads").unwrap().parse().expect("Le nombre de threads spécifié n'est pas valide.")
+                }
+                else
+                {
+                    def_gthreads()
+                };
+            println!("  Taille de clé : {}", ksize);
+            println!("  Nombre de threads à utiliser : {}", nthreads);
+            println!();
+
+            if !args.is_present("nopass")
+            {
+                eprintln!("-> La protection par phrase de passe n'est disponible que pour RSA, X25519, Ed25519 et P-256 : indiquez --nopass pour écrire la clé en clair.");
+                return;
+            }
+
+            println!("+ Génération d'une {} ({} threads)", E::size_desc(ksize), nthreads);
+            let control = match gen_control(args)
+                {
+                    Some(control) => control,
+                    None => return
+                };
+            let progress = GenProgress::new(E::PRIMES);
+            let k = engine.generate_controlled(ksize, nthreads, &control.with_observer(&progress));
+            progress.clear();
+            let (k, stats) = match k
+            {
+                Ok(k) => k,
+                Err(e) => {
+                    eprintln!("-> Impossible de générer la clé : {}", e);
+                    return;
+                }
+            };
+
+            write(kpath, k.serialize_str()).expect("-> Impossible d'écrire la clé dans le fichier clé.");
+            println!("+> Clé générée et écrite avec succès en {} secondes.", stats.elapsed.as_secs());
+            println!("   {} entiers candidats testés", stats.candidates);
+        },
+        "encrypt" | "decrypt" => {
+            let kt = args.value_of("keytype").unwrap().to_lowercase();
+            let rk = read_to_string(kpath).expect("-> Impossible de lire le fichier clé.");
+            println!("  Type de clé : {}", kt);
+            if let Some((puk, prk)) = load_pair::<T, U>(&kt, rk)
+            {
+                run_crypt(engine, op, args, puk, prk);
+            }
+        },
+        "check-key" => {
+            let kt = args.value_of("keytype").unwrap().to_lowercase();
+            let rk = read_to_string(kpath).expect("-> Impossible de lire le fichier clé.");
+            println!("  Type de clé : {}", kt);
+            println!();
+
+            if let Some((Some(puk), _)) = load_pair::<T, U>(&kt, rk)
+            {
+                println!("  Taille du module : {} bits", E::modulus_bits(&puk));
+                println!("+> Clé cohérente.");
+            }
+        },
+        "export" => {
+            let kt = args.value_of("keytype").unwrap().to_lowercase();
+            let rk = read_to_string(kpath).expect("-> Impossible de lire le fichier clé.");
+            let (puk, prk) = match load_pair::<T, U>("main", rk)
+            {
+                Some((Some(puk), Some(prk))) => (puk, prk),
+                _ => return
+            };
+
+            if !args.is_present("output")
+            {
+                eprintln!("-> Aucun fichier de sortie indiqué pour l'export.");
+                return;
+            }
+
+            let (wstr, lbl) =
+                match kt.as_str()
+                {
+                    "public" | "publ" => (puk.serialize_str(), "publique"),
+                    "private" | "priv" if !args.is_present("nopass") => {
+                        eprintln!("-> La protection par phrase de passe n'est disponible que pour RSA, X25519, Ed25519 et P-256 : indiquez --nopass pour exporter la clé privée en clair.");
+                        return;
+                    },
+                    "private" | "priv" => (prk.serialize_str(), "privée"),
+                    _ => {
+                        println!("-> Type de clé à exporter {} non reconnu. Liste : public, private.", kt);
+                        return;
+                    }
+                };
+            write(args.value_of("output").unwrap(), wstr).expect("-> Impossible d'écrire dans le fichier de destination.");
+            println!("+> Clé exportée sous sa forme {} avec succès.", lbl);
+        },
+        _ => eprintln!("-> Opération {} non reconnue pour ce moteur.", op)
+    }
+}
+
+
+/// Nombre de candidats affichés par l'opération `crack`.
+const CRACK_SHOWN: usize = 5;
+
+/// Moteur classique symétrique, dont l'unique clé sert au chiffrement comme au déchiffrement, géré de manière générique par `classical_op`.
+trait ClassicalEngine : Engine + Sized
+{
+    /// Nom du moteur affiché à l'utilisateur.
+    const NAME: &'static str;
+
+    /// Décrit la clé générée pour une taille donnée (voir --ks), ou pour la taille par défaut si elle est absente.
+    fn size_desc(size: Option<u64>) -> String;
+    /// Attaque un texte chiffré en supposant la langue du clair, voir `cryptanalysis`. Retourne `None` si le moteur n'a pas d'attaque à texte chiffré seul.
+    fn crack(_text: &str, _lang: Language) -> Option<Result<Vec<Candidate<Self::MainKey>>, Error>>
+    {
+        None
+    }
+}
+
+impl ClassicalEngine for Shift
+{
+    const NAME: &'static str = "Décalage";
+
+    fn size_desc(_: Option<u64>) -> String
+    {
+        String::from("clé de décalage aléatoire (la taille est ignorée)")
+    }
+
+    fn crack(text: &str, lang: Language) -> Option<Result<Vec<Candidate<Self::MainKey>>, Error>>
+    {
+        Some(cryptanalysis::crack_shift(text, lang))
+    }
+}
+
+impl ClassicalEngine for Vigenere
+{
+    const NAME: &'static str = "Vigenère";
+
+    fn size_desc(size: Option<u64>) -> String
+    {
+        format!("clé Vigenère de {} lettres", size.unwrap_or(VIGENERE_DEF_KEYLEN))
+    }
+
+    fn crack(text: &str, lang: Language) -> Option<Result<Vec<Candidate<Self::MainKey>>, Error>>
+    {
+        Some(cryptanalysis::crack_vigenere(text, lang, cryptanalysis::VIGENERE_MAX_KEYLEN))
+    }
+}
+
+impl ClassicalEngine for Affine
+{
+    const NAME: &'static str = "Affine";
+
+    fn size_desc(_: Option<u64>) -> String
+    {
+        String::from("clé affine aléatoire (la taille est ignorée)")
+    }
+
+    fn crack(text: &str, lang: Language) -> Option<Result<Vec<Candidate<Self::MainKey>>, Error>>
+    {
+        Some(cryptanalysis::crack_affine(text, lang))
+    }
+}
+
+impl ClassicalEngine for Hill
+{
+    const NAME: &'static str = "Hill";
+
+    fn size_desc(size: Option<u64>) -> String
+    {
+        let n = size.unwrap_or(HILL_DEF_SIZE);
+        format!("clé Hill, matrice de taille {}x{}", n, n)
+    }
+}
+
+/// Exécute une opération avec un moteur classique (voir `ClassicalEngine`) : génération, chiffrement, déchiffrement et attaque sans la clé.
+/// Le texte est traité en un seul bloc afin que la clé s'applique à toutes ses lettres dans l'ordre, et le chiffré est écrit sous forme de texte.
+/// Ces chiffrements n'offrent aucune sécurité : les clés sont écrites en clair et la politique de sécurité ne s'applique pas.
+fn classical_op<E, K>(engine: &E, op: &str, args: &clap::ArgMatches)
+where E : ClassicalEngine<EncryptionKey = K, DecryptionKey = K, MainKey = K>, K : Key
+{
+    println!("{}", E::NAME);
+    let kpath = args.value_of("keyfile").unwrap();
+    println!("  Fichier clé : {}", kpath);
+    match op
+    {
+        "gen" => {
+            let ksize: Option<u64> = args.value_of("keysize").map(| s | s.parse().expect("La taille de clé spécifiée n'est pas valide."));
+            println!();
+            println!("- Attention : les chiffrements classiques n'offrent aucune sécurité, la clé sera écrite en clair.");
+            println!();
+
+            println!("+ Génération d'une {}", E::size_desc(ksize));
+            let k = match ksize
+            {
+                Some(ksize) => engine.try_generate(ksize, 0),
+                None => engine.try_gen_def()
+            };
+            let k = match k
+            {
+                Ok(k) => k,
+                Err(e) => {
+                    eprintln!("-> Impossible de générer la clé : {}", e);
+                    return;
+                }
+            };
+
+            write(kpath, k.serialize_str()).expect("-> Impossible d'écrire la clé dans le fichier clé.");
+            println!("+> Clé générée et écrite avec succès.");
+        },
+        "encrypt" | "decrypt" => {
+            let rk = read_to_string(kpath).expect("-> Impossible de lire le fichier clé.");
+            let k = match K::from_str(String::from(rk.trim()))
+            {
+                Ok(k) => k,
+                Err(e) => {
+                    eprintln!("-> Impossible de charger la clé : {}", e);
+                    return;
+                }
+            };
+            let text = read_to_string(args.value_of("input").unwrap()).expect("-> Impossible de lire le fichier en entrée.");
+            let outpath = args.value_of("output").unwrap();
+
+            println!("  Fichier en entrée : {}", args.value_of("input").unwrap());
+            println!("  Fichier de sortie : {}", outpath);
+            println!();
+
+            let bsize = text.len() as u32;
+            let mut msg = match Message::str(text).bsize(bsize.max(1)).try_build()
+            {
+                Ok(msg) => msg,
+                Err(e) => {
+                    eprintln!("-> Impossible de lire le message : {}", e);
+                    return;
+                }
+            };
+            let (res, lbl) = if op == "encrypt" { (engine.try_encrypt(&mut msg, &k), "chiffré") } else { (engine.try_decrypt(&mut msg, &k), "déchiffré") };
+            if let Err(e) = res
+            {
+                eprintln!("-> Impossible de traiter le message : {}", e);
+                return;
+            }
+
+            write(outpath, msg.to_str().expect("-> Impossible de convertir le message.")).expect("-> Impossible d'écrire dans le fichier de sortie.");
+            println!("+> Message {} et écrit avec succès.", lbl);
+        },
+        "crack" => {
+            let lang = match Language::from_code(args.value_of("lang").unwrap_or("fr"))
+            {
+                Some(lang) => lang,
+                None => {
+                    eprintln!("-> Langue non reconnue. Liste : fr, en.");
+                    return;
+                }
+            };
+            let text = read_to_string(args.value_of("input").unwrap()).expect("-> Impossible de lire le fichier en entrée.");
+            println!("  Fichier en entrée : {}", args.value_of("input").unwrap());
+            println!("  Langue supposée : {}", if lang == Language::French { "français" } else { "anglais" });
+            println!();
+
+            let candidates = match E::crack(&text, lang)
+            {
+                Some(Ok(candidates)) => candidates,
+                Some(Err(e)) => {
+                    eprintln!("-> Impossible d'attaquer le message : {}", e);
+                    return;
+                },
+                None => {
+                    eprintln!("-> Aucune attaque à texte chiffré seul n'est disponible pour ce moteur.");
+                    return;
+                }
+            };
+            println!("+ Candidats les plus probables (score du χ², plus bas = plus probable) :");
+            for (i, c) in candidates.iter().take(CRACK_SHOWN).enumerate()
+            {
+                let excerpt: String = c.plaintext.chars().take(60).map(| ch | if ch.is_control() { ' ' } else { ch }).collect();
+                println!("  n°{} [{:.3}] clé {} : {}", i + 1, c.score, c.key.serialize_str(), excerpt);
+            }
+
+            let best = match candidates.first()
+            {
+                Some(best) => best,
+                None => {
+                    eprintln!("-> Aucun candidat n'a été trouvé.");
+                    return;
+                }
+            };
+            // Le fichier clé peut être une vraie clé passée par erreur : il n'est jamais écrasé
+            if Path::new(kpath).exists()
+            {
+                println!("- Le fichier clé {} existe déjà : la clé la plus probable n'y est pas écrite. Indiquez un fichier clé inexistant pour l'enregistrer.", kpath);
+            }
+            else
+            {
+                write(kpath, best.key.serialize_str()).expect("-> Impossible d'écrire la clé dans le fichier clé.");
+                println!("+> Clé la plus probable écrite dans le fichier clé.");
+            }
+            if let Some(outpath) = args.value_of("output")
+            {
+                write(outpath, &best.plaintext).expect("-> Impossible d'écrire dans le fichier de sortie.");
+                println!("+> Texte clair le plus probable écrit dans {}.", outpath);
+            }
+        },
+        _ => eprintln!("-> Opération {} non reconnue pour ce moteur.", op)
+    }
+}
+
+/// Opération d'attaque des moteurs classiques, voir `classical_op`.
+fn crack_oplist() -> Vec<(String, String, String)>
+{
+    vec![
+        (String::from("crack"),
+        String::from("crack : Attaque le message chiffré écrit dans [input] sans la clé, par analyse de fréquences des lettres dans la langue indiquée (--lang, fr par défaut).\n\
+    Les candidats les plus probables sont affichés ; la clé la plus probable est écrite dans le fichier clé s'il n'existe pas encore (un fichier existant n'est jamais écrasé)\n\
+    et, si --out est indiqué, le texte clair correspondant aussi.\n"),
+        String::from("Cryptanalyse"))]
+}
+
+
+/// Groupe Diffie-Hellman utilisé par défaut par l'opération `dh-gen`.
+const DH_DEF_GROUP: &str = "ffdhe2048";
+
+/// Opérations du moteur Diffie-Hellman, voir `dh_op`.
+fn dh_oplist() -> Vec<(String, String, String)>
+{
+    vec![
+        (String::from("dh-gen"),
+        String::from("dh-gen : Génère une clé Diffie-Hellman dans le fichier clé, écrite en clair, et sa valeur publique dans le fichier de sortie (--out) s'il est indiqué.\n\
+    Le groupe est indiqué avec --group : ffdhe2048 (par défaut), ffdhe3072, ffdhe4096, ffdhe6144, ffdhe8192 (RFC 7919), ou custom pour générer\n\
+    un nouveau groupe de --ks octets (256 par défaut). Si un fichier en entrée (--in) est indiqué, il doit contenir la valeur publique de l'autre partie,\n\
+    dont le groupe est alors réutilisé.\n"),
+        String::from("Génération de clé Diffie-Hellman")),
+        (String::from("dh-derive"),
+        String::from("dh-derive : Dérive le secret partagé entre la clé du fichier clé et la valeur publique de l'autre partie écrite dans [input],\n\
+    après vérification de celle-ci. Le secret de --ks octets (32 par défaut), dérivé par HKDF-SHA256, est écrit en hexadécimal dans [output].\n"),
+        String::from("Dérivation de secret partagé"))]
+}
+
+/// Charge et valide une valeur publique Diffie-Hellman depuis un fichier (voir `check_key`). Retourne `None` si elle est illisible ou rejetée.
+fn load_dh_public(path: &str) -> Option<DhPublicKey>
+{
+    let rk = read_to_string(path).expect("-> Impossible de lire le fichier de la valeur publique.");
+    match DhPublicKey::from_str(String::from(rk.trim()))
+    {
+        Ok(k) if check_key(&k) => Some(k),
+        Ok(_) => None,
+        Err(e) => {
+            eprintln!("-> Impossible de charger la valeur publique : {}", e);
+            None
+        }
+    }
+}
+
+/// Exécute une opération avec le moteur Diffie-Hellman : génération de clé (dans un groupe standard, généré ou repris de l'autre partie)
+/// et dérivation du secret partagé. Les clés ne servant qu'à un échange, elles sont écrites en clair.
+fn dh_op(dh: &Dh, op: &str, args: &clap::ArgMatches)
+{
+    println!("Diffie-Hellman");
+    let kpath = args.value_of("keyfile").unwrap();
+    println!("  Fichier clé : {}", kpath);
+    let dh = &
+        if args.is_present("legacy")
+        {
+            println!("  Politique de sécurité : paramètres hérités autorisés (--legacy)");
+            Dh::with_rng(dh.rng()).with_policy(Policy::legacy())
+        }
+        else
+        {
+            Dh::with_rng(dh.rng()).with_policy(dh.policy())
+        };
+    match op
+    {
+        "dh-gen" => {
+            let group = if let Some(inpath) = args.value_of("input")
+            {
+                println!("  Valeur publique de l'autre partie : {}", inpath);
+                match load_dh_public(inpath)
+                {
+                    Some(peer) => peer.0,
+                    None => return
+                }
+            }
+            else
+            {
+                let name = args.value_of("group").unwrap_or(DH_DEF_GROUP).to_lowercase();
+                println!("  Groupe : {}", name);
+                if name == "custom"
+                {
+                    let ksize: u64 = args.value_of("keysize").map_or(u64::from(DH_DEF_GROUP_BITS / 8), | s | s.parse().expect("La taille de clé spécifiée n'est pas valide."));
+                    let nthreads: u8 = args.value_of("genthreads").map_or(def_gthreads(), | s | s.parse().expect("Le nombre de threads spécifié n'est pas valide."));
+                    println!();
+                    println!("+ Génération d'un groupe de {} bits ({} threads)", ksize * 8, nthreads);
+                    let control = match gen_control(args)
+                        {
+                            Some(control) => control,
+                            None => return
+                        };
+                    let progress = GenProgress::new(1);
+                    let group = dh.try_generate_group(ksize, nthreads, &control.with_observer(&progress));
+                    progress.clear();
+                    match group
+                    {
+                        Ok((group, stats)) => {
+                            println!("+> Groupe généré en {} secondes, {} entiers candidats testés.", stats.elapsed.as_secs(), stats.candidates);
+                            group
+                        },
+                        Err(e) => {
+                            eprintln!("-> Impossible de générer le groupe : {}", e);
+                            return;
+                        }
+                    }
+                }
+                else
+                {
+                    match name.strip_prefix("ffdhe").and_then(| b | b.parse().ok()).map(DhGroup::ffdhe)
+                    {
+                        Some(Ok(group)) => group,
+                        _ => {
+                            eprintln!("-> Groupe {} non reconnu. Liste : ffdhe2048, ffdhe3072, ffdhe4096, ffdhe6144, ffdhe8192, custom.", name);
+                            return;
+                        }
+                    }
+                }
+            };
+            println!();
+            println!("- Attention : la protection par phrase de passe n'est disponible que pour RSA, X25519, Ed25519 et P-256, la clé sera écrite en clair.");
+            println!();
+
+            println!("+ Génération d'une clé Diffie-Hellman (module de {} bits)", group.bits());
+            let k = match dh.try_generate_key(&group)
+            {
+                Ok(k) => k,
+                Err(e) => {
+                    eprintln!("-> Impossible de générer la clé : {}", e);
+                    return;
+                }
+            };
+
+            write(kpath, k.serialize_str()).expect("-> Impossible d'écrire la clé dans le fichier clé.");
+            println!("+> Clé générée et écrite avec succès.");
+            if let Some(outpath) = args.value_of("output")
+            {
+                write(outpath, k.public().serialize_str()).expect("-> Impossible d'écrire dans le fichier de sortie.");
+                println!("+> Valeur publique écrite dans {}, à transmettre à l'autre partie.", outpath);
+            }
+        },
+        "dh-derive" => {
+            let rk = read_to_string(kpath).expect("-> Impossible de lire le fichier clé.");
+            let k = match DhKey::from_str(String::from(rk.trim()))
+            {
+                Ok(k) => k,
+                Err(e) => {
+                    eprintln!("-> Impossible de charger la clé : {}", e);
+                    return;
+                }
+            };
+            if !check_key(&k)
+            {
+                return;
+            }
+            let inpath = args.value_of("input").unwrap();
+            let outpath = args.value_of("output").unwrap();
+            let len: usize = args.value_of("keysize").map_or(DH_DEF_SECRET_LEN, | s | s.parse().expect("La taille de secret spécifiée n'est pas valide."));
+            println!("  Valeur publique de l'autre partie : {}", inpath);
+            println!("  Fichier de sortie : {}", outpath);
+            println!("  Taille du secret : {} octets", len);
+            println!();
+
+            let peer = match load_dh_public(inpath)
+            {
+                Some(peer) => peer,
+                None => return
+            };
+            let secret = match dh.try_derive(&k, &peer, &[], len)
+            {
+                Ok(secret) => secret,
+                Err(e) => {
+                    eprintln!("-> Impossible de dériver le secret : {}", e);
+                    return;
+                }
+            };
+
+            let hex = Zeroizing::new(secret.iter().map(| b | format!("{:02x}", b)).collect::<String>());
+            write(outpath, hex.as_bytes()).expect("-> Impossible d'écrire dans le fichier de sortie.");
+            println!("+> Secret partagé dérivé et écrit avec succès.");
+        },
+        _ => eprintln!("-> Opération {} non reconnue pour ce moteur.", op)
+    }
+}
+
+
+/// Moteur à courbe elliptique, dont les clés sont gérées comme celles de RSA par `curve_op` : la clé principale est protégée par une phrase de passe
+/// (PKCS#8 chiffré) sauf avec --nopass, et peut être exportée ou vérifiée. Les opérations propres au moteur (accord de clé, signature) sont traitées à part.
+trait CurveEngine
+{
+    /// Nom du moteur affiché à l'utilisateur.
+    const NAME: &'static str;
+    /// Type de la clé publique.
+    type PublicKey : Key + Validate;
+    /// Type de la clé privée.
+    type PrivateKey : Key;
+
+    /// Génère une clé principale.
+    fn try_generate_pair(&self) -> Result<KeyPair<Self::PublicKey, Self::PrivateKey>, Error>;
+    /// Clé publique correspondant à une clé privée.
+    fn public_of(prk: &Self::PrivateKey) -> Self::PublicKey;
+    /// Encode une clé privée au format PKCS#8.
+    fn to_pkcs8(prk: &Self::PrivateKey) -> Zeroizing<Vec<u8>>;
+    /// Décode une clé privée depuis le format PKCS#8.
+    fn from_pkcs8(der: &[u8]) -> Result<Self::PrivateKey, Error>;
+}
+
+impl CurveEngine for X25519
+{
+    const NAME: &'static str = "X25519";
+    type PublicKey = X25519PublicKey;
+    type PrivateKey = X25519PrivateKey;
+
+    fn try_generate_pair(&self) -> Result<X25519Key, Error>
+    {
+        self.try_generate()
+    }
+
+    fn public_of(prk: &X25519PrivateKey) -> X25519PublicKey
+    {
+        prk.public()
+    }
+
+    fn to_pkcs8(prk: &X25519PrivateKey) -> Zeroizing<Vec<u8>>
+    {
+        prk.to_pkcs8()
+    }
+
+    fn from_pkcs8(der: &[u8]) -> Result<X25519PrivateKey, Error>
+    {
+        X25519PrivateKey::from_pkcs8(der)
+    }
+}
+
+impl CurveEngine for Ed25519
+{
+    const NAME: &'static str = "Ed25519";
+    type PublicKey = Ed25519PublicKey;
+    type PrivateKey = Ed25519PrivateKey;
+
+    fn try_generate_pair(&self) -> Result<Ed25519Key, Error>
+    {
+        self.try_generate()
+    }
+
+    fn public_of(prk: &Ed25519PrivateKey) -> Ed25519PublicKey
+    {
+        prk.public()
+    }
+
+    fn to_pkcs8(prk: &Ed25519PrivateKey) -> Zeroizing<Vec<u8>>
+    {
+        prk.to_pkcs8()
+    }
+
+    fn from_pkcs8(der: &[u8]) -> Result<Ed25519PrivateKey, Error>
+    {
+        Ed25519PrivateKey::from_pkcs8(der)
+    }
+}
+
+impl CurveEngine for P256
+{
+    const NAME: &'static str = "P-256";
+    type PublicKey = P256PublicKey;
+    type PrivateKey = P256PrivateKey;
+
+    fn try_generate_pair(&self) -> Result<P256Key, Error>
+    {
+        self.try_generate()
+    }
+
+    fn public_of(prk: &P256PrivateKey) -> P256PublicKey
+    {
+        prk.public()
+    }
+
+    fn to_pkcs8(prk: &P256PrivateKey) -> Zeroizing<Vec<u8>>
+    {
+        prk.to_pkcs8()
+    }
+
+    fn from_pkcs8(der: &[u8]) -> Result<P256PrivateKey, Error>
+    {
+        P256PrivateKey::from_pkcs8(der)
+    }
+}
+
+/// Charge une clé principale de courbe depuis le contenu d'un fichier clé, en demandant la phrase de passe si elle est chiffrée.
+/// La clé publique, absente du fichier chiffré, est recalculée depuis la clé privée.
+fn load_curve_main<E: CurveEngine>(contents: String) -> KeyPair<E::PublicKey, E::PrivateKey>
+{
+    if storage::is_encrypted(&contents)
+    {
+        println!("  La clé est protégée par une phrase de passe.");
+        let der = storage::decrypt_pkcs8(&contents, &ask_passphrase(false)).expect("-> Impossible de déchiffrer la clé.");
+        let prk = E::from_pkcs8(&der).expect("-> Impossible de charger la clé.");
+        KeyPair(E::public_of(&prk), prk)
+    }
+    else
+    {
+        KeyPair::from_str(String::from(contents.trim())).expect("-> Impossible de charger la clé.")
+    }
+}
+
+/// Moitiés publique et privée éventuelles d'une clé de courbe chargée, voir `load_curve`.
+type CurveHalves<E> = (Option<<E as CurveEngine>::PublicKey>, Option<<E as CurveEngine>::PrivateKey>);
+
+/// Charge une clé de courbe du type `kt` (main, public ou private) depuis le contenu d'un fichier clé, et la sépare en ses moitiés publique et privée.
+/// Les clés principales et publiques sont validées, voir `check_key`. Retourne `None` si le type de clé est invalide ou si la clé est rejetée.
+fn load_curve<E: CurveEngine>(kt: &str, contents: String) -> Option<CurveHalves<E>>
+where KeyPair<E::PublicKey, E::PrivateKey> : Validate
+{
+    match kt
+    {
+        "main" | "pair" => {
+            let k = load_curve_main::<E>(contents);
+            if check_key(&k) { Some((Some(k.0), Some(k.1))) } else { None }
+        },
+        "public" | "publ" => {
+            let k = E::PublicKey::from_str(String::from(contents.trim())).expect("-> Impossible de charger la clé.");
+            if check_key(&k) { Some((Some(k), None)) } else { None }
+        },
+        "private" | "priv" => {
+            if storage::is_encrypted(&contents)
+            {
+                Some((None, Some(load_curve_main::<E>(contents).1)))
+            }
+            else
+            {
+                Some((None, Some(E::PrivateKey::from_str(String::from(contents.trim())).expect("-> Impossible de charger la clé."))))
+            }
+        },
+        _ => {
+            eprintln!("-> Type de clé invalide : {}.", kt);
+            None
+        }
+    }
+}
+
+/// Sérialise une clé principale de courbe pour l'écrire dans un fichier : clé privée chiffrée (PKCS#8) avec une phrase de passe demandée à l'utilisateur,
+/// ou paire de clés en clair si `clear` est vrai.
+fn store_curve_main<E: CurveEngine>(key: &KeyPair<E::PublicKey, E::PrivateKey>, clear: bool) -> String
+{
+    if clear
+    {
+        return key.serialize_str();
+    }
+
+    println!("  Choisissez une phrase de passe pour protéger la clé.");
+    storage::encrypt_pkcs8(&E::to_pkcs8(&key.1), &ask_passphrase(true), storage::PBKDF2_ITER_DEF).expect("-> Impossible de chiffrer la clé.")
+}
+
+/// Opérations communes des moteurs à courbe elliptique (voir `CurveEngine`) : export et vérification de clé.
+fn curve_oplist() -> Vec<(String, String, String)>
+{
+    vec![
+        (String::from("export"),
+        String::from("export : Extrait la clé publique (PUBLIC), privée (PRIVATE) ou une copie de la clé principale (MAIN) et l'écrit dans le fichier de sortie (--output).\n\
+    Les clés privées et principales exportées sont protégées par une phrase de passe (PKCS#8 chiffré), sauf si --nopass est indiqué.\n"),
+        String::from("Export")),
+        (String::from("check-key"),
+        String::from("check-key : Vérifie que la clé publique est un point valide de la courbe (d'ordre non faible pour Curve25519) et, pour une clé principale, qu'elle correspond à la clé privée.\n\
+    Le type de clé indique le contenu du fichier clé : PUBLIC ou MAIN.\n"),
+        String::from("Vérification de clé"))]
+}
+
+/// Exécute une opération commune avec un moteur à courbe elliptique (voir `CurveEngine`) : génération, export et vérification de clé.
+/// La taille des clés étant fixée par la courbe, --ks et --gt sont ignorés.
+fn curve_op<E>(engine: &E, op: &str, args: &clap::ArgMatches)
+where E : CurveEngine, KeyPair<E::PublicKey, E::PrivateKey> : Validate
+{
+    let kpath = args.value_of("keyfile").unwrap();
+    match op
+    {
+        "gen" => {
+            println!();
+            println!("+ Génération d'une clé {}", E::NAME);
+            let k = match engine.try_generate_pair()
+            {
+                Ok(k) => k,
+                Err(e) => {
+                    eprintln!("-> Impossible de générer la clé : {}", e);
+                    return;
+                }
+            };
+
+            write(kpath, store_curve_main::<E>(&k, args.is_present("nopass"))).expect("-> Impossible d'écrire la clé dans le fichier clé.");
+            println!("+> Clé générée et écrite avec succès.");
+        },
+        "check-key" => {
+            let kt = args.value_of("keytype").unwrap().to_lowercase();
+            let rk = read_to_string(kpath).expect("-> Impossible de lire le fichier clé.");
+            println!("  Type de clé : {}", kt);
+            println!();
+
+            if let Some((Some(puk), _)) = load_curve::<E>(&kt, rk)
+            {
+                println!("  Clé publique : {}", puk.serialize_str());
+                println!("+> Clé cohérente.");
+            }
+        },
+        "export" => {
+            let kt = args.value_of("keytype").unwrap().to_lowercase();
+            let rk = read_to_string(kpath).expect("-> Impossible de lire le fichier clé.");
+            let k = load_curve_main::<E>(rk);
+            if !check_key(&k)
+            {
+                return;
+            }
+
+            if !args.is_present("output")
+            {
+                eprintln!("-> Aucun fichier de sortie indiqué pour l'export.");
+                return;
+            }
+
+            let (wstr, lbl) =
+                match kt.as_str()
+                {
+                    "public" | "publ" => (k.0.serialize_str(), "publique"),
+                    // Le format PKCS#8 ne contient que la clé privée, dont la clé publique est recalculée au chargement
+                    "private" | "priv" => (if args.is_present("nopass") { k.1.serialize_str() } else { store_curve_main::<E>(&k, false) }, "privée"),
+                    "main" | "pair" | "all" | "any" => (store_curve_main::<E>(&k, args.is_present("nopass")), "principale"),
+                    _ => {
+                        println!("-> Type de clé à exporter {} non reconnu. Liste : public, private, main (copie).", kt);
+                        return;
+                    }
+                };
+            write(args.value_of("output").unwrap(), wstr).expect("-> Impossible d'écrire dans le fichier de destination.");
+            println!("+> Clé exportée sous sa forme {} avec succès.", lbl);
+        },
+        _ => eprintln!("-> Opération {} non reconnue pour ce moteur.", op)
+    }
+}
+
+/// Signe (`sign`) ou vérifie (`verify`) un message avec un moteur de signature détachée (voir `DetachedSigner`).
+/// Le message signé écrit en sortie contient la signature en hexadécimal sur sa première ligne, suivie du message ; la vérification
+/// contrôle la signature puis écrit le message seul en sortie, comme la vérification RSA restitue le message signé.
+fn detached_sign_op<E>(engine: &E, op: &str, args: &clap::ArgMatches)
+where E : CurveEngine + DetachedSigner<SigningKey = <E as CurveEngine>::PrivateKey, VerifyingKey = <E as CurveEngine>::PublicKey>,
+    KeyPair<<E as CurveEngine>::PublicKey, <E as CurveEngine>::PrivateKey> : Validate
+{
+    let kt = args.value_of("keytype").unwrap().to_lowercase();
+    let rk = read_to_string(args.value_of("keyfile").unwrap()).expect("-> Impossible de lire le fichier clé.");
+    let (puk, prk) = match load_curve::<E>(&kt, rk)
+    {
+        Some(k) => k,
+        None => return
+    };
+    let contents = read_to_string(args.value_of("input").unwrap()).expect("-> Impossible de lire le fichier en entrée.");
+    let outpath = args.value_of("output").unwrap();
+
+    println!("  Type de clé : {}", kt);
+    println!("  Fichier en entrée : {}", args.value_of("input").unwrap());
+    println!("  Fichier de sortie : {}", outpath);
+    println!();
+
+    if op == "sign"
+    {
+        let prk = prk.expect("-> Impossible de signer sans clé privée.");
+        let sig = match engine.try_sign_detached(contents.as_bytes(), &prk)
+        {
+            Ok(sig) => sig,
+            Err(e) => {
+                eprintln!("-> Impossible de signer le message : {}", e);
+                return;
+            }
+        };
+
+        let hex: String = sig.iter().map(| b | format!("{:02x}", b)).collect();
+        write(outpath, format!("{}\n{}", hex, contents)).expect("-> Impossible d'écrire dans le fichier de sortie.");
+        println!("+> Message signé et écrit avec succès.");
+    }
+    else
+    {
+        let puk = puk.expect("-> Impossible de vérifier sans clé publique.");
+        let (hex, msg) = contents.split_once('\n').unwrap_or((&contents, ""));
+        let hex = hex.trim();
+        let sig: Option<Vec<u8>> = if hex.len() % 2 == 0 { (0..hex.len()).step_by(2).map(| i | u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()).collect() } else { None };
+        let res = match sig
+        {
+            Some(sig) => engine.try_verify_detached(msg.as_bytes(), &sig, &puk),
+            None => Err(Error::Decoding(String::from("signature hexadécimale illisible."))),
+        };
+        if let Err(e) = res
+        {
+            eprintln!("-> Signature rejetée : {}", e);
+            return;
+        }
+
+        write(outpath, msg).expect("-> Impossible d'écrire dans le fichier de sortie.");
+        println!("+> Signature valide, message écrit avec succès.");
+    }
+}
+
+/// Dérive le secret partagé X25519 entre la clé principale du fichier clé et la clé publique de l'autre partie écrite dans [input] (voir `X25519::try_derive`).
+/// Le secret de --ks octets (32 par défaut) est écrit en hexadécimal dans [output], comme pour `dh-derive` en Diffie-Hellman.
+fn x25519_derive(x25519: &X25519, args: &clap::ArgMatches)
+{
+    let rk = read_to_string(args.value_of("keyfile").unwrap()).expect("-> Impossible de lire le fichier clé.");
+    let k = match load_curve::<X25519>("main", rk)
+    {
+        Some((_, Some(prk))) => prk,
+        _ => return
+    };
+    let inpath = args.value_of("input").unwrap();
+    let outpath = args.value_of("output").unwrap();
+    let len: usize = args.value_of("keysize").map_or(DH_DEF_SECRET_LEN, | s | s.parse().expect("La taille de secret spécifiée n'est pas valide."));
+    println!("  Clé publique de l'autre partie : {}", inpath);
+    println!("  Fichier de sortie : {}", outpath);
+    println!("  Taille du secret : {} octets", len);
+    println!();
+
+    let peer = match load_curve::<X25519>("public", read_to_string(inpath).expect("-> Impossible de lire le fichier en entrée."))
+    {
+        Some((Some(peer), _)) => peer,
+        _ => return
+    };
+    let secret = match x25519.try_derive(&k, &peer, &[], len)
+    {
+        Ok(secret) => secret,
+        Err(e) => {
+            eprintln!("-> Impossible de dériver le secret : {}", e);
+            return;
+        }
+    };
+
+    let hex = Zeroizing::new(secret.iter().map(| b | format!("{:02x}", b)).collect::<String>());
+    write(outpath, hex.as_bytes()).expect("-> Impossible d'écrire dans le fichier de sortie.");
+    println!("+> Secret partagé dérivé et écrit avec succès.");
+}
 
 
 pub enum GenEngine
 {
-    Rsa(Rsa)
+    Rsa(Rsa),
+    ElGamal(ElGamal),
+    Rabin(Rabin),
+    Shift(Shift),
+    Vigenere(Vigenere),
+    Affine(Affine),
+    Hill(Hill),
+    Dh(Dh),
+    X25519(X25519),
+    Ed25519(Ed25519),
+    P256(P256)
 }
 
 impl GenEngine
@@ -12,7 +1230,17 @@ impl GenEngine
     pub fn list() -> Vec<String>
     {
         vec![
-            String::from("rsa")
+            String::from("rsa"),
+            String::from("elgamal"),
+            String::from("rabin"),
+            String::from("shift"),
+            String::from("vigenere"),
+            String::from("affine"),
+            String::from("hill"),
+            String::from("dh"),
+            String::from("x25519"),
+            String::from("ed25519"),
+            String::from("p256")
         ]
     }
 
@@ -24,8 +1252,49 @@ impl GenEngine
                 (String::from("export"),
                 String::from("export : Extrait la partie d'une clé. Le type de clé détermine le type de destination ; la partie exportée sera écrite dans le fichier de sortie (--output).\n\
     Le fichier clé doit contenir une clé principale (paire clé publique / privée).\n\
-    Les types possibles sont PUBLIC, PRIVATE, MAIN (copie de la clé principale).\n"),
-                String::from("Export"))]
+    Les types possibles sont PUBLIC, PRIVATE, MAIN (copie de la clé principale).\n\
+    Les clés privées et principales exportées sont protégées par une phrase de passe, sauf si --nopass est indiqué.\n"),
+                String::from("Export")),
+                (String::from("check-key"),
+                String::from("check-key : Vérifie la cohérence de la clé (modules, exposants, facteurs premiers) ainsi que sa taille.\n\
+    Le type de clé indique le contenu du fichier clé : PUBLIC, PRIVATE ou MAIN. Seule une clé principale permet de vérifier la correspondance des exposants.\n"),
+                String::from("Vérification de clé")),
+                (String::from("audit"),
+                String::from("audit : Recherche les faiblesses connues de la clé publique : module trop petit, exposant privé trop petit (Wiener), facteurs trop proches (Fermat), empreinte ROCA.\n\
+    Le type de clé indique le contenu du fichier clé : PUBLIC, PRIVATE ou MAIN (seule la partie publique est auditée).\n\
+    Si un fichier en entrée (--in) est indiqué, il doit contenir une clé publique par ligne : les facteurs communs avec ces clés sont alors recherchés.\n"),
+                String::from("Audit de clé")),
+                (String::from("attack"),
+                String::from("attack : Tente de retrouver la clé privée d'une clé publique par les attaques de démonstration : rho et p - 1 de Pollard, Fermat, Wiener.\n\
+    Le type de clé indique le contenu du fichier clé : PUBLIC ou MAIN (seule la partie publique est attaquée). Seules les petites clés cèdent,\n\
+    par exemple celles générées avec --ks 4 --legacy. Si un fichier de sortie (--out) est indiqué, la clé principale retrouvée y est écrite,\n\
+    protégée par une phrase de passe (sauf si --nopass est indiqué). Un fichier existant n'est jamais écrasé.\n"),
+                String::from("Attaque de clé")),
+                (String::from("check-cert"),
+                String::from("check-cert : Vérifie les certificats de primalité contenus dans le fichier clé (un par ligne), tels qu'écrits par une génération avec --provable.\n"),
+                String::from("Vérification de certificats")),
+                (String::from("batch-gcd"),
+                String::from("batch-gcd : Recherche les clés publiques dont les modules partagent un facteur premier (PGCD par lots), puis retrouve les clés principales correspondantes.\n\
+    Le fichier clé (--kf) doit être un répertoire contenant une clé publique par fichier ; les fichiers illisibles sont ignorés.\n\
+    Si un répertoire de sortie (--out) est indiqué, chaque clé principale retrouvée y est écrite sous le nom de son fichier d'origine,\n\
+    protégée par une même phrase de passe (sauf si --nopass est indiqué). Un fichier existant n'est jamais écrasé : en particulier,\n\
+    les clés publiques ne sont pas remplacées si le répertoire de sortie est celui des clés.\n"),
+                String::from("PGCD par lots"))],
+            Self::ElGamal(_) => pair_oplist("Vérifie que le module est un nombre premier sûr, que le générateur et la valeur publique appartiennent au bon sous-groupe,\n\
+    ainsi que la taille du module."),
+            Self::Rabin(_) => pair_oplist("Vérifie que les facteurs sont premiers, congrus à 3 modulo 4 et que leur produit est le module, ainsi que la taille du module."),
+            Self::Shift(_) | Self::Vigenere(_) | Self::Affine(_) => crack_oplist(),
+            Self::Hill(_) => vec![],
+            Self::Dh(_) => dh_oplist(),
+            Self::X25519(_) => {
+                let mut ops = curve_oplist();
+                ops.push((String::from("dh-derive"),
+                String::from("dh-derive : Dérive le secret partagé entre la clé principale du fichier clé et la clé publique de l'autre partie écrite dans [input],\n\
+    après vérification de celle-ci. Le secret de --ks octets (32 par défaut), dérivé par HKDF-SHA256, est écrit en hexadécimal dans [output].\n"),
+                String::from("Dérivation de secret partagé")));
+                ops
+            },
+            Self::Ed25519(_) | Self::P256(_) => curve_oplist()
         }
     }
 
@@ -63,6 +1332,16 @@ impl GenEngine
                 println!("RSA");
                 let kpath = args.value_of("keyfile").unwrap();
                 println!("  Fichier clé : {}", kpath);
+                let rsa = &
+                    if args.is_present("legacy")
+                    {
+                        println!("  Politique de sécurité : paramètres hérités autorisés (--legacy)");
+                        Rsa::with_rng(rsa.rng()).with_policy(Policy::legacy())
+                    }
+                    else
+                    {
+                        Rsa::with_rng(rsa.rng()).with_policy(rsa.policy())
+                    };
                 match op
                 {
                     "gen" => {
@@ -84,7 +1363,21 @@ impl GenEngine
                             {
                                 rsa.def_gthreads()
                             };
+                        let rsa = &match args.value_of("primes").map(str::parse)
+                            {
+                                Some(Ok(nprimes)) => Rsa::with_rng(rsa.rng()).with_policy(rsa.policy()).with_primes(nprimes),
+                                Some(Err(_)) => {
+                                    eprintln!("-> Le nombre de facteurs premiers spécifié n'est pas valide.");
+                                    return;
+                                },
+                                None => Rsa::with_rng(rsa.rng()).with_policy(rsa.policy()).with_primes(rsa.primes())
+                            }.with_fips186(args.is_present("fips"));
                         println!("  Taille de clé : {}", ksize);
+                        println!("  Nombre de facteurs premiers : {}", rsa.primes());
+                        if rsa.fips186()
+                        {
+                            println!("  Mode FIPS 186-5 : oui (e = 65537, modules de 2048, 3072 ou 4096 bits, génération sur un seul thread)");
+                        }
                         println!("  Nombre de threads à utiliser : {}", nthreads);
                         println!();
 
@@ -105,81 +1398,70 @@ impl GenEngine
                             }
                         }
 
-                        println!("+ Génération d'une paire de clés RSA de taille {} octets, soit RSA-{} ({} threads)", ksize, ksize * 16, nthreads);
-                        let sw = Arc::from(Mutex::from(true));
-                        let sw_c = Arc::clone(&sw);
-                        let progress = thread::spawn(move || {
-                            let mut i = 0u8;
-                            while *sw_c.lock().as_deref().unwrap_or(&false)
+                        if args.is_present("provable")
+                        {
+                            // Les certificats contiennent les facteurs premiers : ils ne sont écrits en clair que si la clé l'est aussi
+                            if args.is_present("output") && !args.is_present("nopass")
                             {
-                                print!("\r");
-                                for _ in 0..i
-                                {
-                                    print!(".");
+                                eprintln!("-> Les certificats de primalité contiennent les facteurs premiers et ne peuvent pas être chiffrés : --out n'est accepté qu'avec --nopass.");
+                                return;
+                            }
+                            println!("+ Génération d'une paire de clés RSA de taille {} octets, soit RSA-{}, avec facteurs premiers prouvés", ksize, ksize * 16);
+                            let tpoint = time::Instant::now();
+                            let (k, certs) = match rsa.try_generate_provable(ksize)
+                            {
+                                Ok(k) => k,
+                                Err(e) => {
+                                    eprintln!("-> Impossible de générer la clé : {}", e);
+                                    return;
                                 }
-                                print!(" {}  ",
-                                    match i % 4
-                                    {
-                                        0 => "|",
-                                        1 => "/",
-                                        2 => "—",
-                                        3 => "\\",
-                                        _ => ""
-                                    }
-                                );
-                                i = 
-                                    if i >= 50
-                                    {
-                                        print!("\r");
-                                        for _ in 0..i
-                                        {
-                                            print!(" ");
-                                        }
-                                        print!("  ");
-                                        0
-                                    }
-                                    else
-                                    {
-                                        i + 1
-                                    };
-                                std::io::stdout().flush().expect("?");
-                                thread::sleep(time::Duration::from_millis(100));
+                            };
+
+                            write(kpath, store_rsa_main(&k, args.is_present("nopass"))).expect("-> Impossible d'écrire la clé dans le fichier clé.");
+                            println!("+> Clé générée et écrite avec succès en {} secondes.", tpoint.elapsed().as_secs());
+                            if let Some(cpath) = args.value_of("output")
+                            {
+                                let certs: Vec<String> = certs.iter().map(Key::serialize_str).collect();
+                                write(cpath, certs.join("\n")).expect("-> Impossible d'écrire les certificats dans le fichier de sortie.");
+                                println!("+> Certificats de primalité écrits en clair dans {} : ils contiennent les facteurs premiers et doivent rester secrets.", cpath);
                             }
-                            print!("\r");
-                            for _ in 0..i
+                            return;
+                        }
+
+                        println!("+ Génération d'une paire de clés RSA de taille {} octets, soit RSA-{} ({} threads)", ksize, ksize * 16, nthreads);
+                        let control = match gen_control(args)
                             {
-                                print!(" ");
+                                Some(control) => control,
+                                None => return
+                            };
+                        let progress = GenProgress::new(rsa.primes());
+                        let k = rsa.try_generate_controlled(ksize, nthreads, &control.with_observer(&progress));
+                        progress.clear();
+                        let (k, stats) = match k
+                        {
+                            Ok(k) => k,
+                            Err(e) => {
+                                eprintln!("-> Impossible de générer la clé : {}", e);
+                                return;
                             }
-                            print!("  \r");
-                        });
-                        let tpoint = time::Instant::now();
-                        let k = rsa.generate(ksize, nthreads);
-                        *sw.lock().as_deref_mut().unwrap_or(&mut true) = false;
-                        progress.join().expect("");
+                        };
 
-                        write(kpath, k.serialize_str()).expect("-> Impossible d'écrire la clé dans le fichier clé.");
-                        println!("+> Clé générée et écrite avec succès en {} secondes.", tpoint.elapsed().as_secs());
+                        write(kpath, store_rsa_main(&k, args.is_present("nopass"))).expect("-> Impossible d'écrire la clé dans le fichier clé.");
+                        println!("+> Clé générée et écrite avec succès en {} secondes.", stats.elapsed.as_secs());
+                        println!("   {} entiers candidats testés", stats.candidates);
+                        for (i, t) in stats.prime_times.iter().enumerate()
+                        {
+                            println!("   Facteur premier n°{} trouvé en {} ms", i + 1, t.as_millis());
+                        }
                     },
                     "encrypt" | "decrypt" | "sign" | "verify" => {
                         let kt = args.value_of("keytype").unwrap().to_lowercase();
                         let rk = read_to_string(kpath).expect("-> Impossible de lire le fichier clé.");
-                        let (puk, prk) = 
-                            match kt.as_str()
+                        let (puk, prk) =
+                            match load_rsa(&kt, rk)
                             {
-                                "main" | "pair" => {
-                                    let rk = RsaKey::from_str(String::from(rk)).expect("-> Impossible de charger la clé.");
-                                    (Some(rk.0), Some(rk.1))
-                                },
-                                "public" | "publ" => {
-                                    (Some(PublicKey::from_str(String::from(rk)).expect("-> Impossible de charger la clé.")), None)
-                                },
-                                "private" | "priv" => {
-                                    (None, Some(PrivateKey::from_str(String::from(rk)).expect("-> Impossible de charger la clé.")))
-                                },
-                                _ => {
-                                    eprintln!("-> Type de clé invalide : {}.", kt);
-                                    return;
-                                }
+                                Some(rk) if rk.check() => rk.split(),
+                                _ => return
                             };
 
                         let msg = read_to_string(args.value_of("input").unwrap()).expect("-> Impossible de lire le fichier en entrée.");
@@ -193,18 +1475,34 @@ impl GenEngine
                         match op
                         {
                             "encrypt" | "sign" => {
-                                let mut msg = if args.is_present("raw") { Message::parts_str(msg, false).build() } else { Message::str(msg).build() };
+                                let msg = if args.is_present("raw") { Message::try_parts_str(msg, false).and_then(| b | b.try_build()) } else { Message::str(msg).try_build() };
+                                let mut msg = match msg
+                                {
+                                    Ok(msg) => msg,
+                                    Err(e) => {
+                                        eprintln!("-> Impossible de lire le message : {}", e);
+                                        return;
+                                    }
+                                };
 
                                 match op
                                 {
                                     "encrypt" => {
-                                        rsa.encrypt(&mut msg, &puk.expect("-> Impossible d'encrypter sans clé publique."));
+                                        if let Err(e) = rsa.try_encrypt(&mut msg, &puk.expect("-> Impossible d'encrypter sans clé publique."))
+                                        {
+                                            eprintln!("-> Impossible de chiffrer le message : {}", e);
+                                            return;
+                                        }
                                 
                                         write(outpath, msg.to_parts_str()).expect("-> Impossible d'écrire dans le fichier de sortie.");
                                         println!("+> Message chiffré et écrit avec succès.");
                                     },
                                     "sign" => {
-                                        rsa.encrypt(&mut msg, &prk.expect("-> Impossible de signer sans clé privée."));
+                                        if let Err(e) = rsa.try_sign(&mut msg, &prk.expect("-> Impossible de signer sans clé privée."))
+                                        {
+                                            eprintln!("-> Impossible de signer le message : {}", e);
+                                            return;
+                                        }
 
                                         write(outpath, msg.to_parts_str()).expect("-> Impossible d'écrire dans le fichier de sortie.");
                                         println!("+> Message signé et écrit avec succès.");
@@ -213,19 +1511,34 @@ impl GenEngine
                                 }
                             },
                             "decrypt" | "verify" => {
-                                let mut msg = Message::parts_str(msg, true).build();
+                                let mut msg = match Message::try_parts_str(msg, true).and_then(| b | b.try_build())
+                                {
+                                    Ok(msg) => msg,
+                                    Err(e) => {
+                                        eprintln!("-> Impossible de lire le message chiffré : {}", e);
+                                        return;
+                                    }
+                                };
 
                                 match op
                                 {
                                     "decrypt" => {
-                                        rsa.decrypt(&mut msg, &prk.expect("-> Impossible de décrypter sans clé privée."));
+                                        if let Err(e) = rsa.try_decrypt(&mut msg, &prk.expect("-> Impossible de décrypter sans clé privée."))
+                                        {
+                                            eprintln!("-> Impossible de déchiffrer le message : {}", e);
+                                            return;
+                                        }
 
                                         let contents = if args.is_present("raw") { msg.to_parts_str() } else { msg.to_str().expect("-> Impossible de convertir le message.") };
                                         write(outpath, contents).expect("-> Impossible d'écrire dans le fichier de sortie.");
                                         println!("+> Message déchiffré et écrit avec succès.");
                                     },
                                     "verify" => {
-                                        rsa.decrypt(&mut msg, &puk.expect("-> Impossible de vérifier sans clé publique."));
+                                        if let Err(e) = rsa.try_verify(&mut msg, &puk.expect("-> Impossible de vérifier sans clé publique."))
+                                        {
+                                            eprintln!("-> Impossible de vérifier le message : {}", e);
+                                            return;
+                                        }
 
                                         let contents = if args.is_present("raw") { msg.to_parts_str() } else { msg.to_str().expect("-> Impossible de convertir le message.") };
                                         write(outpath, contents).expect("-> Impossible d'écrire dans le fichier de sortie.");
@@ -239,10 +1552,247 @@ impl GenEngine
                             }
                         }
                     },
+                    "check-key" => {
+                        let kt = args.value_of("keytype").unwrap().to_lowercase();
+                        let rk = read_to_string(kpath).expect("-> Impossible de lire le fichier clé.");
+                        println!("  Type de clé : {}", kt);
+                        println!();
+
+                        let rk = match load_rsa(&kt, rk)
+                        {
+                            Some(rk) => rk,
+                            None => return
+                        };
+                        let (puk, prk) = match &rk
+                        {
+                            LoadedRsaKey::Main(k) => (Some(&k.0), Some(&k.1)),
+                            LoadedRsaKey::Public(k) => (Some(k), None),
+                            LoadedRsaKey::Private(k) => (None, Some(k))
+                        };
+                        if let Some(n) = puk.map(| k | k.0.value()).or(prk.map(| k | k.0.value()))
+                        {
+                            println!("  Taille du module : {} bits", n.bits());
+                        }
+                        if let Some(k) = puk
+                        {
+                            println!("  Exposant public : {}", k.1.value());
+                        }
+                        if let Some(k) = prk.filter(| k | !k.2.is_empty())
+                        {
+                            println!("  Facteurs premiers : {}", k.2.len());
+                        }
+
+                        if rk.check()
+                        {
+                            println!("+> Clé cohérente.");
+                        }
+                    },
+                    "check-cert" => {
+                        let contents = read_to_string(kpath).expect("-> Impossible de lire le fichier de certificats.");
+                        println!();
+
+                        let mut valid = true;
+                        for (i, line) in contents.lines().map(str::trim).filter(| l | !l.is_empty()).enumerate()
+                        {
+                            match provable::Certificate::from_str(String::from(line)).and_then(| c | c.verify())
+                            {
+                                Ok(p) => println!("  Certificat n°{} : nombre premier de {} bits prouvé", i, p.bits()),
+                                Err(e) => {
+                                    eprintln!("- Certificat n°{} invalide : {}", i, e);
+                                    valid = false;
+                                }
+                            }
+                        }
+
+                        if valid
+                        {
+                            println!("+> Tous les certificats sont valides.");
+                        }
+                        else
+                        {
+                            eprintln!("-> Au moins un certificat est invalide.");
+                        }
+                    },
+                    "audit" => {
+                        let kt = args.value_of("keytype").unwrap().to_lowercase();
+                        let rk = read_to_string(kpath).expect("-> Impossible de lire le fichier clé.");
+                        println!("  Type de clé : {}", kt);
+
+                        let puk = match load_rsa(&kt, rk).map(LoadedRsaKey::split)
+                        {
+                            Some((Some(puk), _)) => puk,
+                            Some((None, _)) => {
+                                eprintln!("-> L'audit porte sur la clé publique : le type de clé doit être PUBLIC ou MAIN.");
+                                return;
+                            },
+                            None => return
+                        };
+                        let mut corpus = Vec::new();
+                        if let Some(cpath) = args.value_of("input")
+                        {
+                            println!("  Corpus de clés publiques : {}", cpath);
+                            let contents = read_to_string(cpath).expect("-> Impossible de lire le fichier en entrée.");
+                            for (i, line) in contents.lines().map(str::trim).filter(| l | !l.is_empty()).enumerate()
+                            {
+                                match PublicKey::from_str(String::from(line))
+                                {
+                                    Ok(k) => corpus.push(k),
+                                    Err(e) => eprintln!("- Clé n°{} du corpus ignorée : {}", i, e)
+                                }
+                            }
+                            println!("  Nombre de clés du corpus : {}", corpus.len());
+                        }
+                        println!();
+
+                        let report = match audit::audit(&puk, &corpus)
+                        {
+                            Ok(r) => r,
+                            Err(e) => {
+                                eprintln!("-> Impossible d'auditer la clé : {}", e);
+                                return;
+                            }
+                        };
+                        println!("  Taille du module : {} bits", report.modulus_bits);
+                        for finding in &report.findings
+                        {
+                            println!("  {} {}", if finding.is_critical() { "[CRITIQUE]" } else { "[ATTENTION]" }, finding);
+                        }
+
+                        if report.is_clean()
+                        {
+                            println!("+> Aucune faiblesse détectée.");
+                        }
+                        else if report.is_critical()
+                        {
+                            println!("-> Clé compromise : {} faiblesse(s) détectée(s), dont au moins une expose la clé privée.", report.findings.len());
+                        }
+                        else
+                        {
+                            println!("- {} faiblesse(s) détectée(s).", report.findings.len());
+                        }
+                    },
+                    "attack" => {
+                        let kt = args.value_of("keytype").unwrap().to_lowercase();
+                        let rk = read_to_string(kpath).expect("-> Impossible de lire le fichier clé.");
+                        println!("  Type de clé : {}", kt);
+
+                        let puk = match load_rsa(&kt, rk).map(LoadedRsaKey::split)
+                        {
+                            Some((Some(puk), _)) => puk,
+                            Some((None, _)) => {
+                                eprintln!("-> L'attaque porte sur la clé publique : le type de clé doit être PUBLIC ou MAIN.");
+                                return;
+                            },
+                            None => return
+                        };
+                        println!("  Taille du module : {} bits", puk.0.value().bits());
+                        println!();
+
+                        let mut recovered = None;
+                        for attack in Attack::all().iter()
+                        {
+                            let tpoint = time::Instant::now();
+                            match attack.run(&puk)
+                            {
+                                Ok(Some(k)) => {
+                                    println!("  [RÉUSSIE] {} : clé privée retrouvée en {} ms", attack, tpoint.elapsed().as_millis());
+                                    recovered.get_or_insert(k);
+                                },
+                                Ok(None) => println!("  [ÉCHEC] {} : abandon après {} ms", attack, tpoint.elapsed().as_millis()),
+                                Err(e) => {
+                                    eprintln!("-> Impossible d'attaquer la clé : {}", e);
+                                    return;
+                                }
+                            }
+                        }
+
+                        match (recovered, args.value_of("output"))
+                        {
+                            (Some(_), Some(outpath)) if Path::new(outpath).exists() => {
+                                println!("-> Clé compromise : la clé principale a été retrouvée, mais le fichier {} existe déjà et n'est pas écrasé.", outpath);
+                            },
+                            (Some(k), Some(outpath)) => {
+                                write(outpath, store_rsa_main(&k, args.is_present("nopass"))).expect("-> Impossible d'écrire la clé retrouvée.");
+                                println!("-> Clé compromise : clé principale retrouvée et écrite dans {}.", outpath);
+                            },
+                            (Some(_), None) => println!("-> Clé compromise : la clé principale a été retrouvée."),
+                            (None, _) => println!("+> Aucune attaque n'a abouti.")
+                        }
+                    },
+                    "batch-gcd" => {
+                        let entries = match read_dir(kpath)
+                        {
+                            Ok(entries) => entries,
+                            Err(e) => {
+                                eprintln!("-> Impossible de lire le répertoire de clés : {}", e);
+                                return;
+                            }
+                        };
+                        let mut paths: Vec<_> = entries.filter_map(| e | e.ok()).map(| e | e.path()).filter(| p | p.is_file()).collect();
+                        paths.sort();
+                        let (mut names, mut keys) = (Vec::new(), Vec::new());
+                        for path in paths
+                        {
+                            match read_to_string(&path).map(| c | PublicKey::from_str(String::from(c.trim())))
+                            {
+                                Ok(Ok(k)) => {
+                                    names.push(path.file_name().unwrap_or_default().to_string_lossy().into_owned());
+                                    keys.push(k);
+                                },
+                                _ => eprintln!("- Fichier ignoré (clé publique illisible) : {}", path.display())
+                            }
+                        }
+                        println!("  Nombre de clés publiques : {}", keys.len());
+                        println!();
+
+                        let tpoint = time::Instant::now();
+                        let affected = audit::recover_shared(&keys);
+                        println!("+ Calcul terminé en {} secondes.", tpoint.elapsed().as_secs());
+                        if affected.is_empty()
+                        {
+                            println!("+> Aucun facteur commun trouvé.");
+                            return;
+                        }
+
+                        let outdir = args.value_of("output").map(Path::new);
+                        let pass = match outdir
+                        {
+                            Some(dir) if !args.is_present("nopass") && affected.iter().any(| (i, k) | k.is_some() && !dir.join(&names[*i]).exists()) => {
+                                println!("  Choisissez une phrase de passe pour protéger les clés retrouvées.");
+                                Some(ask_passphrase(true))
+                            },
+                            _ => None
+                        };
+                        for (i, k) in &affected
+                        {
+                            match (k, outdir)
+                            {
+                                (Some(_), Some(dir)) if dir.join(&names[*i]).exists() => {
+                                    println!("  [CRITIQUE] {} : facteur commun, clé principale retrouvée mais non écrite ({} existe déjà).", names[*i], dir.join(&names[*i]).display());
+                                },
+                                (Some(k), Some(dir)) => {
+                                    let contents = match &pass
+                                    {
+                                        Some(pass) => storage::encrypt_key(k, pass, storage::PBKDF2_ITER_DEF).expect("-> Impossible de chiffrer la clé."),
+                                        None => k.serialize_str()
+                                    };
+                                    write(dir.join(&names[*i]), contents).expect("-> Impossible d'écrire la clé retrouvée.");
+                                    println!("  [CRITIQUE] {} : facteur commun, clé principale retrouvée et écrite.", names[*i]);
+                                },
+                                (Some(_), None) => println!("  [CRITIQUE] {} : facteur commun, clé principale retrouvée.", names[*i]),
+                                (None, _) => println!("  [CRITIQUE] {} : module partagé à l'identique avec une autre clé.", names[*i])
+                            }
+                        }
+                        println!("-> {} clé(s) compromise(s) sur {}.", affected.len(), keys.len());
+                    },
                     "export" => {
                         let kt = args.value_of("keytype").unwrap().to_lowercase();
                         let rk = read_to_string(kpath).expect("-> Impossible de lire le fichier clé.");
-                        let rk = RsaKey::from_str(String::from(rk)).expect("-> Impossible de charger la clé.");
+                        let rk = load_rsa_main(rk);
+                        if !check_key(&rk)
+                        {
+                            return;
+                        }
 
                         if !args.is_present("output")
                         {
@@ -254,8 +1804,9 @@ impl GenEngine
                             match kt.as_str()
                             {
                                 "public" | "publ" => (rk.0.serialize_str(), "publique"),
-                                "private" | "priv" => (rk.1.serialize_str(), "privée"),
-                                "main" | "pair" | "all" | "any" => (rk.serialize_str(), "principale"),
+                                // La clé privée seule ne peut pas être chiffrée au format PKCS#8 sans l'exposant public : la paire complète est donc stockée dans ce cas
+                                "private" | "priv" => (if args.is_present("nopass") { rk.1.serialize_str() } else { store_rsa_main(&rk, false) }, "privée"),
+                                "main" | "pair" | "all" | "any" => (store_rsa_main(&rk, args.is_present("nopass")), "principale"),
                                 _ => {
                                     println!("-> Type de clé à exporter {} non reconnu. Liste : public, private, main (copie).", kt);
                                     return;
@@ -266,6 +1817,40 @@ impl GenEngine
                     }
                     _ => ()
                 }
+            },
+            Self::ElGamal(elgamal) => pair_op(elgamal, op, args),
+            Self::Rabin(rabin) => pair_op(rabin, op, args),
+            Self::Shift(shift) => classical_op(shift, op, args),
+            Self::Vigenere(vigenere) => classical_op(vigenere, op, args),
+            Self::Affine(affine) => classical_op(affine, op, args),
+            Self::Hill(hill) => classical_op(hill, op, args),
+            Self::Dh(dh) => dh_op(dh, op, args),
+            Self::X25519(x25519) => {
+                println!("{}", X25519::NAME);
+                println!("  Fichier clé : {}", args.value_of("keyfile").unwrap());
+                match op
+                {
+                    "dh-derive" => x25519_derive(x25519, args),
+                    _ => curve_op(x25519, op, args)
+                }
+            },
+            Self::Ed25519(ed25519) => {
+                println!("{}", Ed25519::NAME);
+                println!("  Fichier clé : {}", args.value_of("keyfile").unwrap());
+                match op
+                {
+                    "sign" | "verify" => detached_sign_op(ed25519, op, args),
+                    _ => curve_op(ed25519, op, args)
+                }
+            },
+            Self::P256(p256) => {
+                println!("{}", P256::NAME);
+                println!("  Fichier clé : {}", args.value_of("keyfile").unwrap());
+                match op
+                {
+                    "sign" | "verify" => detached_sign_op(p256, op, args),
+                    _ => curve_op(p256, op, args)
+                }
             }
         }
     }
@@ -274,7 +1859,17 @@ impl GenEngine
     {
         match engine 
         {
-            "rsa" => Some(GenEngine::Rsa(Rsa {})),
+            "rsa" => Some(GenEngine::Rsa(Rsa::new())),
+            "elgamal" => Some(GenEngine::ElGamal(ElGamal::new())),
+            "rabin" => Some(GenEngine::Rabin(Rabin::new())),
+            "shift" => Some(GenEngine::Shift(Shift::new())),
+            "vigenere" => Some(GenEngine::Vigenere(Vigenere::new())),
+            "affine" => Some(GenEngine::Affine(Affine::new())),
+            "hill" => Some(GenEngine::Hill(Hill::new())),
+            "dh" => Some(GenEngine::Dh(Dh::new())),
+            "x25519" => Some(GenEngine::X25519(X25519::new())),
+            "ed25519" => Some(GenEngine::Ed25519(Ed25519::new())),
+            "p256" => Some(GenEngine::P256(P256::new())),
             _ => None,
         }
     }